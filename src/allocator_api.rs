@@ -0,0 +1,45 @@
+//! Functions which require the unstable `allocator_api` feature.
+
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::fmt::Formatter;
+use crate::{CombArc, CombRc};
+
+/// The error returned when allocating a new `CombArc` or `CombRc` fails.
+///
+/// This wraps the standard library's unstable `AllocError` so that it does not need to be named
+/// directly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllocError;
+
+impl From<alloc::alloc::AllocError> for AllocError {
+    fn from(_: alloc::alloc::AllocError) -> Self {
+        AllocError
+    }
+}
+
+impl core::fmt::Display for AllocError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl<T: Clone> CombArc<T> {
+    /// Constructs a `CombArc`, returning an error if the allocation fails.
+    ///
+    /// This just calls [`Arc::try_new`].
+    #[inline]
+    pub fn try_new(what: T) -> Result<CombArc<T>, AllocError> {
+        Ok(CombArc::from_arc(Arc::try_new(what)?))
+    }
+}
+
+impl<T: Clone> CombRc<T> {
+    /// Constructs a `CombRc`, returning an error if the allocation fails.
+    ///
+    /// This just calls [`Rc::try_new`].
+    #[inline]
+    pub fn try_new(what: T) -> Result<CombRc<T>, AllocError> {
+        Ok(CombRc::from_rc(Rc::try_new(what)?))
+    }
+}
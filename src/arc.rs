@@ -1,110 +1,1587 @@
-use alloc::borrow::ToOwned;
-use alloc::sync::Arc;
+#[cfg(feature = "allocator_api")]
+use alloc::alloc::{Allocator, Global};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::any::{Any, TypeId};
 use core::cmp::Ordering;
 use core::fmt::Formatter;
+use core::iter::{FromIterator, Product, Sum};
+use core::marker::PhantomData;
+use crate::{CloneOnWrite, CombArcRef, CombRc, CombArcTransaction, CombArcWriteGuard, CombWeak, CowPolicy, FrozenCombArc, TryClone, UniqueCombArc};
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
 
 /// A wrapper around an [`Arc`] that clones when mutably borrowed if it is not unique.
 ///
 /// Like [`Arc`], this value is thread-safe.
 ///
+/// What happens when a shared `CombArc` is mutably borrowed is decided by the [`CowPolicy`] `P`. By
+/// default, it is [`CloneOnWrite`], which just clones.
+///
 /// # Examples
 ///
 /// See the crate documentation for examples.
-#[derive(Clone, Default, Debug, Ord, PartialOrd, Eq, PartialEq)]
-#[repr(transparent)]
-pub struct CombArc<T: Clone> {
-    inner: Arc<T>
+#[cfg_attr(not(feature = "debug_hooks"), repr(transparent))]
+pub struct CombArc<T: Clone, P: CowPolicy = CloneOnWrite, #[cfg(feature = "allocator_api")] A: Allocator = Global> {
+    #[cfg(not(feature = "allocator_api"))]
+    inner: Arc<T>,
+    #[cfg(feature = "allocator_api")]
+    inner: Arc<T, A>,
+    policy: PhantomData<P>,
+    #[cfg(feature = "debug_hooks")]
+    budget: crate::debug_hooks::BudgetSlot
 }
 
 impl<T: Clone> CombArc<T> {
     /// Constructs a `CombArc`.
     #[inline]
     pub fn new(what: T) -> CombArc<T> {
+        Self::from_arc(Arc::new(what))
+    }
+
+    /// Constructs a `CombArc` with a clone of a value.
+    ///
+    /// This is the same as converting from `&T`.
+    #[inline]
+    pub fn from_ref(what: &T) -> CombArc<T> {
+        Self::new(what.clone())
+    }
+
+    /// Constructs a `CombArc` which holds a weak reference to itself.
+    ///
+    /// This just calls [`Arc::new_cyclic`], so the weak reference cannot be upgraded until this
+    /// function returns.
+    ///
+    /// Keep in mind that mutably borrowing affects the weak reference like any other:
+    /// * If the `CombArc` is shared, the clone will contain a weak reference to the original
+    ///   allocation, not to itself.
+    /// * If the `CombArc` is unique, the value is moved to a new allocation and the weak reference
+    ///   will no longer upgrade.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    /// use std::sync::{Arc, Weak};
+    ///
+    /// #[derive(Clone)]
+    /// struct Node {
+    ///     this: Weak<Node>,
+    ///     value: u32
+    /// }
+    ///
+    /// let mut node = CombArc::new_cyclic(|this| Node { this: this.clone(), value: 1 });
+    /// let original = node.clone();
+    /// assert!(Arc::ptr_eq(&node.this.upgrade().unwrap(), CombArc::get_arc(&node)));
+    ///
+    /// // `node` is shared, so it gets cloned, but its weak reference still points to `original`.
+    /// node.value = 2;
+    /// assert!(Arc::ptr_eq(&node.this.upgrade().unwrap(), CombArc::get_arc(&original)));
+    /// ```
+    #[inline]
+    pub fn new_cyclic<F: FnOnce(&Weak<T>) -> T>(data_fn: F) -> CombArc<T> {
+        Self::from_arc(Arc::new_cyclic(data_fn))
+    }
+
+    /// Get a `CombArc` of the default value which is shared by every call with the same type.
+    ///
+    /// The default value is constructed on the first call and is never dropped. Since every result
+    /// shares one allocation, the first mutable borrow of each one will clone it, as with any other
+    /// shared `CombArc`. This is useful if the default value is expensive to construct, such as a
+    /// large lookup table.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    ///
+    /// let mut a = CombArc::<Vec<u32>>::default_shared();
+    /// let b = CombArc::<Vec<u32>>::default_shared();
+    /// assert!(CombArc::ptr_eq(&a, &b));
+    ///
+    /// a.push(1);
+    /// assert!(!CombArc::ptr_eq(&a, &b));
+    /// assert!(b.is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn default_shared() -> CombArc<T> where T: Default + Send + Sync + 'static {
+        static DEFAULTS: Mutex<BTreeMap<TypeId, Box<dyn Any + Send + Sync>>> = Mutex::new(BTreeMap::new());
+
+        // The lock is not held while constructing the default value, since it may call this too.
+        let lookup = || DEFAULTS.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&TypeId::of::<T>())
+            .and_then(|d| d.downcast_ref::<Arc<T>>())
+            .cloned();
+
+        let inner = lookup().unwrap_or_else(|| {
+            let value = Arc::new(T::default());
+            DEFAULTS.lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Box::new(value))
+                .downcast_ref::<Arc<T>>()
+                .expect("default values should be stored by their type")
+                .clone()
+        });
+
+        Self::from_arc(inner)
+    }
+
+    /// Constructs a `CombArc` which panics if mutably borrowing it and its clones makes more than
+    /// `budget` copy-on-write clones.
+    ///
+    /// The budget is shared with every clone of this reference, and is kept after a copy-on-write
+    /// clone, so it catches references which are unexpectedly shared and then mutated. Use
+    /// [`CombArc::try_make_mut_within_budget`] to get an error instead of panicking, and
+    /// [`CombArc::clones_used`] to see how much of the budget is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    ///
+    /// let mut frame = CombArc::with_clone_budget(vec![0u8; 16], 1);
+    /// let snapshot = frame.clone();
+    ///
+    /// frame[0] = 1; // clones, using up the budget
+    /// assert_eq!(CombArc::clones_used(&frame), Some(1));
+    ///
+    /// let another_snapshot = frame.clone();
+    /// assert!(CombArc::try_make_mut_within_budget(&mut frame).is_err());
+    /// ```
+    #[cfg(feature = "debug_hooks")]
+    pub fn with_clone_budget(what: T, budget: usize) -> CombArc<T> {
         Self {
-            inner: Arc::new(what)
+            inner: Arc::new(what),
+            policy: PhantomData,
+            budget: crate::debug_hooks::BudgetSlot::new(budget)
         }
     }
 
     /// Constructs a `CombArc` from an already created `Arc`.
     #[inline]
     pub fn from_arc(what: Arc<T>) -> CombArc<T> {
+        Self::from_inner(what)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, A: Allocator + Clone> CombArc<T, CloneOnWrite, A> {
+    /// Constructs a `CombArc` in the given allocator.
+    ///
+    /// Copy-on-write clones are allocated with a clone of the same allocator. Only [`Clone`],
+    /// [`Deref`], [`DerefMut`], and [`CombArc::allocator`] are available for allocators other than
+    /// [`Global`] so far.
+    ///
+    /// This just calls [`Arc::new_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    /// use combarc::CombArc;
+    /// use std::alloc::{AllocError, Allocator, Global, Layout};
+    /// use std::ptr::NonNull;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Counting(Arc<AtomicUsize>);
+    ///
+    /// unsafe impl Allocator for Counting {
+    ///     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///         Global.allocate(layout)
+    ///     }
+    ///
+    ///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    ///         unsafe { Global.deallocate(ptr, layout) }
+    ///     }
+    /// }
+    ///
+    /// let allocator = Counting::default();
+    /// let mut my_value = CombArc::new_in(1u32, allocator.clone());
+    /// let another_value = my_value.clone();
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 1);
+    ///
+    /// *my_value += 1; // clones into `allocator`
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 2);
+    /// assert_eq!((*my_value, *another_value), (2, 1));
+    /// ```
+    ///
+    /// [`Deref`]: core::ops::Deref
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn new_in(what: T, alloc: A) -> CombArc<T, CloneOnWrite, A> {
+        Self {
+            inner: Arc::new_in(what, alloc),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> CombArc<T, P, A> {
+    /// Get a reference to the allocator of the inner value.
+    ///
+    /// This just calls [`Arc::allocator`].
+    #[inline]
+    pub fn allocator(what: &CombArc<T, P, A>) -> &A {
+        Arc::allocator(&what.inner)
+    }
+
+    /// [`CombArc::make_mut`] for any allocator.
+    fn make_mut_in(what: &mut CombArc<T, P, A>) -> &mut T {
+        let shared = Arc::strong_count(&what.inner) != 1;
+        if P::INTERCEPTS_CLONES && shared {
+            P::before_clone::<T>();
+        }
+        #[cfg(feature = "debug_hooks")]
+        if shared {
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+        }
+        Arc::make_mut(&mut what.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombArc<T, P> {
+    fn from_inner(inner: Arc<T>) -> CombArc<T, P> {
         Self {
-            inner: what
+            inner,
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: Default::default()
+        }
+    }
+
+    /// Convert this reference to one with a different [`CowPolicy`].
+    ///
+    /// This does not clone or allocate, so the new reference still shares the inner value with any
+    /// other references.
+    #[inline]
+    pub fn with_policy<Q: CowPolicy>(what: CombArc<T, P>) -> CombArc<T, Q> {
+        CombArc {
+            inner: what.inner,
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: what.budget
         }
     }
 
     /// Clones the inner value stored in the `CombArc`, returning a unique clone of it.
     #[inline]
-    pub fn clone_unique(what: &CombArc<T>) -> CombArc<T> {
-        Self::new(what.inner.as_ref().clone())
+    pub fn clone_unique(what: &CombArc<T, P>) -> CombArc<T, P> {
+        Self::from_inner(Arc::new(what.inner.as_ref().clone()))
     }
 
     /// Attempt to get the inner value inside of the `CombArc`.
     ///
     /// If this is a unique reference, the inner value will be moved. Otherwise, the reference will
     /// be re-returned.
+    ///
+    /// There is no `TryFrom<CombArc<T>>` implementation for `T`, since the orphan rules do not allow
+    /// it.
+    #[inline]
+    pub fn try_unwrap(what: CombArc<T, P>) -> Result<T, Self> {
+        Arc::try_unwrap(what.inner).map_err(Self::from_inner)
+    }
+
+    /// Get the inner value inside of the `CombArc` if this is a unique reference.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, the reference will
+    /// be dropped and `None` is returned.
+    ///
+    /// Unlike [`CombArc::try_unwrap`], this does not give the reference back on failure. As such,
+    /// if every `CombArc` is consumed with this function, even across threads, exactly one of them
+    /// is guaranteed to get the inner value. Unlike [`CombArc::make_inner`], this never clones.
+    ///
+    /// This just calls [`Arc::into_inner`] on the inner value.
     #[inline]
-    pub fn try_unwrap(what: CombArc<T>) -> Result<T, Self> {
-        Arc::try_unwrap(what.inner).map_err(Self::from_arc)
+    pub fn into_inner(what: CombArc<T, P>) -> Option<T> {
+        Arc::into_inner(what.inner)
     }
 
     /// Try to get the inner value inside of the `CombArc` or clone otherwise.
     ///
     /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
     #[inline]
-    pub fn make_inner(what: CombArc<T>) -> T {
+    pub fn make_inner(what: CombArc<T, P>) -> T {
         Arc::try_unwrap(what.inner).unwrap_or_else(|e| T::to_owned(e.as_ref()))
     }
 
+    /// Move the inner value into a new [`Box`].
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    ///
+    /// There is no `From<CombArc<T>>` implementation for `Box<T>`, since the orphan rules do not
+    /// allow it.
+    #[inline]
+    pub fn into_box(what: CombArc<T, P>) -> Box<T> {
+        Box::new(Self::make_inner(what))
+    }
+
+    /// Borrow the inner value as a [`Cow`], without cloning it.
+    ///
+    /// This is the same as converting from `&CombArc<T>`.
+    #[inline]
+    pub fn to_cow(what: &CombArc<T, P>) -> Cow<'_, T> {
+        Cow::Borrowed(&what.inner)
+    }
+
+    /// Get the inner value as an owned [`Cow`].
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    #[inline]
+    pub fn into_cow(what: CombArc<T, P>) -> Cow<'static, T> {
+        Cow::Owned(Self::make_inner(what))
+    }
+
+    /// Move the inner value into a new [`Box`] and leak it, returning a mutable reference to it
+    /// which lives for the rest of the program.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned,
+    /// and any other references are unaffected.
+    ///
+    /// This always allocates, and the memory is intentionally never freed, so it should only be
+    /// used for values which are needed until the program exits, such as configuration.
+    #[inline]
+    pub fn leak_inner(what: CombArc<T, P>) -> &'static mut T where T: 'static {
+        Box::leak(Self::into_box(what))
+    }
+
+    /// Move the inner value into a new [`Box`] and leak it, returning a reference to it which lives
+    /// for the rest of the program.
+    ///
+    /// This is the same as [`CombArc::leak_inner`], except the returned reference is immutable.
+    #[inline]
+    pub fn leak(what: CombArc<T, P>) -> &'static T where T: 'static {
+        Self::leak_inner(what)
+    }
+
+    /// Convert this into a [`FrozenCombArc`], which cannot be mutably borrowed.
+    ///
+    /// This does not clone, so it still shares the inner value with any other references.
+    #[inline]
+    pub fn freeze(what: CombArc<T, P>) -> FrozenCombArc<T> {
+        FrozenCombArc::from_arc(what.inner)
+    }
+
+    /// Convert this into a [`UniqueCombArc`], which can be mutably borrowed without checking whether it
+    /// is shared.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    #[inline]
+    pub fn into_unique(what: CombArc<T, P>) -> UniqueCombArc<T, P> {
+        UniqueCombArc::from_inner(Self::make_inner(what))
+    }
+
+    /// Make equal values in a slice share one allocation, returning how many allocations were
+    /// eliminated.
+    ///
+    /// For each set of equal values, every reference is pointed to the allocation of the first one
+    /// in the slice. References which already point to it are not touched, and no value is ever
+    /// mutated or cloned. Allocations which are no longer referenced are dropped, along with any
+    /// weak references to them.
+    ///
+    /// The returned count is the number of distinct allocations referenced by the slice before,
+    /// minus the number referenced after.
+    #[cfg(feature = "std")]
+    pub fn unify(handles: &mut [CombArc<T, P>]) -> usize where T: Eq + core::hash::Hash {
+        let allocations_before: HashSet<*const T> = handles.iter().map(Self::as_ptr).collect();
+
+        let mut representatives: HashMap<&T, usize> = HashMap::with_capacity(handles.len());
+        let indices: Vec<usize> = handles.iter()
+            .enumerate()
+            .map(|(index, handle)| *representatives.entry(handle.inner.as_ref()).or_insert(index))
+            .collect();
+        let allocations_after = representatives.len();
+
+        for (index, representative) in indices.into_iter().enumerate() {
+            if !Self::ptr_eq(&handles[index], &handles[representative]) {
+                handles[index] = handles[representative].clone();
+            }
+        }
+
+        allocations_before.len() - allocations_after
+    }
+
     /// Get the inner `Arc` value.
     #[inline]
-    pub fn get_arc(what: &CombArc<T>) -> &Arc<T> {
+    pub fn get_arc(what: &CombArc<T, P>) -> &Arc<T> {
         &what.inner
     }
-}
 
-impl<T: Clone + PartialEq> PartialEq<T> for CombArc<T> {
-    fn eq(&self, other: &T) -> bool {
-        Arc::as_ref(&self.inner) == other
+    /// Get a mutable reference to the inner `Arc` value.
+    ///
+    /// This is an escape hatch for using APIs which need a `&mut Arc<T>`. Anything done through it
+    /// bypasses copy-on-write, such as replacing the `Arc` with one shared elsewhere. However, the
+    /// next mutable borrow of this `CombArc` will still clone if the new `Arc` is shared.
+    #[inline]
+    pub fn get_arc_mut(what: &mut CombArc<T, P>) -> &mut Arc<T> {
+        &mut what.inner
     }
-}
 
-impl<T: Clone + PartialOrd> PartialOrd<T> for CombArc<T> {
-    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        Arc::as_ref(&self.inner).partial_cmp(other)
+    /// Replace the inner `Arc` value.
+    ///
+    /// This bypasses copy-on-write for this assignment, so other holders of `value` will share the
+    /// allocation with this `CombArc` until either of them is mutably borrowed.
+    #[inline]
+    pub fn set_arc(what: &mut CombArc<T, P>, value: Arc<T>) {
+        what.inner = value;
     }
-}
 
-impl<T: Clone> From<CombArc<T>> for Arc<T> {
-    fn from(value: CombArc<T>) -> Self {
-        value.inner
+    /// Get a raw pointer to the inner value.
+    ///
+    /// This is the address of the shared allocation, so it stays the same until the value is
+    /// cloned by a mutable borrow. Unlike calling `as_ptr` as a method, this never resolves to a
+    /// method of the inner type.
+    #[inline]
+    pub fn as_ptr(what: &CombArc<T, P>) -> *const T {
+        Arc::as_ptr(&what.inner)
     }
-}
 
-impl<T: Clone> From<Arc<T>> for CombArc<T> {
-    fn from(value: Arc<T>) -> Self {
-        CombArc::from_arc(value)
+    /// Get a raw pointer to the inner value, for passing across FFI boundaries.
+    ///
+    /// This is the same as [`CombArc::as_ptr`]. The pointer is only valid for as long as a strong
+    /// reference to this allocation exists.
+    #[inline]
+    pub fn as_raw(what: &CombArc<T, P>) -> *const T {
+        Self::as_ptr(what)
     }
-}
 
-impl<T: Clone> core::ops::Deref for CombArc<T> {
-    type Target = T;
-    fn deref(&self) -> &T {
-        self.inner.as_ref()
+    /// Consume the `CombArc`, returning a raw pointer to the inner value.
+    ///
+    /// This just calls [`Arc::into_raw`] on the inner value, so the strong reference is not
+    /// dropped. If the pointer is never turned back into a strong reference with
+    /// [`Arc::from_raw`], the inner value will be leaked.
+    #[inline]
+    pub fn into_raw(what: CombArc<T, P>) -> *const T {
+        Arc::into_raw(what.inner)
     }
-}
 
-impl<T: Clone> core::ops::DerefMut for CombArc<T> {
-    /// If the inner [`Arc`] has no strong references, get a mutable reference. Otherwise, clone the
-    /// [`Arc`].
+    /// Returns `true` if both `CombArc`s point to the same allocation.
+    ///
+    /// This is unrelated to value equality: two `CombArc`s can be equal without sharing an
+    /// allocation, and a value that does not implement [`PartialEq`] can still be compared this
+    /// way.
+    #[inline]
+    pub fn ptr_eq(a: &CombArc<T, P>, b: &CombArc<T, P>) -> bool {
+        Arc::ptr_eq(&a.inner, &b.inner)
+    }
+
+    /// Get the number of strong references to the inner value.
+    ///
+    /// Another thread can change the count at any time, so this is only a snapshot and should not
+    /// be relied on for correctness.
+    #[inline]
+    pub fn strong_count(what: &CombArc<T, P>) -> usize {
+        Arc::strong_count(&what.inner)
+    }
+
+    /// Get the number of weak references to the inner value.
+    ///
+    /// Another thread can change the count at any time, so this is only a snapshot and should not
+    /// be relied on for correctness.
+    #[inline]
+    pub fn weak_count(what: &CombArc<T, P>) -> usize {
+        Arc::weak_count(&what.inner)
+    }
+
+    /// Returns `true` if this is the only strong reference to the inner value.
+    ///
+    /// If this returns `true`, mutably borrowing will not clone the inner value. However, if there
+    /// are any weak references, mutably borrowing will still dissociate them, as the value is
+    /// moved to a new allocation.
+    ///
+    /// Like [`CombArc::strong_count`], this is only a snapshot, as a weak reference can be upgraded
+    /// on another thread at any time.
+    #[inline]
+    pub fn is_unique(what: &CombArc<T, P>) -> bool {
+        Arc::strong_count(&what.inner) == 1
+    }
+
+    /// Returns `true` if there are other strong references to the inner value.
+    ///
+    /// If this returns `true`, mutably borrowing will clone the inner value. This is the opposite
+    /// of [`CombArc::is_unique`].
+    #[inline]
+    pub fn is_shared(what: &CombArc<T, P>) -> bool {
+        !Self::is_unique(what)
+    }
+
+    /// Get a mutable reference to the inner value if it is not shared, without ever cloning it.
+    ///
+    /// Returns `None` if there are other strong references or any weak references, in which case
+    /// nothing is changed. This just calls [`Arc::get_mut`] on the inner value.
+    #[inline]
+    pub fn get_mut_if_unique(what: &mut CombArc<T, P>) -> Option<&mut T> {
+        Arc::get_mut(&mut what.inner)
+    }
+
+    /// Mutate the inner value with a closure only if it is not shared, returning `true` if the
+    /// closure was called.
+    ///
+    /// This uses [`CombArc::get_mut_if_unique`], so it never clones or dissociates weak references.
+    /// This is useful for opportunistic cleanup which is not worth cloning for.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    ///
+    /// let mut buffer = CombArc::new(Vec::<u8>::with_capacity(1024));
+    /// buffer.extend_from_slice(b"hello");
+    ///
+    /// // Shared, so trimming it is not worth cloning for.
+    /// let another_buffer = buffer.clone();
+    /// assert!(!CombArc::apply_if_unique(&mut buffer, Vec::shrink_to_fit));
+    /// assert!(buffer.capacity() >= 1024);
+    ///
+    /// // Unique, so it can be trimmed for free.
+    /// drop(another_buffer);
+    /// assert!(CombArc::apply_if_unique(&mut buffer, Vec::shrink_to_fit));
+    /// assert!(buffer.capacity() < 1024);
+    /// ```
+    #[inline]
+    pub fn apply_if_unique<F: FnOnce(&mut T)>(what: &mut CombArc<T, P>, f: F) -> bool {
+        match Self::get_mut_if_unique(what) {
+            Some(inner) => {
+                f(inner);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Mutate the inner value with a closure only if a predicate on the current value returns
+    /// `true`, returning `true` if the closure was called.
+    ///
+    /// The predicate only gets a shared reference, so if it returns `false`, the inner value is left
+    /// untouched and never cloned. Otherwise, this calls [`CombArc::make_mut`] and then the closure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut counts = CombArc::new(HashMap::from([("apples", 1u32)]));
+    /// let another_counts = counts.clone();
+    ///
+    /// // The key is absent, so the map is not cloned.
+    /// assert!(!CombArc::mutate_if(&mut counts, |m| m.contains_key("pears"), |m| *m.get_mut("pears").unwrap() += 1));
+    /// assert!(CombArc::ptr_eq(&counts, &another_counts));
+    ///
+    /// // The key is present, so the map is cloned and then modified.
+    /// assert!(CombArc::mutate_if(&mut counts, |m| m.contains_key("apples"), |m| *m.get_mut("apples").unwrap() += 1));
+    /// assert_eq!(counts["apples"], 2);
+    /// assert_eq!(another_counts["apples"], 1);
+    /// ```
+    #[inline]
+    pub fn mutate_if<C: FnOnce(&T) -> bool, F: FnOnce(&mut T)>(what: &mut CombArc<T, P>, predicate: C, f: F) -> bool {
+        if predicate(&what.inner) {
+            f(Self::make_mut(what));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get a mutable reference to the inner value, cloning it first if it is shared.
+    ///
+    /// This is what mutably borrowing through [`DerefMut`] does, but named so it can be called
+    /// explicitly. If there are no other strong references but there are weak references, the
+    /// weak references will be dissociated.
+    ///
+    /// This just calls [`Arc::make_mut`] on the inner value, after calling
+    /// [`CowPolicy::before_clone`] if it is shared.
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn make_mut(what: &mut CombArc<T, P>) -> &mut T {
+        if P::INTERCEPTS_CLONES && Self::is_shared(what) {
+            P::before_clone::<T>();
+        }
+        #[cfg(feature = "debug_hooks")]
+        if Self::is_shared(what) {
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+        }
+        Arc::make_mut(&mut what.inner)
+    }
+
+    /// Get a mutable reference to the inner value, attempting to clone it first if it is shared.
+    ///
+    /// This is the same as [`CombArc::make_mut`], but the inner value is cloned with
+    /// [`TryClone::try_clone`]. If that fails, the error is returned and this reference still
+    /// points to the shared value. A clone budget is only used if cloning succeeds.
+    ///
+    /// If a weak reference is upgraded on another thread while this is unique, this may still fall
+    /// back to cloning with [`Clone::clone`], like [`CombArc::make_mut`] would.
+    #[inline]
+    pub fn try_make_mut(what: &mut CombArc<T, P>) -> Result<&mut T, T::Error> where T: TryClone {
+        if Self::is_shared(what) {
+            P::before_clone::<T>();
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::check_clone::<T>();
+            let value = what.inner.try_clone()?;
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::spend_budget::<T>(&what.budget);
+            what.inner = Arc::new(value);
+        }
+        Ok(Self::make_mut(what))
+    }
+
+    /// Get a mutable reference to the inner value, cloning it first with `clone` if it is shared.
+    ///
+    /// This is the same as [`CombArc::make_mut`], but `clone` is called instead of [`Clone::clone`]
+    /// when a clone is needed, e.g. to reset cached data rather than copying it. If this is a
+    /// unique reference, `clone` is not called.
+    ///
+    /// The result of `clone` is used as-is, so it should be a logically equivalent starting point
+    /// for the mutation.
+    ///
+    /// If a weak reference is upgraded on another thread while this is unique, this may still fall
+    /// back to cloning with [`Clone::clone`], like [`CombArc::make_mut`] would.
+    #[inline]
+    pub fn make_mut_with<F: FnOnce(&T) -> T>(what: &mut CombArc<T, P>, clone: F) -> &mut T {
+        if Self::is_shared(what) {
+            P::before_clone::<T>();
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+            what.inner = Arc::new(clone(&what.inner));
+        }
+        Self::make_mut(what)
+    }
+
+    /// Get a mutable reference to the inner value like [`CombArc::make_mut`], but return an error
+    /// instead of panicking if cloning it would exceed its clone budget.
+    ///
+    /// See [`CombArc::with_clone_budget`]. References without a clone budget never return an error.
+    #[cfg(feature = "debug_hooks")]
+    pub fn try_make_mut_within_budget(what: &mut CombArc<T, P>) -> Result<&mut T, crate::CloneBudgetExceeded> {
+        if Self::is_shared(what) {
+            what.budget.try_spend()?;
+            P::before_clone::<T>();
+            crate::debug_hooks::check_clone::<T>();
+        }
+        Ok(Arc::make_mut(&mut what.inner))
+    }
+
+    /// Get the number of copy-on-write clones used from the clone budget of this reference.
+    ///
+    /// Returns `None` if this reference has no clone budget. See [`CombArc::with_clone_budget`].
+    #[cfg(feature = "debug_hooks")]
+    pub fn clones_used(what: &CombArc<T, P>) -> Option<usize> {
+        what.budget.used()
+    }
+
+    /// Get a guard which only calls [`CombArc::make_mut`] once it is mutably dereferenced.
+    ///
+    /// Reading through the guard never clones, so if it is never mutably dereferenced, this
+    /// reference is left untouched. See [`CombArcWriteGuard`].
+    #[inline]
+    pub fn write(what: &mut CombArc<T, P>) -> CombArcWriteGuard<'_, T, P> {
+        CombArcWriteGuard::new(what)
+    }
+
+    /// Start a transaction which puts back the current value unless it is committed.
+    ///
+    /// See [`CombArcTransaction`].
+    #[inline]
+    pub fn transaction(what: &mut CombArc<T, P>) -> CombArcTransaction<'_, T, P> {
+        CombArcTransaction::new(what)
+    }
+
+    /// Edit the inner value with a closure, putting back the previous value if it returns an error
+    /// or panics.
+    ///
+    /// Like [`CombArc::transaction`], this always clones the inner value if it is mutably borrowed.
+    pub fn transact<R, E, F: FnOnce(&mut T) -> Result<R, E>>(what: &mut CombArc<T, P>, f: F) -> Result<R, E> {
+        let mut transaction = Self::transaction(what);
+        let result = f(&mut transaction)?;
+        CombArcTransaction::commit(transaction);
+        Ok(result)
+    }
+
+    /// Get a mutable reference to the inner value like [`CombArc::make_mut`], but without ever
+    /// dissociating weak references.
+    ///
+    /// * If there are no weak references, this is the same as [`CombArc::make_mut`].
+    /// * If there are other strong references, the inner value is cloned. Weak references keep
+    ///   pointing to the old allocation, which the other strong references keep alive.
+    /// * If this is the only strong reference but there are weak references, `None` is returned
+    ///   and nothing is changed.
+    ///
+    /// The last case cannot mutate in place, since a weak reference could be upgraded while the
+    /// mutable reference is still in use, and cloning would not help either, since the old
+    /// allocation would be dropped along with this reference.
+    #[inline]
+    pub fn make_mut_keep_weak(what: &mut CombArc<T, P>) -> Option<&mut T> {
+        if Self::is_unique(what) && Self::weak_count(what) != 0 {
+            None
+        } else {
+            Some(Self::make_mut(what))
+        }
+    }
+
+    /// Make sure this is a unique reference, returning `true` if a new allocation was made.
+    ///
+    /// This does the same thing as [`CombArc::make_mut`] without returning the reference, so it can be
+    /// called before a series of mutations. Afterwards, [`CombArc::is_unique`] will return `true`.
+    ///
+    /// A new allocation is made if the inner value is shared (in which case it is cloned), or if
+    /// there are weak references (in which case it is moved and the weak references dissociated).
+    #[inline]
+    pub fn ensure_unique(what: &mut CombArc<T, P>) -> bool {
+        let address = Self::as_ptr(what);
+        Self::make_mut(what);
+        address != Self::as_ptr(what)
+    }
+
+    /// Take the inner value out, leaving a default value in its place.
+    ///
+    /// If this is a unique reference, the inner value is moved out and replaced with
+    /// [`T::default`](Default::default). Otherwise, the inner value is cloned first like with any
+    /// other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn take(what: &mut CombArc<T, P>) -> T where T: Default {
+        core::mem::take(Self::make_mut(what))
+    }
+
+    /// Replace the inner value, returning the old value.
+    ///
+    /// If this is a unique reference, the inner value is swapped in place. Otherwise, the old value
+    /// is cloned first like with any other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn replace(what: &mut CombArc<T, P>, value: T) -> T {
+        core::mem::replace(Self::make_mut(what), value)
+    }
+
+    /// Swap the inner value with `other`.
+    ///
+    /// If this is a unique reference, the values are swapped in place. Otherwise, the inner value
+    /// is cloned first like with any other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn swap_inner(what: &mut CombArc<T, P>, other: &mut T) {
+        core::mem::swap(Self::make_mut(what), other)
+    }
+
+    /// Overwrite the inner value without cloning the old one.
+    ///
+    /// If this is a unique reference with no weak references, the value is assigned in place.
+    /// Otherwise, this reference is pointed to a new allocation containing `value`, leaving other
+    /// references untouched. In both cases, [`Clone::clone`] is never called, so this is the
+    /// preferred way to overwrite the whole value rather than assigning through [`DerefMut`].
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn set(what: &mut CombArc<T, P>, value: T) {
+        match Arc::get_mut(&mut what.inner) {
+            Some(inner) => *inner = value,
+            None => what.inner = Arc::new(value)
+        }
+    }
+
+    /// Overwrite the inner value if it is not equal to `value`, returning `true` if it was
+    /// overwritten.
+    ///
+    /// The comparison is done through a shared reference, so if the values are equal, nothing is
+    /// cloned and the address of the inner value stays the same. Otherwise, this does the same
+    /// thing as [`CombArc::set`].
+    #[inline]
+    pub fn set_if_ne(what: &mut CombArc<T, P>, value: T) -> bool where T: PartialEq {
+        if *what.inner == value {
+            false
+        } else {
+            Self::set(what, value);
+            true
+        }
+    }
+
+    /// Mutate the inner value with a closure, returning whatever the closure returns.
+    ///
+    /// This calls [`CombArc::make_mut`] exactly once, so the inner value is cloned first if it is
+    /// shared, and the closure is given a mutable reference to the result.
+    #[inline]
+    pub fn update<R, F: FnOnce(&mut T) -> R>(what: &mut CombArc<T, P>, f: F) -> R {
+        f(Self::make_mut(what))
+    }
+
+    /// Mutate the inner value with a closure and return the `CombArc`, for chaining.
+    ///
+    /// A newly constructed `CombArc` is unique, so this will not clone unless the `CombArc` was shared
+    /// beforehand, in which case it is cloned like with any other mutable borrow.
+    ///
+    /// Unlike most functions on `CombArc`, this is a method so that it can be chained. As such, it
+    /// takes priority over any `with` method on the inner type, which can still be called by
+    /// dereferencing (e.g. `(*value).with()`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Config {
+    ///     retries: u32,
+    ///     name: String
+    /// }
+    ///
+    /// let config = CombArc::new(Config::default())
+    ///     .with(|c| c.retries = 3)
+    ///     .with(|c| c.name = "x".into());
+    /// assert_eq!(config.retries, 3);
+    /// assert_eq!(config.name, "x");
+    /// ```
+    #[inline]
+    pub fn with<F: FnOnce(&mut T)>(mut self, f: F) -> CombArc<T, P> {
+        f(Self::make_mut(&mut self));
+        self
+    }
+
+    /// Create a new `CombArc` from a reference to the inner value.
+    ///
+    /// The result is always a new allocation. Other references to the inner value are untouched.
+    #[inline]
+    pub fn map<U: Clone, F: FnOnce(&T) -> U>(what: CombArc<T, P>, f: F) -> CombArc<U, P> {
+        CombArc::from_inner(Arc::new(f(&what)))
+    }
+
+    /// Create a new `CombArc` from the inner value.
+    ///
+    /// If this is a unique reference, the inner value will be moved into `f`. Otherwise, it will
+    /// be cloned, and other references to it are untouched. See [`CombArc::make_inner`].
+    #[inline]
+    pub fn map_owned<U: Clone, F: FnOnce(T) -> U>(what: CombArc<T, P>, f: F) -> CombArc<U, P> {
+        CombArc::from_inner(Arc::new(f(Self::make_inner(what))))
+    }
+
+    /// Attempt to create a new `CombArc` from a reference to the inner value.
+    ///
+    /// If `f` returns an error, the error is returned and nothing is allocated. Otherwise, the
+    /// result is a new allocation.
+    #[inline]
+    pub fn try_map<U: Clone, E, F: FnOnce(&T) -> Result<U, E>>(what: &CombArc<T, P>, f: F) -> Result<CombArc<U, P>, E> {
+        f(what).map(|v| CombArc::from_inner(Arc::new(v)))
+    }
+
+    /// Attempt to create a new `CombArc` from a reference to the inner value.
+    ///
+    /// If `f` returns `None`, `None` is returned and nothing is allocated. Otherwise, the result
+    /// is a new allocation.
+    #[inline]
+    pub fn filter_map<U: Clone, F: FnOnce(&T) -> Option<U>>(what: &CombArc<T, P>, f: F) -> Option<CombArc<U, P>> {
+        f(what).map(|v| CombArc::from_inner(Arc::new(v)))
+    }
+
+    /// Transform the inner value by value, returning a reference to the result.
+    ///
+    /// If this is a unique reference, the inner value is moved into `f`, so it is never cloned.
+    /// Otherwise, it is cloned once like with any other mutable borrow, leaving other references
+    /// untouched.
+    ///
+    /// Unlike [`CombArc::map_owned`], the clone goes through the [`CowPolicy`], and the result keeps
+    /// the clone budget of this reference, if any. This takes the `CombArc` by value since nothing
+    /// can be left in its place while `f` runs.
+    #[inline]
+    pub fn map_in_place<F: FnOnce(T) -> T>(what: CombArc<T, P>, f: F) -> CombArc<T, P> {
+        let mut what = what;
+        Self::make_mut(&mut what);
+        let value = Arc::try_unwrap(what.inner).unwrap_or_else(|inner| T::clone(&inner));
+        Self {
+            inner: Arc::new(f(value)),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: what.budget
+        }
+    }
+
+    /// Clone the inner value, mutate the clone with a closure, and return it as a new `CombArc`.
+    ///
+    /// This reference and any other references to the inner value are untouched.
+    #[inline]
+    pub fn modify_cloned<F: FnOnce(&mut T)>(what: &CombArc<T, P>, f: F) -> CombArc<T, P> {
+        let mut value = what.inner.as_ref().clone();
+        f(&mut value);
+        Self::from_inner(Arc::new(value))
+    }
+
+    /// Build a new inner value from the current one, store it in a new allocation, and return the
+    /// previous reference.
+    ///
+    /// The previous inner value is never cloned, and any other references to it keep sharing it
+    /// with the returned `CombArc`. This is useful for keeping old snapshots around, such as for undo
+    /// history.
+    #[inline]
+    pub fn fetch_update<F: FnOnce(&T) -> T>(what: &mut CombArc<T, P>, f: F) -> CombArc<T, P> {
+        let value = f(&what.inner);
+        core::mem::replace(what, Self::from_inner(Arc::new(value)))
+    }
+
+    /// Create a [`CombArcRef`] which dereferences to a part of the inner value, such as a field, while
+    /// keeping this reference.
+    ///
+    /// The projection must not capture anything, since it is stored as a function pointer.
+    #[inline]
+    pub fn project<U: ?Sized>(what: CombArc<T, P>, projection: fn(&T) -> &U) -> CombArcRef<T, U, P> {
+        CombArcRef::new(what, projection)
+    }
+
+    /// Get a mutable reference to a part of the inner value, such as a field, cloning the inner
+    /// value first if it is shared.
+    ///
+    /// This is the same as calling `projection` on [`CombArc::make_mut`]. The returned reference can
+    /// be used for any number of writes, and whether this is shared is only checked once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    ///
+    /// #[derive(Clone)]
+    /// struct Document {
+    ///     title: String,
+    ///     paragraphs: Vec<String>
+    /// }
+    ///
+    /// let mut document = CombArc::new(Document { title: "Hello".to_owned(), paragraphs: vec!["World".to_owned()] });
+    /// let another_document = document.clone();
+    ///
+    /// let paragraph = CombArc::project_mut(&mut document, |d| &mut d.paragraphs[0]);
+    /// paragraph.push('!');
+    /// paragraph.push('?');
+    ///
+    /// assert_eq!(document.paragraphs[0], "World!?");
+    /// assert_eq!(another_document.paragraphs[0], "World");
+    /// ```
+    #[inline]
+    pub fn project_mut<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(what: &mut CombArc<T, P>, projection: F) -> &mut U {
+        projection(Self::make_mut(what))
+    }
+
+    /// Call the inner closure with an argument.
+    ///
+    /// Closures taking more than one argument can be called with the `fn_traits` feature, which
+    /// implements the [`Fn`] traits for `CombArc`.
+    #[inline]
+    pub fn call<A, R>(what: &CombArc<T, P>, argument: A) -> R where T: Fn(A) -> R {
+        (what.inner)(argument)
+    }
+
+    /// Call the inner closure with an argument, allowing it to mutate what it captured.
+    ///
+    /// This goes through [`CombArc::make_mut`], so if the closure is shared, it is cloned first, along
+    /// with everything it captured. Each reference which called it then has its own copy of the
+    /// captures, so for example, a counter captured by the closure will be counted separately by
+    /// each reference after the first call.
+    #[inline]
+    pub fn call_mut<A, R>(what: &mut CombArc<T, P>, argument: A) -> R where T: FnMut(A) -> R {
+        Self::make_mut(what)(argument)
+    }
+
+    /// Merge another reference into this one with a closure, returning the merged reference.
+    ///
+    /// The inner value of `what` is made mutable with [`CombArc::make_mut`], so it is not cloned if it
+    /// is unique. If both references share the same allocation, `what` is not unique, so the inner
+    /// value is cloned first and `f` is still called with the clone and the original.
+    #[inline]
+    pub fn merge_with<F: FnOnce(&mut T, &T)>(mut what: CombArc<T, P>, other: &CombArc<T, P>, f: F) -> CombArc<T, P> {
+        f(Self::make_mut(&mut what), &other.inner);
+        what
+    }
+
+    /// Create a [`Weak`] reference to the inner value.
+    ///
+    /// The weak reference refers to the current allocation, not to the `CombArc`, so:
+    /// * If the inner value is cloned by a mutable borrow, it will keep pointing to the old
+    ///   allocation which the other strong references still share.
+    /// * If there are no other strong references, a mutable borrow will move the value into a new
+    ///   allocation, and the weak reference will no longer be able to upgrade.
+    #[inline]
+    pub fn downgrade(what: &CombArc<T, P>) -> Weak<T> {
+        Arc::downgrade(&what.inner)
+    }
+
+    /// Create a [`CombWeak`] reference to the inner value.
+    ///
+    /// This is the same as [`CombArc::downgrade`], but upgrading it gives a `CombArc`.
+    #[inline]
+    pub fn downgrade_comb(what: &CombArc<T, P>) -> CombWeak<T> {
+        CombWeak::from_weak(Self::downgrade(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombArc<Option<T>, P> {
+    /// Convert a `CombArc<Option<T>>` into an `Option<CombArc<T>>`.
+    ///
+    /// If this is a unique reference, the inner value will be moved into a new allocation.
+    /// Otherwise, it will be cloned. If the inner value is `None`, the reference is simply dropped.
+    #[inline]
+    pub fn transpose(what: CombArc<Option<T>, P>) -> Option<CombArc<T, P>> {
+        Self::make_inner(what).map(|v| CombArc::from_inner(Arc::new(v)))
+    }
+}
+
+impl<T: Clone, E: Clone, P: CowPolicy> CombArc<Result<T, E>, P> {
+    /// Convert a `CombArc<Result<T, E>>` into a `Result<CombArc<T>, E>`.
+    ///
+    /// If this is a unique reference, the inner value will be moved, with `T` moved into a new
+    /// allocation. Otherwise, it will be cloned. The error is always returned by value.
+    ///
+    /// This is the `Result` version of [`CombArc::transpose`]. It has a different name, since calling
+    /// `CombArc::transpose` would otherwise be ambiguous.
+    #[inline]
+    pub fn into_result(what: CombArc<Result<T, E>, P>) -> Result<CombArc<T, P>, E> {
+        Self::make_inner(what).map(|v| CombArc::from_inner(Arc::new(v)))
+    }
+
+    /// Borrow the inner value as a `Result` of references without cloning or restructuring.
+    #[inline]
+    pub fn as_result(what: &CombArc<Result<T, E>, P>) -> Result<&T, &E> {
+        what.inner.as_ref().as_ref()
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> Clone for CombArc<T, P> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: self.budget.clone()
+        }
+    }
+
+    /// Make this reference equal to `source`.
+    ///
+    /// If this is a unique reference, the inner value of `source` is cloned into the existing
+    /// allocation with [`Clone::clone_from`], so its resources (such as the capacity of a `Vec`)
+    /// may be reused. Otherwise, this reference is pointed to the allocation of `source`.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        match Arc::get_mut(&mut self.inner) {
+            Some(inner) => inner.clone_from(&source.inner),
+            None => *self = source.clone()
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> Clone for CombArc<T, P, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: self.budget.clone()
+        }
+    }
+
+    /// Make this reference equal to `source`.
+    ///
+    /// If this is a unique reference, the inner value of `source` is cloned into the existing
+    /// allocation with [`Clone::clone_from`], so its resources (such as the capacity of a `Vec`)
+    /// may be reused. Otherwise, this reference is pointed to the allocation of `source`.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        match Arc::get_mut(&mut self.inner) {
+            Some(inner) => inner.clone_from(&source.inner),
+            None => *self = source.clone()
+        }
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for CombArc<T, P> {
+    fn default() -> Self {
+        Self::from_inner(Arc::default())
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation. This
+/// assumes that the inner value is equal to itself, which is not true of values such as `NaN`.
+///
+/// This cannot be generalized to `PartialEq<CombArc<U>>` for `T: PartialEq<U>`, since it would
+/// conflict with [`PartialEq<T>`] when `T` is itself a `CombArc`. To compare references with
+/// different inner types, compare the inner values instead, like `*a == *b`.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq for CombArc<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) || *self.inner == *other.inner
+    }
+}
+
+impl<T: Clone + Eq, P: CowPolicy> Eq for CombArc<T, P> {}
+
+/// Compares by value.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombRc};
+///
+/// let actual = CombArc::new(vec![1, 2, 3]);
+/// let expected = CombRc::new(vec![1, 2, 3]);
+/// assert_eq!(actual, expected);
+/// ```
+impl<T: Clone + PartialEq, P: CowPolicy, Q: CowPolicy> PartialEq<CombRc<T, Q>> for CombArc<T, P> {
+    fn eq(&self, other: &CombRc<T, Q>) -> bool {
+        *self.inner == **other
+    }
+}
+
+/// Compares by value, returning [`Ordering::Equal`] without comparing if both sides share an
+/// allocation, like [`PartialEq`].
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd for CombArc<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            Some(Ordering::Equal)
+        } else {
+            self.inner.partial_cmp(&other.inner)
+        }
+    }
+}
+
+/// Compares by value, returning [`Ordering::Equal`] without comparing if both sides share an
+/// allocation.
+impl<T: Clone + Ord, P: CowPolicy> Ord for CombArc<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            Ordering::Equal
+        } else {
+            self.inner.cmp(&other.inner)
+        }
+    }
+}
+
+impl<T: Clone + core::hash::Hash, P: CowPolicy> core::hash::Hash for CombArc<T, P> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<T: Clone + core::hash::Hasher, P: CowPolicy> core::hash::Hasher for CombArc<T, P> {
+    fn finish(&self) -> u64 {
+        self.inner.finish()
+    }
+
+    /// This goes through [`CombArc::make_mut`], so if the hasher is shared, its state is cloned first,
+    /// and the other references keep the state from before the write.
+    fn write(&mut self, bytes: &[u8]) {
+        Self::make_mut(self).write(bytes)
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        Self::make_mut(self).write_u8(i)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        Self::make_mut(self).write_u16(i)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        Self::make_mut(self).write_u32(i)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        Self::make_mut(self).write_u64(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        Self::make_mut(self).write_u128(i)
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        Self::make_mut(self).write_usize(i)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        Self::make_mut(self).write_i8(i)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        Self::make_mut(self).write_i16(i)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        Self::make_mut(self).write_i32(i)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        Self::make_mut(self).write_i64(i)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        Self::make_mut(self).write_i128(i)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        Self::make_mut(self).write_isize(i)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::Borrow<T> for CombArc<T, P> {
+    fn borrow(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::BorrowMut<T> for CombArc<T, P> {
+    /// This just calls [`CombArc::make_mut`], so it clones the inner value if it is shared.
+    fn borrow_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+impl<P: CowPolicy> core::borrow::Borrow<str> for CombArc<String, P> {
+    fn borrow(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::Borrow<[T]> for CombArc<Vec<T>, P> {
+    fn borrow(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<T: Clone + AsRef<U>, U: ?Sized, P: CowPolicy> AsRef<U> for CombArc<T, P> {
+    /// Unlike [`Arc`], this forwards to the inner value, so a `CombArc<String>` can be referenced as a
+    /// `str`, just like the `String` itself. As such, it cannot also be referenced as the inner
+    /// value, since the implementations would conflict. Use [`Deref`] or [`Borrow`] for that.
+    ///
+    /// Since this is a method of `CombArc`, calling `as_ref` as a method calls this, not the `as_ref`
+    /// of the inner value, such as [`Option::as_ref`]. Call those like `Option::as_ref(&value)`.
+    ///
+    /// [`Deref`]: core::ops::Deref
+    /// [`Borrow`]: core::borrow::Borrow
+    fn as_ref(&self) -> &U {
+        T::as_ref(&self.inner)
+    }
+}
+
+impl<T: Clone + AsMut<U>, U: ?Sized, P: CowPolicy> AsMut<U> for CombArc<T, P> {
+    /// This calls [`CombArc::make_mut`] and forwards to the inner value, so it clones the inner value
+    /// if it is shared. Like `AsRef`, it cannot also get the inner value itself. Use [`DerefMut`] or
+    /// [`BorrowMut`] for that.
+    ///
+    /// Whether this is shared is checked on every call, so calling this in a loop only clones the
+    /// inner value the first time.
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    /// [`BorrowMut`]: core::borrow::BorrowMut
+    fn as_mut(&mut self) -> &mut U {
+        T::as_mut(Self::make_mut(self))
+    }
+}
+
+/// Formats the inner value, like [`Display`](core::fmt::Display).
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Arc::as_ref(&self.inner).fmt(f)
+    }
+}
+
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<T> for CombArc<T, P> {
+    fn eq(&self, other: &T) -> bool {
+        Arc::as_ref(&self.inner) == other
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<T> for CombArc<T, P> {
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        Arc::as_ref(&self.inner).partial_cmp(other)
+    }
+}
+
+impl<'a, T: Clone + PartialEq, P: CowPolicy> PartialEq<&'a T> for CombArc<T, P> {
+    fn eq(&self, other: &&'a T) -> bool {
+        Arc::as_ref(&self.inner) == *other
+    }
+}
+
+impl<'a, 'b, T: Clone + PartialEq, P: CowPolicy> PartialEq<&'a &'b T> for CombArc<T, P> {
+    fn eq(&self, other: &&'a &'b T) -> bool {
+        Arc::as_ref(&self.inner) == **other
+    }
+}
+
+impl<'a, T: Clone + PartialOrd, P: CowPolicy> PartialOrd<&'a T> for CombArc<T, P> {
+    fn partial_cmp(&self, other: &&'a T) -> Option<Ordering> {
+        Arc::as_ref(&self.inner).partial_cmp(*other)
+    }
+}
+
+impl<'a, 'b, T: Clone + PartialOrd, P: CowPolicy> PartialOrd<&'a &'b T> for CombArc<T, P> {
+    fn partial_cmp(&self, other: &&'a &'b T) -> Option<Ordering> {
+        Arc::as_ref(&self.inner).partial_cmp(**other)
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<Arc<T>> for CombArc<T, P> {
+    fn eq(&self, other: &Arc<T>) -> bool {
+        Arc::ptr_eq(&self.inner, other) || *self.inner == **other
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<CombArc<T, P>> for Arc<T> {
+    fn eq(&self, other: &CombArc<T, P>) -> bool {
+        Arc::ptr_eq(self, &other.inner) || **self == *other.inner
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<Arc<T>> for CombArc<T, P> {
+    fn partial_cmp(&self, other: &Arc<T>) -> Option<Ordering> {
+        self.inner.partial_cmp(other)
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<CombArc<T, P>> for Arc<T> {
+    fn partial_cmp(&self, other: &CombArc<T, P>) -> Option<Ordering> {
+        self.partial_cmp(&other.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for Arc<T> {
+    fn from(value: CombArc<T, P>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<T> for CombArc<T, P> {
+    /// Moves the value into a new allocation, like [`CombArc::new`].
+    ///
+    /// This allows taking an `impl Into<CombArc<T>>`, which accepts either a value or a reference to
+    /// one. If `T` is itself an `Arc`, the type has to be specified, since the `Arc` could also be
+    /// converted to a `CombArc` without allocating. The same goes for an `Option<CombArc<T>>`, which could
+    /// also be converted to a `CombArc<Option<T>>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombArc;
+    /// use std::sync::Arc;
+    ///
+    /// fn store(value: impl Into<CombArc<Vec<u32>>>) -> CombArc<Vec<u32>> {
+    ///     value.into()
+    /// }
+    ///
+    /// let stored = store(vec![1]);
+    /// assert_eq!(*store(stored.clone()), [1]);
+    ///
+    /// let inner = Arc::new(1u32);
+    /// let wrapped: CombArc<Arc<u32>> = CombArc::from(inner.clone());
+    /// let converted: CombArc<u32> = CombArc::from(inner);
+    /// assert!(Arc::ptr_eq(&*wrapped, CombArc::get_arc(&converted)));
+    /// ```
+    fn from(value: T) -> Self {
+        Self::from_inner(Arc::new(value))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<&T> for CombArc<T, P> {
+    /// Clones the value into a new allocation, like [`CombArc::from_ref`].
+    ///
+    /// Since a reference can also be moved into a `CombArc<&T>`, the type may have to be specified.
+    fn from(value: &T) -> Self {
+        Self::from_inner(Arc::new(value.clone()))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Box<T>> for CombArc<T, P> {
+    /// Moves the value into a new allocation with [`Arc::from`], copying it from the box directly.
+    ///
+    /// Unlike `CombArc::new(*value)`, this does not move the value through the stack, which matters for
+    /// large values.
+    fn from(value: Box<T>) -> Self {
+        Self::from_inner(Arc::from(value))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Cow<'_, T>> for CombArc<T, P> {
+    /// Moves an owned value into a new allocation, or clones a borrowed one.
+    ///
+    /// Since `CombArc` needs a sized value, there is no conversion from a `Cow<str>` or `Cow<[T]>`
+    /// to a `CombArc<str>` or `CombArc<[T]>`. Convert those to a `CombArc<String>` or `CombArc<Vec<T>>` with
+    /// [`Cow::into_owned`] instead.
+    fn from(value: Cow<'_, T>) -> Self {
+        Self::from_inner(Arc::new(value.into_owned()))
+    }
+}
+
+impl<'a, T: Clone, P: CowPolicy> From<&'a CombArc<T, P>> for Cow<'a, T> {
+    /// Borrows the inner value, like [`CombArc::to_cow`].
+    fn from(value: &'a CombArc<T, P>) -> Self {
+        CombArc::to_cow(value)
+    }
+}
+
+impl<T: Clone + core::str::FromStr, P: CowPolicy> core::str::FromStr for CombArc<T, P> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, T::Err> {
+        T::from_str(s).map(|value| Self::from_inner(Arc::new(value)))
+    }
+}
+
+/// Collects into the inner value, then moves it into a new allocation.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArc;
+///
+/// let shouted: CombArc<String> = "hello".chars().map(|c| c.to_ascii_uppercase()).collect();
+/// assert_eq!(*shouted, "HELLO");
+/// ```
+impl<A, T: Clone + FromIterator<A>, P: CowPolicy> FromIterator<A> for CombArc<T, P> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::from_inner(Arc::new(T::from_iter(iter)))
+    }
+}
+
+/// Extends the inner value, cloning it first if this reference is not unique.
+///
+/// The iterator is peeked first, so extending with an empty iterator never clones.
+impl<A, T: Clone + Extend<A>, P: CowPolicy> Extend<A> for CombArc<T, P> {
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        if iter.peek().is_some() {
+            Self::make_mut(self).extend(iter)
+        }
+    }
+}
+
+/// Iterates over a reference to the inner value. This never clones.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArc;
+///
+/// let numbers = CombArc::new(vec![1, 2, 3]);
+/// let mut sum = 0;
+/// for number in &numbers {
+///     sum += number;
+/// }
+/// assert_eq!(sum, 6);
+/// ```
+impl<'a, T: Clone, P: CowPolicy> IntoIterator for &'a CombArc<T, P> where &'a T: IntoIterator {
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (&*self.inner).into_iter()
+    }
+}
+
+/// Iterates over the inner value by value.
+///
+/// This goes through [`CombArc::make_inner`], so if this reference is not unique, the whole inner
+/// value is cloned first. Iterate over `&CombArc` instead to avoid this.
+impl<T: Clone + IntoIterator, P: CowPolicy> IntoIterator for CombArc<T, P> {
+    type Item = T::Item;
+    type IntoIter = T::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Self::make_inner(self).into_iter()
+    }
+}
+
+/// Sums the inner values, moving them out of unique references and cloning them otherwise.
+impl<T: Clone + Sum, P: CowPolicy> Sum for CombArc<T, P> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::from_inner(Arc::new(iter.map(Self::make_inner).sum()))
+    }
+}
+
+/// Sums references to the inner values. This never clones.
+impl<'a, T: Clone + Sum<&'a T>, P: CowPolicy> Sum<&'a CombArc<T, P>> for CombArc<T, P> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self::from_inner(Arc::new(iter.map(|what| &*what.inner).sum()))
+    }
+}
+
+/// Multiplies the inner values, moving them out of unique references and cloning them otherwise.
+impl<T: Clone + Product, P: CowPolicy> Product for CombArc<T, P> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::from_inner(Arc::new(iter.map(Self::make_inner).product()))
+    }
+}
+
+/// Multiplies references to the inner values. This never clones.
+impl<'a, T: Clone + Product<&'a T>, P: CowPolicy> Product<&'a CombArc<T, P>> for CombArc<T, P> {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self::from_inner(Arc::new(iter.map(|what| &*what.inner).product()))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Arc<T>> for CombArc<T, P> {
+    fn from(value: Arc<T>) -> Self {
+        Self::from_inner(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Option<CombArc<T, P>>> for CombArc<Option<T>, P> {
+    /// Convert an `Option<CombArc<T>>` into a `CombArc<Option<T>>`.
+    ///
+    /// This is the reverse of [`CombArc::transpose`], so the inner value is moved if it is unique.
+    fn from(value: Option<CombArc<T, P>>) -> Self {
+        Self::from_inner(Arc::new(value.map(CombArc::make_inner)))
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombArc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.as_ref()
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombArc<T, P> {
+    /// If the inner [`Arc`] has no strong references, get a mutable reference. Otherwise, clone the
+    /// [`Arc`].
+    ///
+    /// This just calls [`CombArc::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator> core::ops::Deref for CombArc<T, P, A> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.as_ref()
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> core::ops::DerefMut for CombArc<T, P, A> {
+    /// If the inner [`Arc`] has no strong references, get a mutable reference. Otherwise, clone the
+    /// [`Arc`] into the same allocator.
     ///
-    /// This just calls [`Arc::make_mut`] on the inner value.
+    /// This does the same thing as [`CombArc::make_mut`], but for any allocator.
     fn deref_mut(&mut self) -> &mut T {
-        Arc::make_mut(&mut self.inner)
+        Self::make_mut_in(self)
     }
 }
 
-impl<T: core::fmt::Display + Clone> core::fmt::Display for CombArc<T> {
+impl<T: core::fmt::Display + Clone, P: CowPolicy> core::fmt::Display for CombArc<T, P> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Arc::as_ref(&self.inner).fmt(f)
     }
@@ -0,0 +1,148 @@
+use core::cmp::Ordering;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A [`CombArc`] which is hashed, compared, and ordered by the address of its allocation rather
+/// than by its inner value.
+///
+/// This holds the reference, so the allocation cannot be freed and its address cannot be reused
+/// while this exists. Two of these are equal if they share an allocation, even if the inner value
+/// does not implement [`Eq`], and they are not equal if the inner values are equal but were
+/// allocated separately, such as after a copy-on-write clone. The order is consistent, but it is
+/// arbitrary and may differ between runs.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombArcByPtr};
+/// use std::collections::HashSet;
+///
+/// let node = CombArc::new(1u32);
+/// let mut visited = HashSet::new();
+/// assert!(visited.insert(CombArcByPtr(node.clone())));
+/// assert!(!visited.insert(CombArcByPtr(node.clone())));
+///
+/// // Equal, but a different allocation.
+/// assert!(visited.insert(CombArcByPtr(CombArc::new(1u32))));
+/// ```
+pub struct CombArcByPtr<T: Clone, P: CowPolicy = CloneOnWrite>(pub CombArc<T, P>);
+
+impl<T: Clone, P: CowPolicy> Clone for CombArcByPtr<T, P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Clone, P: CowPolicy> PartialEq for CombArcByPtr<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        CombArc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Eq for CombArcByPtr<T, P> {}
+
+impl<T: Clone, P: CowPolicy> PartialOrd for CombArcByPtr<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Ord for CombArcByPtr<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        CombArc::as_ptr(&self.0).cmp(&CombArc::as_ptr(&other.0))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Hash for CombArcByPtr<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        CombArc::as_ptr(&self.0).hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcByPtr<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CombArcByPtr")
+            .field(&CombArc::as_ptr(&self.0))
+            .field(&*self.0)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for CombArcByPtr<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        Self(value)
+    }
+}
+
+/// A [`CombRc`] which is hashed, compared, and ordered by the address of its allocation rather
+/// than by its inner value.
+///
+/// This holds the reference, so the allocation cannot be freed and its address cannot be reused
+/// while this exists. Two of these are equal if they share an allocation, even if the inner value
+/// does not implement [`Eq`], and they are not equal if the inner values are equal but were
+/// allocated separately, such as after a copy-on-write clone. The order is consistent, but it is
+/// arbitrary and may differ between runs.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcByPtr};
+/// use std::collections::HashSet;
+///
+/// let node = CombRc::new(1u32);
+/// let mut visited = HashSet::new();
+/// assert!(visited.insert(CombRcByPtr(node.clone())));
+/// assert!(!visited.insert(CombRcByPtr(node.clone())));
+///
+/// // Equal, but a different allocation.
+/// assert!(visited.insert(CombRcByPtr(CombRc::new(1u32))));
+/// ```
+pub struct CombRcByPtr<T: Clone, P: CowPolicy = CloneOnWrite>(pub CombRc<T, P>);
+
+impl<T: Clone, P: CowPolicy> Clone for CombRcByPtr<T, P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Clone, P: CowPolicy> PartialEq for CombRcByPtr<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        CombRc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Eq for CombRcByPtr<T, P> {}
+
+impl<T: Clone, P: CowPolicy> PartialOrd for CombRcByPtr<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Ord for CombRcByPtr<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        CombRc::as_ptr(&self.0).cmp(&CombRc::as_ptr(&other.0))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Hash for CombRcByPtr<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        CombRc::as_ptr(&self.0).hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcByPtr<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CombRcByPtr")
+            .field(&CombRc::as_ptr(&self.0))
+            .field(&*self.0)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for CombRcByPtr<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        Self(value)
+    }
+}
@@ -0,0 +1,143 @@
+use core::cell::Cell;
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombRc, CowPolicy};
+
+/// A cell holding a [`CombRc`], which can be swapped through a shared reference.
+///
+/// Like [`Cell`], this is not thread-safe, and it never gives out references to the handle inside.
+/// [`CombRcCell::get`] clones the handle instead, which is as cheap as cloning a `CombRc`.
+/// For sharing a handle between threads, see `CombArcLock`, which needs the `std` feature.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcCell};
+///
+/// let slot = CombRcCell::new(CombRc::new(1u32));
+/// let alias = &slot;
+///
+/// let old = slot.replace(CombRc::new(2));
+/// assert_eq!(*old, 1);
+/// assert_eq!(*alias.get(), 2);
+/// ```
+pub struct CombRcCell<T: Clone, P: CowPolicy = CloneOnWrite> {
+    // This is only empty while a closure passed to `update` is running.
+    inner: Cell<Option<CombRc<T, P>>>
+}
+
+impl<T: Clone, P: CowPolicy> CombRcCell<T, P> {
+    /// Constructs a `CombRcCell`.
+    #[inline]
+    pub fn new(what: CombRc<T, P>) -> CombRcCell<T, P> {
+        Self {
+            inner: Cell::new(Some(what))
+        }
+    }
+
+    /// Get a clone of the handle in the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a closure passed to [`CombRcCell::update`] on the same cell.
+    pub fn get(&self) -> CombRc<T, P> {
+        let handle = self.take_handle();
+        let result = handle.clone();
+        self.inner.set(Some(handle));
+        result
+    }
+
+    /// Put a handle in the cell, dropping the previous handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a closure passed to [`CombRcCell::update`] on the same cell, since the
+    /// new handle would be overwritten when `update` finishes.
+    #[inline]
+    pub fn set(&self, what: CombRc<T, P>) {
+        drop(self.replace(what));
+    }
+
+    /// Put a handle in the cell, returning the previous handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a closure passed to [`CombRcCell::update`] on the same cell.
+    pub fn replace(&self, what: CombRc<T, P>) -> CombRc<T, P> {
+        self.inner.replace(Some(what)).expect(IN_UPDATE)
+    }
+
+    /// Edit the inner value with [`CombRc::make_mut`].
+    ///
+    /// Handles previously given out by [`CombRcCell::get`] are not changed, since they share the
+    /// inner value and it is cloned first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a closure passed to `update` on the same cell. The cell is left
+    /// untouched if `f` panics.
+    pub fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        let mut restore = Restore {
+            cell: self,
+            handle: Some(self.take_handle())
+        };
+        if let Some(handle) = restore.handle.as_mut() {
+            f(CombRc::make_mut(handle));
+        }
+    }
+
+    /// Get the handle in the cell.
+    #[inline]
+    pub fn into_inner(self) -> CombRc<T, P> {
+        self.inner.into_inner().expect(IN_UPDATE)
+    }
+
+    fn take_handle(&self) -> CombRc<T, P> {
+        self.inner.take().expect(IN_UPDATE)
+    }
+}
+
+const IN_UPDATE: &str = "a CombRcCell was accessed from its own update closure";
+
+// Puts the handle back even if the update closure panics.
+struct Restore<'a, T: Clone, P: CowPolicy> {
+    cell: &'a CombRcCell<T, P>,
+    handle: Option<CombRc<T, P>>
+}
+
+impl<T: Clone, P: CowPolicy> Drop for Restore<'_, T, P> {
+    fn drop(&mut self) {
+        self.cell.inner.set(self.handle.take());
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Clone for CombRcCell<T, P> {
+    /// Constructs a new cell with a clone of the handle in this cell.
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+impl<T: Clone + Default> Default for CombRcCell<T> {
+    fn default() -> Self {
+        Self::new(CombRc::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcCell<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.inner.take() {
+            Some(handle) => {
+                let result = f.debug_struct("CombRcCell").field("inner", &handle).finish();
+                self.inner.set(Some(handle));
+                result
+            },
+            None => f.debug_struct("CombRcCell").finish_non_exhaustive()
+        }
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for CombRcCell<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        CombRcCell::new(value)
+    }
+}
@@ -0,0 +1,183 @@
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::CombArc;
+
+/// A [`CombArc`] which can also borrow a `'static` value, only allocating once it is mutably
+/// borrowed.
+///
+/// This can be created in a const context with [`CombStatic::from_static`], such as for lookup
+/// tables which are part of the program. Reading and cloning it never allocates. The first mutable
+/// borrow clones the static value into a new allocation, after which it behaves like a `CombArc`.
+///
+/// Comparisons are by value, so a static-backed `CombStatic` is equal to an allocated one with an
+/// equal inner value.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombStatic;
+///
+/// static TABLE: [u32; 4] = [1, 2, 4, 8];
+/// static DEFAULT_TABLE: CombStatic<[u32; 4]> = CombStatic::from_static(&TABLE);
+///
+/// let mut table = DEFAULT_TABLE.clone();
+/// assert!(CombStatic::is_static(&table));
+///
+/// table[0] = 0;
+/// assert!(!CombStatic::is_static(&table));
+/// assert_eq!(TABLE, [1, 2, 4, 8]);
+/// ```
+pub struct CombStatic<T: Clone + 'static> {
+    inner: Inner<T>
+}
+
+enum Inner<T: Clone + 'static> {
+    Static(&'static T),
+    Shared(CombArc<T>)
+}
+
+impl<T: Clone + 'static> CombStatic<T> {
+    /// Constructs a `CombStatic` which borrows a `'static` value.
+    #[inline]
+    pub const fn from_static(what: &'static T) -> CombStatic<T> {
+        Self {
+            inner: Inner::Static(what)
+        }
+    }
+
+    /// Constructs a `CombStatic` which holds a new allocation.
+    #[inline]
+    pub fn new(what: T) -> CombStatic<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+
+    /// Constructs a `CombStatic` from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<T>) -> CombStatic<T> {
+        Self {
+            inner: Inner::Shared(what)
+        }
+    }
+
+    /// Returns `true` if this still borrows a `'static` value.
+    #[inline]
+    pub fn is_static(what: &CombStatic<T>) -> bool {
+        matches!(what.inner, Inner::Static(_))
+    }
+
+    /// Convert this into a `CombArc`, cloning the inner value into a new allocation if it is still
+    /// static.
+    #[inline]
+    pub fn into_comb(what: CombStatic<T>) -> CombArc<T> {
+        match what.inner {
+            Inner::Static(s) => CombArc::new(s.clone()),
+            Inner::Shared(s) => s
+        }
+    }
+
+    /// Get a mutable reference to the inner value.
+    ///
+    /// If this still borrows a `'static` value, it is cloned into a new allocation first.
+    /// Otherwise, this calls [`CombArc::make_mut`].
+    #[inline]
+    pub fn make_mut(what: &mut CombStatic<T>) -> &mut T {
+        if let Inner::Static(s) = what.inner {
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::check_clone::<T>();
+            what.inner = Inner::Shared(CombArc::new(s.clone()));
+        }
+        match &mut what.inner {
+            Inner::Static(_) => unreachable!("static values should have been cloned"),
+            Inner::Shared(s) => CombArc::make_mut(s)
+        }
+    }
+}
+
+impl<T: Clone + 'static> Clone for CombStatic<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: match &self.inner {
+                Inner::Static(s) => Inner::Static(s),
+                Inner::Shared(s) => Inner::Shared(s.clone())
+            }
+        }
+    }
+}
+
+impl<T: Clone + Default + 'static> Default for CombStatic<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + PartialEq + 'static> PartialEq for CombStatic<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Clone + Eq + 'static> Eq for CombStatic<T> {}
+
+impl<T: Clone + PartialOrd + 'static> PartialOrd for CombStatic<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: Clone + Ord + 'static> Ord for CombStatic<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: Clone + Hash + 'static> Hash for CombStatic<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug + 'static> core::fmt::Debug for CombStatic<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombStatic")
+            .field("inner", &**self)
+            .field("static", &Self::is_static(self))
+            .finish()
+    }
+}
+
+impl<T: core::fmt::Display + Clone + 'static> core::fmt::Display for CombStatic<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: Clone + 'static> From<CombArc<T>> for CombStatic<T> {
+    fn from(value: CombArc<T>) -> Self {
+        CombStatic::from_comb(value)
+    }
+}
+
+impl<T: Clone + 'static> From<Arc<T>> for CombStatic<T> {
+    fn from(value: Arc<T>) -> Self {
+        CombStatic::from_comb(CombArc::from_arc(value))
+    }
+}
+
+impl<T: Clone + 'static> core::ops::Deref for CombStatic<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match &self.inner {
+            Inner::Static(s) => s,
+            Inner::Shared(s) => s
+        }
+    }
+}
+
+impl<T: Clone + 'static> core::ops::DerefMut for CombStatic<T> {
+    /// This just calls [`CombStatic::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
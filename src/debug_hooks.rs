@@ -0,0 +1,140 @@
+use alloc::sync::Arc;
+use core::cell::Cell;
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+std::thread_local! {
+    static NO_CLONE_GUARDS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A guard which makes any copy-on-write clone on the current thread panic while it is alive.
+///
+/// This covers any clone made by mutably borrowing a shared [`CombArc`] or [`CombRc`], such as
+/// with `make_mut` or [`DerefMut`]. Mutably borrowing a unique reference is still allowed. Clones
+/// made explicitly, such as with [`Clone::clone`] on the inner value, are not affected.
+///
+/// Guards can be nested, and only affect the thread they were created on, so they cannot be sent
+/// to other threads.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use combarc::{CombArc, NoCloneGuard};
+///
+/// let mut a = CombArc::new(vec![1u32]);
+/// let b = a.clone();
+///
+/// let _guard = NoCloneGuard::new();
+/// a.push(2); // panics, since `a` is shared with `b`
+/// ```
+///
+/// [`CombArc`]: crate::CombArc
+/// [`CombRc`]: crate::CombRc
+/// [`DerefMut`]: core::ops::DerefMut
+pub struct NoCloneGuard {
+    not_send: PhantomData<*const ()>
+}
+
+impl NoCloneGuard {
+    /// Start disallowing copy-on-write clones on the current thread until the guard is dropped.
+    #[inline]
+    pub fn new() -> NoCloneGuard {
+        NO_CLONE_GUARDS.with(|g| g.set(g.get() + 1));
+        Self {
+            not_send: PhantomData
+        }
+    }
+}
+
+impl Default for NoCloneGuard {
+    /// This is the same as [`NoCloneGuard::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NoCloneGuard {
+    fn drop(&mut self) {
+        NO_CLONE_GUARDS.with(|g| g.set(g.get() - 1));
+    }
+}
+
+/// Call `f`, panicking if any copy-on-write clone happens on the current thread while it runs.
+///
+/// This just holds a [`NoCloneGuard`] while calling `f`.
+#[inline]
+pub fn no_clone_scope<R, F: FnOnce() -> R>(f: F) -> R {
+    let _guard = NoCloneGuard::new();
+    f()
+}
+
+/// The error returned when a copy-on-write clone would exceed the clone budget of a reference.
+///
+/// See `with_clone_budget`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CloneBudgetExceeded {
+    /// The number of clones the budget allowed.
+    pub budget: usize
+}
+
+impl core::fmt::Display for CloneBudgetExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "clone budget of {} copy-on-write clones exceeded", self.budget)
+    }
+}
+
+struct CloneBudget {
+    budget: usize,
+    used: AtomicUsize
+}
+
+/// The clone budget of a reference, if any, which is shared with its clones.
+#[derive(Clone, Default)]
+pub(crate) struct BudgetSlot {
+    budget: Option<Arc<CloneBudget>>
+}
+
+impl BudgetSlot {
+    pub(crate) fn new(budget: usize) -> BudgetSlot {
+        Self {
+            budget: Some(Arc::new(CloneBudget {
+                budget,
+                used: AtomicUsize::new(0)
+            }))
+        }
+    }
+
+    pub(crate) fn used(&self) -> Option<usize> {
+        self.budget.as_ref().map(|b| b.used.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn try_spend(&self) -> Result<(), CloneBudgetExceeded> {
+        let Some(b) = self.budget.as_ref() else {
+            return Ok(())
+        };
+        b.used.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| (used < b.budget).then_some(used + 1))
+            .map(|_| ())
+            .map_err(|_| CloneBudgetExceeded { budget: b.budget })
+    }
+}
+
+/// Called before a copy-on-write clone of a `T`.
+pub(crate) fn check_clone<T>() {
+    if NO_CLONE_GUARDS.with(Cell::get) != 0 {
+        panic!("a copy-on-write clone of `{}` happened while a NoCloneGuard was alive", core::any::type_name::<T>());
+    }
+}
+
+/// Called before a copy-on-write clone of a `T` from a reference with the given budget.
+pub(crate) fn before_clone<T>(budget: &BudgetSlot) {
+    check_clone::<T>();
+    spend_budget::<T>(budget);
+}
+
+/// Spends one copy-on-write clone of a `T` from the given budget, panicking if it is exhausted.
+pub(crate) fn spend_budget<T>(budget: &BudgetSlot) {
+    if let Err(e) = budget.try_spend() {
+        panic!("{} for `{}`", e, core::any::type_name::<T>());
+    }
+}
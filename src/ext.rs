@@ -0,0 +1,121 @@
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use crate::{CombArc, CombRc};
+
+/// Constructors for a [`CombArc`] or [`CombRc`] as methods on any value.
+///
+/// This is implemented for every type which implements [`Clone`], and is in the
+/// [`prelude`](crate::prelude).
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::prelude::*;
+///
+/// let value = vec![1u32].comb();
+/// let another_value: CombArc<Vec<u32>> = value.clone();
+/// assert!(CombArc::ptr_eq(&value, &another_value));
+///
+/// let value = vec![1u32].comb_rc();
+/// let another_value: CombRc<Vec<u32>> = value.clone();
+/// assert!(CombRc::ptr_eq(&value, &another_value));
+/// ```
+pub trait IntoComb: Clone + Sized {
+    /// Move this value into a new [`CombArc`].
+    ///
+    /// This is the same as [`CombArc::new`].
+    fn comb(self) -> CombArc<Self>;
+
+    /// Move this value into a new [`CombRc`].
+    ///
+    /// This is the same as [`CombRc::new`].
+    fn comb_rc(self) -> CombRc<Self>;
+}
+
+impl<T: Clone> IntoComb for T {
+    #[inline]
+    fn comb(self) -> CombArc<T> {
+        CombArc::new(self)
+    }
+
+    #[inline]
+    fn comb_rc(self) -> CombRc<T> {
+        CombRc::new(self)
+    }
+}
+
+/// Conversions from an [`Arc`] to a [`CombArc`] as methods.
+///
+/// This is in the [`prelude`](crate::prelude).
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::prelude::*;
+/// use std::sync::Arc;
+///
+/// let value = Arc::new(1u32);
+/// let comb = value.as_comb();
+/// assert!(std::ptr::eq(Arc::as_ptr(&value), CombArc::as_ptr(&comb)));
+/// ```
+///
+/// [`Arc`]: alloc::sync::Arc
+pub trait ArcExt<T: Clone> {
+    /// Convert to a `CombArc` without allocating.
+    ///
+    /// This is the same as [`CombArc::from_arc`].
+    fn into_comb(self) -> CombArc<T>;
+
+    /// Get a `CombArc` which shares this allocation, without allocating.
+    fn as_comb(&self) -> CombArc<T>;
+}
+
+impl<T: Clone> ArcExt<T> for Arc<T> {
+    #[inline]
+    fn into_comb(self) -> CombArc<T> {
+        CombArc::from_arc(self)
+    }
+
+    #[inline]
+    fn as_comb(&self) -> CombArc<T> {
+        CombArc::from_arc(self.clone())
+    }
+}
+
+/// Conversions from an [`Rc`] to a [`CombRc`] as methods.
+///
+/// This is in the [`prelude`](crate::prelude).
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::prelude::*;
+/// use std::rc::Rc;
+///
+/// let value = Rc::new(1u32);
+/// let comb = value.as_comb();
+/// assert!(std::ptr::eq(Rc::as_ptr(&value), CombRc::as_ptr(&comb)));
+/// ```
+///
+/// [`Rc`]: alloc::rc::Rc
+pub trait RcExt<T: Clone> {
+    /// Convert to a `CombRc` without allocating.
+    ///
+    /// This is the same as [`CombRc::from_rc`].
+    fn into_comb(self) -> CombRc<T>;
+
+    /// Get a `CombRc` which shares this allocation, without allocating.
+    fn as_comb(&self) -> CombRc<T>;
+}
+
+impl<T: Clone> RcExt<T> for Rc<T> {
+    #[inline]
+    fn into_comb(self) -> CombRc<T> {
+        CombRc::from_rc(self)
+    }
+
+    #[inline]
+    fn as_comb(&self) -> CombRc<T> {
+        CombRc::from_rc(self.clone())
+    }
+}
@@ -0,0 +1,54 @@
+//! Implementations which require the unstable `fn_traits` and `unboxed_closures` features.
+//!
+//! A closure which mutates what it captured can only be called through a reference once its kind
+//! is known, such as by returning it as an `impl FnMut`. Otherwise, calling it is assumed to need
+//! `Fn`.
+
+use core::marker::Tuple;
+use crate::{CombArc, CombRc, CowPolicy};
+
+impl<A: Tuple, T: Clone + FnMut<A>, P: CowPolicy> FnOnce<A> for CombArc<T, P> {
+    type Output = T::Output;
+
+    /// This goes through [`CombArc::make_mut`], so if the closure is shared, it is cloned first.
+    extern "rust-call" fn call_once(mut self, arguments: A) -> T::Output {
+        CombArc::make_mut(&mut self).call_mut(arguments)
+    }
+}
+
+impl<A: Tuple, T: Clone + FnMut<A>, P: CowPolicy> FnMut<A> for CombArc<T, P> {
+    /// This goes through [`CombArc::make_mut`], so if the closure is shared, it is cloned first. See
+    /// [`CombArc::call_mut`].
+    extern "rust-call" fn call_mut(&mut self, arguments: A) -> T::Output {
+        CombArc::make_mut(self).call_mut(arguments)
+    }
+}
+
+impl<A: Tuple, T: Clone + Fn<A>, P: CowPolicy> Fn<A> for CombArc<T, P> {
+    extern "rust-call" fn call(&self, arguments: A) -> T::Output {
+        (**self).call(arguments)
+    }
+}
+
+impl<A: Tuple, T: Clone + FnMut<A>, P: CowPolicy> FnOnce<A> for CombRc<T, P> {
+    type Output = T::Output;
+
+    /// This goes through [`CombRc::make_mut`], so if the closure is shared, it is cloned first.
+    extern "rust-call" fn call_once(mut self, arguments: A) -> T::Output {
+        CombRc::make_mut(&mut self).call_mut(arguments)
+    }
+}
+
+impl<A: Tuple, T: Clone + FnMut<A>, P: CowPolicy> FnMut<A> for CombRc<T, P> {
+    /// This goes through [`CombRc::make_mut`], so if the closure is shared, it is cloned first. See
+    /// [`CombRc::call_mut`].
+    extern "rust-call" fn call_mut(&mut self, arguments: A) -> T::Output {
+        CombRc::make_mut(self).call_mut(arguments)
+    }
+}
+
+impl<A: Tuple, T: Clone + Fn<A>, P: CowPolicy> Fn<A> for CombRc<T, P> {
+    extern "rust-call" fn call(&self, arguments: A) -> T::Output {
+        (**self).call(arguments)
+    }
+}
@@ -0,0 +1,244 @@
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::{CombArc, CombRc};
+
+/// A read-only [`CombArc`], which cannot be mutably borrowed.
+///
+/// This can be cloned and stored like a `CombArc`, but there is no [`DerefMut`] or `make_mut`, so
+/// code given this can never cause a copy-on-write clone. Since the inner value can never change
+/// through it, it can be hashed and used as a key. Use [`FrozenCombArc::thaw`] to get a `CombArc`
+/// back.
+///
+/// Converting between this and `CombArc` does not clone or allocate.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, FrozenCombArc};
+///
+/// let state = CombArc::new(vec![1u32, 2, 3]);
+/// let frozen = CombArc::freeze(state.clone());
+/// assert_eq!(frozen.len(), 3);
+///
+/// let thawed = FrozenCombArc::thaw(frozen);
+/// assert!(CombArc::ptr_eq(&state, &thawed));
+/// ```
+///
+/// It cannot be mutably borrowed:
+///
+/// ```rust,compile_fail
+/// use combarc::CombArc;
+///
+/// let mut frozen = CombArc::freeze(CombArc::new(vec![1u32]));
+/// frozen.push(2);
+/// ```
+///
+/// [`DerefMut`]: core::ops::DerefMut
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct FrozenCombArc<T: Clone> {
+    inner: Arc<T>
+}
+
+impl<T: Clone> FrozenCombArc<T> {
+    pub(crate) fn from_arc(inner: Arc<T>) -> FrozenCombArc<T> {
+        Self {
+            inner
+        }
+    }
+
+    /// Convert this back into a mutable `CombArc`.
+    ///
+    /// This does not clone, so it still shares the inner value with any other references.
+    #[inline]
+    pub fn thaw(what: FrozenCombArc<T>) -> CombArc<T> {
+        CombArc::from_arc(what.inner)
+    }
+
+    /// Returns `true` if both `FrozenCombArc`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(a: &FrozenCombArc<T>, b: &FrozenCombArc<T>) -> bool {
+        Arc::ptr_eq(&a.inner, &b.inner)
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for FrozenCombArc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Clone + Eq> Eq for FrozenCombArc<T> {}
+
+impl<T: Clone + PartialOrd> PartialOrd for FrozenCombArc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.inner.partial_cmp(&other.inner)
+    }
+}
+
+impl<T: Clone + Ord> Ord for FrozenCombArc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl<T: Clone + Hash> Hash for FrozenCombArc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug> core::fmt::Debug for FrozenCombArc<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FrozenCombArc")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: core::fmt::Display + Clone> core::fmt::Display for FrozenCombArc<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: Clone> From<FrozenCombArc<T>> for CombArc<T> {
+    fn from(value: FrozenCombArc<T>) -> Self {
+        FrozenCombArc::thaw(value)
+    }
+}
+
+impl<T: Clone> From<CombArc<T>> for FrozenCombArc<T> {
+    fn from(value: CombArc<T>) -> Self {
+        CombArc::freeze(value)
+    }
+}
+
+impl<T: Clone> core::ops::Deref for FrozenCombArc<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// A read-only [`CombRc`], which cannot be mutably borrowed.
+///
+/// This can be cloned and stored like a `CombRc`, but there is no [`DerefMut`] or `make_mut`, so
+/// code given this can never cause a copy-on-write clone. Since the inner value can never change
+/// through it, it can be hashed and used as a key. Use [`FrozenCombRc::thaw`] to get a `CombRc`
+/// back.
+///
+/// Converting between this and `CombRc` does not clone or allocate.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, FrozenCombRc};
+///
+/// let state = CombRc::new(vec![1u32, 2, 3]);
+/// let frozen = CombRc::freeze(state.clone());
+/// assert_eq!(frozen.len(), 3);
+///
+/// let thawed = FrozenCombRc::thaw(frozen);
+/// assert!(CombRc::ptr_eq(&state, &thawed));
+/// ```
+///
+/// It cannot be mutably borrowed:
+///
+/// ```rust,compile_fail
+/// use combarc::CombRc;
+///
+/// let mut frozen = CombRc::freeze(CombRc::new(vec![1u32]));
+/// frozen.push(2);
+/// ```
+///
+/// [`DerefMut`]: core::ops::DerefMut
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct FrozenCombRc<T: Clone> {
+    inner: Rc<T>
+}
+
+impl<T: Clone> FrozenCombRc<T> {
+    pub(crate) fn from_rc(inner: Rc<T>) -> FrozenCombRc<T> {
+        Self {
+            inner
+        }
+    }
+
+    /// Convert this back into a mutable `CombRc`.
+    ///
+    /// This does not clone, so it still shares the inner value with any other references.
+    #[inline]
+    pub fn thaw(what: FrozenCombRc<T>) -> CombRc<T> {
+        CombRc::from_rc(what.inner)
+    }
+
+    /// Returns `true` if both `FrozenCombRc`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(a: &FrozenCombRc<T>, b: &FrozenCombRc<T>) -> bool {
+        Rc::ptr_eq(&a.inner, &b.inner)
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for FrozenCombRc<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Clone + Eq> Eq for FrozenCombRc<T> {}
+
+impl<T: Clone + PartialOrd> PartialOrd for FrozenCombRc<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.inner.partial_cmp(&other.inner)
+    }
+}
+
+impl<T: Clone + Ord> Ord for FrozenCombRc<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl<T: Clone + Hash> Hash for FrozenCombRc<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug> core::fmt::Debug for FrozenCombRc<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FrozenCombRc")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: core::fmt::Display + Clone> core::fmt::Display for FrozenCombRc<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: Clone> From<FrozenCombRc<T>> for CombRc<T> {
+    fn from(value: FrozenCombRc<T>) -> Self {
+        FrozenCombRc::thaw(value)
+    }
+}
+
+impl<T: Clone> From<CombRc<T>> for FrozenCombRc<T> {
+    fn from(value: CombRc<T>) -> Self {
+        CombRc::freeze(value)
+    }
+}
+
+impl<T: Clone> core::ops::Deref for FrozenCombRc<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
@@ -0,0 +1,166 @@
+use core::fmt::Formatter;
+use core::hash::{BuildHasher, Hash, Hasher};
+use crate::CombArc;
+use std::collections::hash_map::RandomState;
+use std::sync::OnceLock;
+
+/// A [`CombArc`] which caches the hash of its inner value.
+///
+/// This is for using large values as keys, such as in a [`HashMap`]. The hash is computed with the
+/// stored [`BuildHasher`] the first time it is needed, and [`Hash`] just writes the cached hash.
+/// Mutably borrowing the inner value discards the cached hash, so it is computed again if needed.
+///
+/// By default, the hasher is a [`RandomState`] whose keys are chosen once per process, so the
+/// cached hashes are not predictable and a [`HashMap`] keeps its HashDoS protection. A different
+/// hasher can be given with [`CombArcHashed::with_hasher`]. Keys of the same map should use equal
+/// hashers.
+///
+/// Since this is hashed differently from the inner value, it cannot be looked up by a reference to
+/// the inner value, so it does not implement [`Borrow`].
+///
+/// Comparisons are by value, but they are faster if both sides share an allocation. Cached hashes
+/// are not compared, since each side may have been hashed with a different hasher.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArcHashed;
+/// use std::collections::HashMap;
+///
+/// let key = CombArcHashed::new("a large key".to_owned());
+/// let mut map = HashMap::new();
+/// map.insert(key.clone(), 1);
+/// assert_eq!(map.get(&key), Some(&1));
+/// ```
+///
+/// [`HashMap`]: std::collections::HashMap
+/// [`Borrow`]: core::borrow::Borrow
+pub struct CombArcHashed<T: Clone + Hash, S: BuildHasher = RandomState> {
+    inner: CombArc<T>,
+    hash: OnceLock<u64>,
+    hasher: S
+}
+
+/// Get the process-wide [`RandomState`] used by [`CombArcHashed::new`].
+fn random_state() -> RandomState {
+    static STATE: OnceLock<RandomState> = OnceLock::new();
+    STATE.get_or_init(RandomState::new).clone()
+}
+
+impl<T: Clone + Hash> CombArcHashed<T> {
+    /// Constructs a `CombArcHashed` with the process-wide [`RandomState`].
+    #[inline]
+    pub fn new(what: T) -> CombArcHashed<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+
+    /// Constructs a `CombArcHashed` from an already created `CombArc` with the process-wide
+    /// [`RandomState`].
+    #[inline]
+    pub fn from_comb(what: CombArc<T>) -> CombArcHashed<T> {
+        Self::from_comb_with_hasher(what, random_state())
+    }
+}
+
+impl<T: Clone + Hash, S: BuildHasher> CombArcHashed<T, S> {
+    /// Constructs a `CombArcHashed` which hashes with `hasher`.
+    #[inline]
+    pub fn with_hasher(what: T, hasher: S) -> CombArcHashed<T, S> {
+        Self::from_comb_with_hasher(CombArc::new(what), hasher)
+    }
+
+    /// Constructs a `CombArcHashed` from an already created `CombArc` which hashes with `hasher`.
+    #[inline]
+    pub fn from_comb_with_hasher(what: CombArc<T>, hasher: S) -> CombArcHashed<T, S> {
+        Self {
+            inner: what,
+            hash: OnceLock::new(),
+            hasher
+        }
+    }
+
+    /// Get the inner `CombArc`, discarding the cached hash.
+    #[inline]
+    pub fn into_comb(what: CombArcHashed<T, S>) -> CombArc<T> {
+        what.inner
+    }
+
+    /// Get the inner `CombArc` value.
+    #[inline]
+    pub fn get_comb(what: &CombArcHashed<T, S>) -> &CombArc<T> {
+        &what.inner
+    }
+
+    /// Get the hasher.
+    #[inline]
+    pub fn hasher(what: &CombArcHashed<T, S>) -> &S {
+        &what.hasher
+    }
+
+    /// Get the hash of the inner value with the hasher, computing it if it is not cached.
+    #[inline]
+    pub fn cached_hash(what: &CombArcHashed<T, S>) -> u64 {
+        *what.hash.get_or_init(|| what.hasher.hash_one(&*what.inner))
+    }
+
+    /// Get a mutable reference to the inner value with [`CombArc::make_mut`], discarding the cached
+    /// hash.
+    #[inline]
+    pub fn make_mut(what: &mut CombArcHashed<T, S>) -> &mut T {
+        what.hash.take();
+        CombArc::make_mut(&mut what.inner)
+    }
+}
+
+impl<T: Clone + Hash, S: BuildHasher + Clone> Clone for CombArcHashed<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            hash: self.hash.clone(),
+            hasher: self.hasher.clone()
+        }
+    }
+}
+
+impl<T: Clone + Hash + PartialEq, S: BuildHasher> PartialEq for CombArcHashed<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        CombArc::ptr_eq(&self.inner, &other.inner) || *self.inner == *other.inner
+    }
+}
+
+impl<T: Clone + Hash + Eq, S: BuildHasher> Eq for CombArcHashed<T, S> {}
+
+impl<T: Clone + Hash, S: BuildHasher> Hash for CombArcHashed<T, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(Self::cached_hash(self))
+    }
+}
+
+impl<T: Clone + Hash + core::fmt::Debug, S: BuildHasher> core::fmt::Debug for CombArcHashed<T, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcHashed")
+            .field("inner", &self.inner)
+            .field("hash", &self.hash.get())
+            .finish()
+    }
+}
+
+impl<T: Clone + Hash> From<CombArc<T>> for CombArcHashed<T> {
+    fn from(value: CombArc<T>) -> Self {
+        CombArcHashed::from_comb(value)
+    }
+}
+
+impl<T: Clone + Hash, S: BuildHasher> core::ops::Deref for CombArcHashed<T, S> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone + Hash, S: BuildHasher> core::ops::DerefMut for CombArcHashed<T, S> {
+    /// This just calls [`CombArcHashed::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
@@ -0,0 +1,350 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// An undo/redo history of a [`CombArc`].
+///
+/// Each edit pushes a reference to the previous value onto the undo stack, so a snapshot costs as
+/// much as cloning a `CombArc`, and values are only cloned when they are edited while shared.
+///
+/// The undo stack holds at most [`CombHistory::limit`] snapshots, evicting the oldest ones. It is
+/// unlimited by default.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombHistory;
+///
+/// let mut document = CombHistory::new(vec!["hello"]);
+/// document.edit(|lines| lines.push("world"));
+/// assert_eq!(**document.current(), ["hello", "world"]);
+///
+/// assert!(document.undo());
+/// assert_eq!(**document.current(), ["hello"]);
+///
+/// assert!(document.redo());
+/// assert_eq!(**document.current(), ["hello", "world"]);
+/// ```
+pub struct CombHistory<T: Clone, P: CowPolicy = CloneOnWrite> {
+    current: CombArc<T, P>,
+    undo: VecDeque<CombArc<T, P>>,
+    redo: Vec<CombArc<T, P>>,
+    limit: usize
+}
+
+impl<T: Clone> CombHistory<T> {
+    /// Constructs a `CombHistory` with no snapshots.
+    #[inline]
+    pub fn new(what: T) -> CombHistory<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombHistory<T, P> {
+    /// Constructs a `CombHistory` with no snapshots from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<T, P>) -> CombHistory<T, P> {
+        Self {
+            current: what,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: usize::MAX
+        }
+    }
+
+    /// Get the current value.
+    #[inline]
+    pub fn current(&self) -> &CombArc<T, P> {
+        &self.current
+    }
+
+    /// Get the current value, discarding the history.
+    #[inline]
+    pub fn into_current(self) -> CombArc<T, P> {
+        self.current
+    }
+
+    /// Edit the current value, pushing the previous value onto the undo stack.
+    ///
+    /// This clears the redo stack.
+    pub fn edit<F: FnOnce(&mut T)>(&mut self, f: F) {
+        let previous = self.current.clone();
+        f(CombArc::make_mut(&mut self.current));
+        self.push_undo(previous);
+        self.redo.clear();
+    }
+
+    /// Go back to the value before the last edit, returning `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop_back() {
+            Some(previous) => {
+                self.redo.push(core::mem::replace(&mut self.current, previous));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Go forward to the value before the last undo, returning `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                let previous = core::mem::replace(&mut self.current, next);
+                self.push_undo(previous);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Returns `true` if there is anything to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is anything to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Get the maximum number of snapshots on the undo stack.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Set the maximum number of snapshots on the undo stack, evicting the oldest ones if there are
+    /// too many.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Remove all snapshots, keeping the current value.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, what: CombArc<T, P>) {
+        if self.limit == 0 {
+            return
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(what);
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Clone for CombHistory<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            undo: self.undo.clone(),
+            redo: self.redo.clone(),
+            limit: self.limit
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombHistory<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombHistory<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombHistory")
+            .field("current", &self.current)
+            .field("undo", &self.undo.len())
+            .field("redo", &self.redo.len())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for CombHistory<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        CombHistory::from_comb(value)
+    }
+}
+
+/// An undo/redo history of a [`CombRc`].
+///
+/// Each edit pushes a reference to the previous value onto the undo stack, so a snapshot costs as
+/// much as cloning a `CombRc`, and values are only cloned when they are edited while shared.
+///
+/// The undo stack holds at most [`CombRcHistory::limit`] snapshots, evicting the oldest ones. It is
+/// unlimited by default.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRcHistory;
+///
+/// let mut document = CombRcHistory::new(vec!["hello"]);
+/// document.edit(|lines| lines.push("world"));
+/// assert_eq!(**document.current(), ["hello", "world"]);
+///
+/// assert!(document.undo());
+/// assert_eq!(**document.current(), ["hello"]);
+///
+/// assert!(document.redo());
+/// assert_eq!(**document.current(), ["hello", "world"]);
+/// ```
+pub struct CombRcHistory<T: Clone, P: CowPolicy = CloneOnWrite> {
+    current: CombRc<T, P>,
+    undo: VecDeque<CombRc<T, P>>,
+    redo: Vec<CombRc<T, P>>,
+    limit: usize
+}
+
+impl<T: Clone> CombRcHistory<T> {
+    /// Constructs a `CombRcHistory` with no snapshots.
+    #[inline]
+    pub fn new(what: T) -> CombRcHistory<T> {
+        Self::from_comb(CombRc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombRcHistory<T, P> {
+    /// Constructs a `CombRcHistory` with no snapshots from an already created `CombRc`.
+    #[inline]
+    pub fn from_comb(what: CombRc<T, P>) -> CombRcHistory<T, P> {
+        Self {
+            current: what,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: usize::MAX
+        }
+    }
+
+    /// Get the current value.
+    #[inline]
+    pub fn current(&self) -> &CombRc<T, P> {
+        &self.current
+    }
+
+    /// Get the current value, discarding the history.
+    #[inline]
+    pub fn into_current(self) -> CombRc<T, P> {
+        self.current
+    }
+
+    /// Edit the current value, pushing the previous value onto the undo stack.
+    ///
+    /// This clears the redo stack.
+    pub fn edit<F: FnOnce(&mut T)>(&mut self, f: F) {
+        let previous = self.current.clone();
+        f(CombRc::make_mut(&mut self.current));
+        self.push_undo(previous);
+        self.redo.clear();
+    }
+
+    /// Go back to the value before the last edit, returning `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop_back() {
+            Some(previous) => {
+                self.redo.push(core::mem::replace(&mut self.current, previous));
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Go forward to the value before the last undo, returning `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                let previous = core::mem::replace(&mut self.current, next);
+                self.push_undo(previous);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Returns `true` if there is anything to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is anything to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Get the maximum number of snapshots on the undo stack.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Set the maximum number of snapshots on the undo stack, evicting the oldest ones if there are
+    /// too many.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Remove all snapshots, keeping the current value.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, what: CombRc<T, P>) {
+        if self.limit == 0 {
+            return
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(what);
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Clone for CombRcHistory<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            undo: self.undo.clone(),
+            redo: self.redo.clone(),
+            limit: self.limit
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombRcHistory<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcHistory<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombRcHistory")
+            .field("current", &self.current)
+            .field("undo", &self.undo.len())
+            .field("redo", &self.redo.len())
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for CombRcHistory<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        CombRcHistory::from_comb(value)
+    }
+}
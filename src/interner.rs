@@ -0,0 +1,220 @@
+use alloc::rc;
+use alloc::sync;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+use crate::{CombArc, CombRc};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+
+/// How many entries can be stored before they are purged for the first time.
+const MINIMUM_PURGE_THRESHOLD: usize = 16;
+
+/// An interning pool which gives out [`CombArc`]s that share one allocation per distinct value.
+///
+/// The pool only holds weak references, so it never keeps a value alive by itself. Entries whose
+/// values were dropped are removed by [`CombArcInterner::purge`], which is also done periodically
+/// when interning new values.
+///
+/// Mutating an interned `CombArc` never changes the value seen by the pool: if it is shared, it is
+/// cloned as usual, and if it is unique, it is moved to a new allocation which dissociates the
+/// pool's weak reference.
+pub struct CombArcInterner<T: Clone + Eq + Hash> {
+    entries: HashMap<u64, Vec<sync::Weak<T>>>,
+    hasher: RandomState,
+    stored: usize,
+    next_purge: usize
+}
+
+impl<T: Clone + Eq + Hash> CombArcInterner<T> {
+    /// Constructs an empty `CombArcInterner`.
+    #[inline]
+    pub fn new() -> CombArcInterner<T> {
+        Self {
+            entries: HashMap::new(),
+            hasher: RandomState::new(),
+            stored: 0,
+            next_purge: MINIMUM_PURGE_THRESHOLD
+        }
+    }
+
+    /// Get a `CombArc` for `value`.
+    ///
+    /// If an equal value is already interned and alive, a reference to it is returned and `value`
+    /// is dropped. Otherwise, `value` is moved into a new allocation and interned.
+    pub fn intern(&mut self, value: T) -> CombArc<T> {
+        let hash = self.hasher.hash_one(&value);
+        if let Some(handle) = self.find(hash, &value) {
+            return handle
+        }
+        let handle = CombArc::new(value);
+        self.insert(hash, &handle);
+        handle
+    }
+
+    /// Get a `CombArc` for `value`, cloning it only if it is not already interned.
+    ///
+    /// If an equal value is already interned and alive, a reference to it is returned. Otherwise,
+    /// `value` is cloned into a new allocation and interned.
+    pub fn intern_ref(&mut self, value: &T) -> CombArc<T> {
+        let hash = self.hasher.hash_one(value);
+        if let Some(handle) = self.find(hash, value) {
+            return handle
+        }
+        let handle = CombArc::new(value.clone());
+        self.insert(hash, &handle);
+        handle
+    }
+
+    /// Get the number of interned values which are still alive.
+    pub fn len(&self) -> usize {
+        self.entries.values().flatten().filter(|w| w.strong_count() > 0).count()
+    }
+
+    /// Returns `true` if no interned values are still alive.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all entries whose values were dropped.
+    pub fn purge(&mut self) {
+        self.entries.retain(|_, bucket| {
+            bucket.retain(|w| w.strong_count() > 0);
+            !bucket.is_empty()
+        });
+        self.stored = self.entries.values().map(Vec::len).sum();
+    }
+
+    fn find(&mut self, hash: u64, value: &T) -> Option<CombArc<T>> {
+        let bucket = self.entries.get_mut(&hash)?;
+        let before = bucket.len();
+        bucket.retain(|w| w.strong_count() > 0);
+        self.stored -= before - bucket.len();
+        bucket.iter()
+            .filter_map(sync::Weak::upgrade)
+            .find(|arc| arc.as_ref() == value)
+            .map(CombArc::from_arc)
+    }
+
+    fn insert(&mut self, hash: u64, handle: &CombArc<T>) {
+        if self.stored >= self.next_purge {
+            self.purge();
+            self.next_purge = (self.stored * 2).max(MINIMUM_PURGE_THRESHOLD);
+        }
+        self.entries.entry(hash).or_default().push(CombArc::downgrade(handle));
+        self.stored += 1;
+    }
+}
+
+impl<T: Clone + Eq + Hash> Default for CombArcInterner<T> {
+    /// Constructs an empty `CombArcInterner`.
+    ///
+    /// This is the same as [`CombArcInterner::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An interning pool which gives out [`CombRc`]s that share one allocation per distinct value.
+///
+/// The pool only holds weak references, so it never keeps a value alive by itself. Entries whose
+/// values were dropped are removed by [`CombRcInterner::purge`], which is also done periodically
+/// when interning new values.
+///
+/// Mutating an interned `CombRc` never changes the value seen by the pool: if it is shared, it is
+/// cloned as usual, and if it is unique, it is moved to a new allocation which dissociates the
+/// pool's weak reference.
+pub struct CombRcInterner<T: Clone + Eq + Hash> {
+    entries: HashMap<u64, Vec<rc::Weak<T>>>,
+    hasher: RandomState,
+    stored: usize,
+    next_purge: usize
+}
+
+impl<T: Clone + Eq + Hash> CombRcInterner<T> {
+    /// Constructs an empty `CombRcInterner`.
+    #[inline]
+    pub fn new() -> CombRcInterner<T> {
+        Self {
+            entries: HashMap::new(),
+            hasher: RandomState::new(),
+            stored: 0,
+            next_purge: MINIMUM_PURGE_THRESHOLD
+        }
+    }
+
+    /// Get a `CombRc` for `value`.
+    ///
+    /// If an equal value is already interned and alive, a reference to it is returned and `value`
+    /// is dropped. Otherwise, `value` is moved into a new allocation and interned.
+    pub fn intern(&mut self, value: T) -> CombRc<T> {
+        let hash = self.hasher.hash_one(&value);
+        if let Some(handle) = self.find(hash, &value) {
+            return handle
+        }
+        let handle = CombRc::new(value);
+        self.insert(hash, &handle);
+        handle
+    }
+
+    /// Get a `CombRc` for `value`, cloning it only if it is not already interned.
+    ///
+    /// If an equal value is already interned and alive, a reference to it is returned. Otherwise,
+    /// `value` is cloned into a new allocation and interned.
+    pub fn intern_ref(&mut self, value: &T) -> CombRc<T> {
+        let hash = self.hasher.hash_one(value);
+        if let Some(handle) = self.find(hash, value) {
+            return handle
+        }
+        let handle = CombRc::new(value.clone());
+        self.insert(hash, &handle);
+        handle
+    }
+
+    /// Get the number of interned values which are still alive.
+    pub fn len(&self) -> usize {
+        self.entries.values().flatten().filter(|w| w.strong_count() > 0).count()
+    }
+
+    /// Returns `true` if no interned values are still alive.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all entries whose values were dropped.
+    pub fn purge(&mut self) {
+        self.entries.retain(|_, bucket| {
+            bucket.retain(|w| w.strong_count() > 0);
+            !bucket.is_empty()
+        });
+        self.stored = self.entries.values().map(Vec::len).sum();
+    }
+
+    fn find(&mut self, hash: u64, value: &T) -> Option<CombRc<T>> {
+        let bucket = self.entries.get_mut(&hash)?;
+        let before = bucket.len();
+        bucket.retain(|w| w.strong_count() > 0);
+        self.stored -= before - bucket.len();
+        bucket.iter()
+            .filter_map(rc::Weak::upgrade)
+            .find(|rc| rc.as_ref() == value)
+            .map(CombRc::from_rc)
+    }
+
+    fn insert(&mut self, hash: u64, handle: &CombRc<T>) {
+        if self.stored >= self.next_purge {
+            self.purge();
+            self.next_purge = (self.stored * 2).max(MINIMUM_PURGE_THRESHOLD);
+        }
+        self.entries.entry(hash).or_default().push(CombRc::downgrade(handle));
+        self.stored += 1;
+    }
+}
+
+impl<T: Clone + Eq + Hash> Default for CombRcInterner<T> {
+    /// Constructs an empty `CombRcInterner`.
+    ///
+    /// This is the same as [`CombRcInterner::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
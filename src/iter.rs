@@ -0,0 +1,213 @@
+use core::fmt::Formatter;
+use core::iter::FusedIterator;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// An iterator which shares its state in a [`CombArc`] until it is advanced.
+///
+/// **Cloning a `CombIter` forks the iterator.** Clones share the same state until one of them is
+/// advanced, at which point that one clones the state with [`CombArc::make_mut`] and continues on
+/// its own. Each clone then produces the rest of the items independently of the others.
+///
+/// `CombArc` itself cannot implement [`Iterator`], since it would conflict with its
+/// [`IntoIterator`] implementation, so this wraps it instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombIter;
+///
+/// let mut preview = CombIter::new(1..=4);
+/// assert_eq!(preview.next(), Some(1));
+///
+/// let real = preview.clone();
+/// assert_eq!(preview.collect::<Vec<_>>(), [2, 3, 4]);
+/// assert_eq!(real.collect::<Vec<_>>(), [2, 3, 4]);
+/// ```
+pub struct CombIter<I: Iterator + Clone, P: CowPolicy = CloneOnWrite> {
+    inner: CombArc<I, P>
+}
+
+impl<I: Iterator + Clone> CombIter<I> {
+    /// Constructs a `CombIter`.
+    #[inline]
+    pub fn new(what: I) -> CombIter<I> {
+        Self::from_comb(CombArc::new(what))
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> CombIter<I, P> {
+    /// Constructs a `CombIter` from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<I, P>) -> CombIter<I, P> {
+        Self {
+            inner: what
+        }
+    }
+
+    /// Get the inner `CombArc`.
+    #[inline]
+    pub fn get_comb(&self) -> &CombArc<I, P> {
+        &self.inner
+    }
+
+    /// Get the inner `CombArc`, consuming the `CombIter`.
+    #[inline]
+    pub fn into_comb(self) -> CombArc<I, P> {
+        self.inner
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> Iterator for CombIter<I, P> {
+    type Item = I::Item;
+
+    /// Advances the iterator, cloning its state first if it is shared.
+    fn next(&mut self) -> Option<I::Item> {
+        CombArc::make_mut(&mut self.inner).next()
+    }
+
+    /// This does not clone.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: DoubleEndedIterator + Clone, P: CowPolicy> DoubleEndedIterator for CombIter<I, P> {
+    /// Advances the iterator from the back, cloning its state first if it is shared.
+    fn next_back(&mut self) -> Option<I::Item> {
+        CombArc::make_mut(&mut self.inner).next_back()
+    }
+}
+
+impl<I: ExactSizeIterator + Clone, P: CowPolicy> ExactSizeIterator for CombIter<I, P> {
+    /// This does not clone.
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<I: FusedIterator + Clone, P: CowPolicy> FusedIterator for CombIter<I, P> {}
+
+impl<I: Iterator + Clone, P: CowPolicy> Clone for CombIter<I, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone()
+        }
+    }
+}
+
+impl<I: Iterator + Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombIter<I, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CombIter").field(&self.inner).finish()
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> From<CombArc<I, P>> for CombIter<I, P> {
+    fn from(value: CombArc<I, P>) -> Self {
+        CombIter::from_comb(value)
+    }
+}
+
+/// An iterator which shares its state in a [`CombRc`] until it is advanced.
+///
+/// **Cloning a `CombRcIter` forks the iterator.** Clones share the same state until one of them is
+/// advanced, at which point that one clones the state with [`CombRc::make_mut`] and continues on
+/// its own. Each clone then produces the rest of the items independently of the others.
+///
+/// `CombRc` itself cannot implement [`Iterator`], since it would conflict with its
+/// [`IntoIterator`] implementation, so this wraps it instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRcIter;
+///
+/// let mut preview = CombRcIter::new(1..=4);
+/// assert_eq!(preview.next(), Some(1));
+///
+/// let real = preview.clone();
+/// assert_eq!(preview.collect::<Vec<_>>(), [2, 3, 4]);
+/// assert_eq!(real.collect::<Vec<_>>(), [2, 3, 4]);
+/// ```
+pub struct CombRcIter<I: Iterator + Clone, P: CowPolicy = CloneOnWrite> {
+    inner: CombRc<I, P>
+}
+
+impl<I: Iterator + Clone> CombRcIter<I> {
+    /// Constructs a `CombRcIter`.
+    #[inline]
+    pub fn new(what: I) -> CombRcIter<I> {
+        Self::from_comb(CombRc::new(what))
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> CombRcIter<I, P> {
+    /// Constructs a `CombRcIter` from an already created `CombRc`.
+    #[inline]
+    pub fn from_comb(what: CombRc<I, P>) -> CombRcIter<I, P> {
+        Self {
+            inner: what
+        }
+    }
+
+    /// Get the inner `CombRc`.
+    #[inline]
+    pub fn get_comb(&self) -> &CombRc<I, P> {
+        &self.inner
+    }
+
+    /// Get the inner `CombRc`, consuming the `CombRcIter`.
+    #[inline]
+    pub fn into_comb(self) -> CombRc<I, P> {
+        self.inner
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> Iterator for CombRcIter<I, P> {
+    type Item = I::Item;
+
+    /// Advances the iterator, cloning its state first if it is shared.
+    fn next(&mut self) -> Option<I::Item> {
+        CombRc::make_mut(&mut self.inner).next()
+    }
+
+    /// This does not clone.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: DoubleEndedIterator + Clone, P: CowPolicy> DoubleEndedIterator for CombRcIter<I, P> {
+    /// Advances the iterator from the back, cloning its state first if it is shared.
+    fn next_back(&mut self) -> Option<I::Item> {
+        CombRc::make_mut(&mut self.inner).next_back()
+    }
+}
+
+impl<I: ExactSizeIterator + Clone, P: CowPolicy> ExactSizeIterator for CombRcIter<I, P> {
+    /// This does not clone.
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl<I: FusedIterator + Clone, P: CowPolicy> FusedIterator for CombRcIter<I, P> {}
+
+impl<I: Iterator + Clone, P: CowPolicy> Clone for CombRcIter<I, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone()
+        }
+    }
+}
+
+impl<I: Iterator + Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcIter<I, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("CombRcIter").field(&self.inner).finish()
+    }
+}
+
+impl<I: Iterator + Clone, P: CowPolicy> From<CombRc<I, P>> for CombRcIter<I, P> {
+    fn from(value: CombRc<I, P>) -> Self {
+        CombRcIter::from_comb(value)
+    }
+}
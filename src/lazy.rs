@@ -0,0 +1,237 @@
+use alloc::rc::Rc;
+use core::cell::{Cell, OnceCell};
+use core::fmt::Formatter;
+use crate::CombRc;
+#[cfg(feature = "std")]
+use crate::CombArc;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+const POISONED: &str = "the function of a lazy value panicked";
+
+/// A [`CombArc`] whose value is computed the first time it is needed.
+///
+/// Clones share the pending computation, so the function runs at most once, even across threads.
+/// Once computed, the value is shared by every clone. To mutate it, get a `CombArc` with
+/// [`CombArcLazy::into_comb`], which is copy-on-write as usual.
+///
+/// If the function panics, the `CombArcLazy` and its clones will panic whenever they are accessed.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArcLazy;
+///
+/// let lazy = CombArcLazy::new(|| vec![1u32, 2, 3]);
+/// let another_lazy = lazy.clone();
+/// assert_eq!(*another_lazy, [1, 2, 3]);
+///
+/// let mut value = CombArcLazy::into_comb(lazy);
+/// value.push(4);
+/// assert_eq!(*another_lazy, [1, 2, 3]);
+/// ```
+#[cfg(feature = "std")]
+pub struct CombArcLazy<T: Clone, F: FnOnce() -> T = fn() -> T> {
+    shared: Arc<LazyShared<T, F>>
+}
+
+#[cfg(feature = "std")]
+struct LazyShared<T: Clone, F: FnOnce() -> T> {
+    value: OnceLock<CombArc<T>>,
+    init: Mutex<Option<F>>
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone, F: FnOnce() -> T> CombArcLazy<T, F> {
+    /// Constructs a `CombArcLazy` which computes its value with `f`.
+    #[inline]
+    pub fn new(f: F) -> CombArcLazy<T, F> {
+        Self {
+            shared: Arc::new(LazyShared {
+                value: OnceLock::new(),
+                init: Mutex::new(Some(f))
+            })
+        }
+    }
+
+    /// Get the value, computing it if it was not computed yet.
+    ///
+    /// This is the same as dereferencing.
+    #[inline]
+    pub fn force(what: &CombArcLazy<T, F>) -> &T {
+        Self::force_comb(what)
+    }
+
+    /// Get the value as a `CombArc`, computing it if it was not computed yet.
+    pub fn force_comb(what: &CombArcLazy<T, F>) -> &CombArc<T> {
+        what.shared.value.get_or_init(|| {
+            let f = what.shared.init.lock().unwrap_or_else(PoisonError::into_inner).take();
+            CombArc::new(f.expect(POISONED)())
+        })
+    }
+
+    /// Get the value if it was computed.
+    #[inline]
+    pub fn get(what: &CombArcLazy<T, F>) -> Option<&T> {
+        what.shared.value.get().map(|value| &**value)
+    }
+
+    /// Get the value as a `CombArc`, computing it if it was not computed yet.
+    ///
+    /// If this is the only clone, the `CombArc` is moved out, so it may be unique. Otherwise, it is
+    /// shared with the other clones.
+    pub fn into_comb(what: CombArcLazy<T, F>) -> CombArc<T> {
+        Self::force_comb(&what);
+        match Arc::try_unwrap(what.shared) {
+            Ok(shared) => shared.value.into_inner().expect(POISONED),
+            Err(shared) => shared.value.get().expect(POISONED).clone()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone, F: FnOnce() -> T> Clone for CombArcLazy<T, F> {
+    /// Constructs a `CombArcLazy` which shares the value, or the pending computation of it.
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone()
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone + Default> Default for CombArcLazy<T> {
+    /// Constructs a `CombArcLazy` which computes `T::default()`.
+    fn default() -> Self {
+        Self::new(T::default)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone + core::fmt::Debug, F: FnOnce() -> T> core::fmt::Debug for CombArcLazy<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match Self::get(self) {
+            Some(value) => f.debug_tuple("CombArcLazy").field(value).finish(),
+            None => f.write_str("CombArcLazy(<uninit>)")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone, F: FnOnce() -> T> core::ops::Deref for CombArcLazy<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
+
+/// A [`CombRc`] whose value is computed the first time it is needed.
+///
+/// Clones share the pending computation, so the function runs at most once.
+/// Once computed, the value is shared by every clone. To mutate it, get a `CombRc` with
+/// [`CombRcLazy::into_comb`], which is copy-on-write as usual.
+///
+/// If the function panics, the `CombRcLazy` and its clones will panic whenever they are accessed.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRcLazy;
+///
+/// let lazy = CombRcLazy::new(|| vec![1u32, 2, 3]);
+/// let another_lazy = lazy.clone();
+/// assert_eq!(*another_lazy, [1, 2, 3]);
+///
+/// let mut value = CombRcLazy::into_comb(lazy);
+/// value.push(4);
+/// assert_eq!(*another_lazy, [1, 2, 3]);
+/// ```
+pub struct CombRcLazy<T: Clone, F: FnOnce() -> T = fn() -> T> {
+    shared: Rc<RcLazyShared<T, F>>
+}
+
+struct RcLazyShared<T: Clone, F: FnOnce() -> T> {
+    value: OnceCell<CombRc<T>>,
+    init: Cell<Option<F>>
+}
+
+impl<T: Clone, F: FnOnce() -> T> CombRcLazy<T, F> {
+    /// Constructs a `CombRcLazy` which computes its value with `f`.
+    #[inline]
+    pub fn new(f: F) -> CombRcLazy<T, F> {
+        Self {
+            shared: Rc::new(RcLazyShared {
+                value: OnceCell::new(),
+                init: Cell::new(Some(f))
+            })
+        }
+    }
+
+    /// Get the value, computing it if it was not computed yet.
+    ///
+    /// This is the same as dereferencing.
+    #[inline]
+    pub fn force(what: &CombRcLazy<T, F>) -> &T {
+        Self::force_comb(what)
+    }
+
+    /// Get the value as a `CombRc`, computing it if it was not computed yet.
+    pub fn force_comb(what: &CombRcLazy<T, F>) -> &CombRc<T> {
+        what.shared.value.get_or_init(|| {
+            let f = what.shared.init.take();
+            CombRc::new(f.expect(POISONED)())
+        })
+    }
+
+    /// Get the value if it was computed.
+    #[inline]
+    pub fn get(what: &CombRcLazy<T, F>) -> Option<&T> {
+        what.shared.value.get().map(|value| &**value)
+    }
+
+    /// Get the value as a `CombRc`, computing it if it was not computed yet.
+    ///
+    /// If this is the only clone, the `CombRc` is moved out, so it may be unique. Otherwise, it is
+    /// shared with the other clones.
+    pub fn into_comb(what: CombRcLazy<T, F>) -> CombRc<T> {
+        Self::force_comb(&what);
+        match Rc::try_unwrap(what.shared) {
+            Ok(shared) => shared.value.into_inner().expect(POISONED),
+            Err(shared) => shared.value.get().expect(POISONED).clone()
+        }
+    }
+}
+
+impl<T: Clone, F: FnOnce() -> T> Clone for CombRcLazy<T, F> {
+    /// Constructs a `CombRcLazy` which shares the value, or the pending computation of it.
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone()
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombRcLazy<T> {
+    /// Constructs a `CombRcLazy` which computes `T::default()`.
+    fn default() -> Self {
+        Self::new(T::default)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, F: FnOnce() -> T> core::fmt::Debug for CombRcLazy<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match Self::get(self) {
+            Some(value) => f.debug_tuple("CombRcLazy").field(value).finish(),
+            None => f.write_str("CombRcLazy(<uninit>)")
+        }
+    }
+}
+
+impl<T: Clone, F: FnOnce() -> T> core::ops::Deref for CombRcLazy<T, F> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        Self::force(self)
+    }
+}
@@ -27,6 +27,44 @@
 //!
 //! This crate uses no unsafe code directly and only uses safe methods from the `alloc` crate.
 //!
+//! The only exception is the `raw` feature, which adds `CombArc::from_raw` and `CombRc::from_raw`
+//! for reconstructing references from pointers returned by `into_raw`. These are unsafe functions
+//! which just call [`Arc::from_raw`] and [`Rc::from_raw`], and all other code still denies unsafe
+//! code.
+//!
+//! ## Pinning
+//!
+//! `CombArc` and `CombRc` cannot be pinned. Mutably borrowing may move the inner value to a new
+//! allocation (e.g. when dissociating weak references) which would break the guarantees of [`Pin`],
+//! and pinning a value that is not [`Unpin`] requires unsafe code anyway. If you need a pinned shared
+//! value, use [`Arc::pin`] or [`Rc::pin`] directly, and mutate its contents through interior
+//! mutability or `Pin`-safe APIs instead.
+//!
+//! ## Features
+//!
+//! * `std`: Adds `unify`, `default_shared`, `AbortOnClone`, `CombArcHashed`, `CombArcLazy`,
+//!   `CombArcLock`, `CombArcPool`, and the interning pools `CombArcInterner` and `CombRcInterner`,
+//!   which need the standard library.
+//! * `raw`: Adds `from_raw` for reconstructing references from raw pointers. See above.
+//! * `debug_hooks`: Adds `no_clone_scope`, `NoCloneGuard`, and `with_clone_budget` for limiting
+//!   copy-on-write clones, such as in tests. Implies `std`. Without it, there is no overhead.
+//! * `allocator_api`: Adds `try_new` for handling allocation failure, and `new_in` for allocating
+//!   references with a custom allocator, which copy-on-write clones are also allocated with.
+//!   Requires a nightly compiler.
+//! * `fn_traits`: Implements the `Fn`, `FnMut`, and `FnOnce` traits for references to closures.
+//!   Mutably calling a shared closure clones it first, like any other mutable borrow. Requires a
+//!   nightly compiler.
+//!
+//! ## Breaking changes
+//!
+//! * `AsRef` and `AsMut` now forward to the inner value's implementations, so a `CombArc<String>` is
+//!   `AsRef<str>`, and this replaces the previous `AsRef<T>` implementation. `AsRef<T>` only resolves
+//!   if `T` itself implements `AsRef<T>`, like `Vec`. Use `Deref` or `Borrow` to get the inner value
+//!   instead.
+//! * Since `CombArc` and `CombRc` now implement `AsRef` for more types, calling `.as_ref()` or
+//!   `.as_mut()` as a method may fail to infer a type where it did before, such as on the inner
+//!   [`Option`]'s own `as_ref`. Call those like `Option::as_ref(&value)` instead.
+//!
 //! ## Examples
 //!
 //! In this example, `CombArc` is used, but `CombRc` can be used interchangeably here.
@@ -44,20 +82,20 @@
 //! // so they still point to the same memory address.
 //! my_value.set(true);
 //! assert_eq!(my_value, another_value);
-//! assert_eq!(my_value.as_ptr(), another_value.as_ptr());
+//! assert_eq!(CombArc::as_ptr(&my_value), CombArc::as_ptr(&another_value));
 //!
 //! // get_mut does mutably borrow, thus `my_value` is cloned.
 //! *my_value.get_mut() = false;
 //! assert_ne!(my_value, another_value);
 //!
 //! // Also, if there is only one reference, `my_value` is not cloned.
-//! let address_before = my_value.as_ptr();
+//! let address_before = CombArc::as_ptr(&my_value);
 //! *my_value.get_mut() = true;
-//! let address_after = my_value.as_ptr();
+//! let address_after = CombArc::as_ptr(&my_value);
 //! assert_eq!(address_before, address_after);
 //!
 //! // Despite not pointing to the same thing, these are equal now.
-//! assert_ne!(my_value.as_ptr(), another_value.as_ptr());
+//! assert_ne!(CombArc::as_ptr(&my_value), CombArc::as_ptr(&another_value));
 //! assert_eq!(my_value, another_value);
 //! ```
 //!
@@ -69,18 +107,104 @@
 //! [`Deref`]: core::ops::Deref
 //! [`DerefMut`]: core::ops::DerefMut
 //! [`Cell::set`]: core::cell::Cell::set
+//! [`Pin`]: core::pin::Pin
+//! [`Unpin`]: core::marker::Unpin
+//! [`Arc::pin`]: alloc::sync::Arc::pin
+//! [`Rc::pin`]: alloc::rc::Rc::pin
+//! [`Arc::from_raw`]: alloc::sync::Arc::from_raw
+//! [`Rc::from_raw`]: alloc::rc::Rc::from_raw
 #![no_std]
-#![forbid(unsafe_code)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![cfg_attr(feature = "fn_traits", feature(fn_traits, tuple_trait, unboxed_closures))]
+#![cfg_attr(not(any(feature = "raw", test)), forbid(unsafe_code))]
+#![cfg_attr(any(feature = "raw", test), deny(unsafe_code))]
 #![forbid(dead_code)]
 #![forbid(missing_docs)]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+#[cfg(feature = "allocator_api")]
+mod allocator_api;
 mod arc;
+mod by_ptr;
+mod cell;
+mod comb_static;
+#[cfg(feature = "debug_hooks")]
+mod debug_hooks;
+mod ext;
+#[cfg(feature = "fn_traits")]
+mod fn_traits;
+mod frozen;
+#[cfg(feature = "std")]
+mod hashed;
+mod history;
+#[cfg(feature = "std")]
+mod interner;
+mod iter;
+mod lazy;
+#[cfg(feature = "std")]
+mod lock;
+mod maybe_comb;
+mod option;
+mod policy;
+pub mod prelude;
+mod pool;
+mod projection;
 mod rc;
+#[cfg(feature = "raw")]
+mod raw;
+mod small;
+mod transaction;
+mod try_clone;
+mod unique;
+mod versioned;
+mod watched;
+mod weak;
+mod write_guard;
 
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "allocator_api")]
+pub use allocator_api::AllocError;
 pub use arc::CombArc;
+pub use by_ptr::{CombArcByPtr, CombRcByPtr};
+pub use cell::CombRcCell;
+pub use comb_static::CombStatic;
+#[cfg(feature = "debug_hooks")]
+pub use debug_hooks::{no_clone_scope, CloneBudgetExceeded, NoCloneGuard};
+pub use ext::{ArcExt, IntoComb, RcExt};
+pub use frozen::{FrozenCombArc, FrozenCombRc};
+#[cfg(feature = "std")]
+pub use hashed::CombArcHashed;
+pub use history::{CombHistory, CombRcHistory};
+#[cfg(feature = "std")]
+pub use interner::{CombArcInterner, CombRcInterner};
+pub use iter::{CombIter, CombRcIter};
+#[cfg(feature = "std")]
+pub use lazy::CombArcLazy;
+pub use lazy::CombRcLazy;
+#[cfg(feature = "std")]
+pub use lock::CombArcLock;
+pub use maybe_comb::MaybeComb;
+pub use option::OptionCombExt;
+#[cfg(feature = "std")]
+pub use policy::AbortOnClone;
+pub use policy::{CloneOnWrite, CowPolicy, PanicOnClone};
+#[cfg(feature = "std")]
+pub use pool::CombArcPool;
+pub use pool::CombRcPool;
+pub use projection::{CombArcRef, CombRcRef};
+#[doc(hidden)]
+pub use projection::Project as __Project;
 pub use rc::CombRc;
+pub use small::CombSmall;
+pub use transaction::{CombArcTransaction, CombRcTransaction};
+pub use try_clone::TryClone;
+pub use unique::{UniqueCombArc, UniqueCombRc};
+pub use versioned::{VersionedCombArc, VersionedCombRc};
+pub use watched::{CombArcWatched, CombRcWatched, SubscriptionId};
+pub use weak::{CombRcWeak, CombWeak};
+pub use write_guard::{CombArcWriteGuard, CombRcWriteGuard};
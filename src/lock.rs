@@ -0,0 +1,110 @@
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CowPolicy};
+use std::sync::{Mutex, PoisonError, RwLock};
+
+/// A lock holding a [`CombArc`], where readers take snapshots and writers publish new values.
+///
+/// Locks are only held long enough to clone or replace the handle, so readers are never blocked by
+/// a writer that is still editing its value. [`CombArcLock::write`] edits a clone of the current
+/// value outside the lock, then publishes it, so snapshots taken before a write never change.
+/// Writers are serialized, so concurrent writes do not overwrite each other.
+///
+/// # Poisoning
+///
+/// This never panics because of poisoning. Since the value is not edited while the lock is held, a
+/// panicking writer just leaves the current value as it was.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArcLock;
+///
+/// let state = CombArcLock::new(vec![1u32]);
+/// let before = state.snapshot();
+///
+/// state.write(|value| value.push(2));
+/// assert_eq!(*before, [1]);
+/// assert_eq!(*state.snapshot(), [1, 2]);
+/// ```
+pub struct CombArcLock<T: Clone, P: CowPolicy = CloneOnWrite> {
+    current: RwLock<CombArc<T, P>>,
+    writer: Mutex<()>
+}
+
+impl<T: Clone> CombArcLock<T> {
+    /// Constructs a `CombArcLock`.
+    #[inline]
+    pub fn new(what: T) -> CombArcLock<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombArcLock<T, P> {
+    /// Constructs a `CombArcLock` from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<T, P>) -> CombArcLock<T, P> {
+        Self {
+            current: RwLock::new(what),
+            writer: Mutex::new(())
+        }
+    }
+
+    /// Get a reference to the current value.
+    ///
+    /// The read lock is only held while cloning the handle.
+    pub fn snapshot(&self) -> CombArc<T, P> {
+        self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Edit a clone of the current value with [`CombArc::make_mut`], then publish it.
+    ///
+    /// Since the lock keeps a reference to the current value, this always clones it. If `f` panics,
+    /// nothing is published.
+    pub fn write<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut next = self.snapshot();
+        let result = f(CombArc::make_mut(&mut next));
+        self.publish(next);
+        result
+    }
+
+    /// Publish a new value, returning the previous value.
+    ///
+    /// This waits for any [`CombArcLock::write`] in progress, so it is not overwritten by it.
+    pub fn replace(&self, what: CombArc<T, P>) -> CombArc<T, P> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.publish(what)
+    }
+
+    // The writer lock must already be held.
+    fn publish(&self, what: CombArc<T, P>) -> CombArc<T, P> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        core::mem::replace(&mut *current, what)
+    }
+
+    /// Get the current value.
+    #[inline]
+    pub fn into_inner(self) -> CombArc<T, P> {
+        self.current.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Clone + Default> Default for CombArcLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcLock<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcLock")
+            .field("current", &self.snapshot())
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for CombArcLock<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        CombArcLock::from_comb(value)
+    }
+}
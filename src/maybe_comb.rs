@@ -0,0 +1,143 @@
+use core::cmp::Ordering;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::CombArc;
+
+/// Either a borrowed value or a [`CombArc`].
+///
+/// This is like [`Cow`], but the owned side is a `CombArc`, so it can be shared further without
+/// cloning the inner value. [`MaybeComb::to_mut`] promotes a borrowed value into a `CombArc` by
+/// cloning it, and otherwise calls [`CombArc::make_mut`].
+///
+/// Comparisons are by value, regardless of which variant either side is.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, MaybeComb};
+///
+/// fn normalize(mut name: MaybeComb<'_, String>) -> CombArc<String> {
+///     if name.contains(' ') {
+///         *MaybeComb::to_mut(&mut name) = name.replace(' ', "_");
+///     }
+///     MaybeComb::into_shared(name)
+/// }
+///
+/// assert_eq!(*normalize(MaybeComb::from(&"no_spaces".to_owned())), "no_spaces");
+/// assert_eq!(*normalize(MaybeComb::from(CombArc::new("some spaces".to_owned()))), "some_spaces");
+/// ```
+///
+/// [`Cow`]: alloc::borrow::Cow
+pub enum MaybeComb<'a, T: Clone> {
+    /// A borrowed value.
+    Borrowed(&'a T),
+
+    /// A shared value.
+    Shared(CombArc<T>)
+}
+
+impl<'a, T: Clone> MaybeComb<'a, T> {
+    /// Returns `true` if this is [`MaybeComb::Borrowed`].
+    #[inline]
+    pub fn is_borrowed(what: &MaybeComb<'a, T>) -> bool {
+        matches!(what, MaybeComb::Borrowed(_))
+    }
+
+    /// Get a mutable reference to the inner value.
+    ///
+    /// If this is borrowed, the value is cloned into a new `CombArc` first. Otherwise, this calls
+    /// [`CombArc::make_mut`].
+    #[inline]
+    pub fn to_mut<'b>(what: &'b mut MaybeComb<'a, T>) -> &'b mut T {
+        if let MaybeComb::Borrowed(b) = *what {
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::check_clone::<T>();
+            *what = MaybeComb::Shared(CombArc::new(b.clone()));
+        }
+        match what {
+            MaybeComb::Borrowed(_) => unreachable!("borrowed values should have been cloned"),
+            MaybeComb::Shared(s) => CombArc::make_mut(s)
+        }
+    }
+
+    /// Convert this into a `CombArc`, cloning the value into a new allocation if it is borrowed.
+    #[inline]
+    pub fn into_shared(what: MaybeComb<'a, T>) -> CombArc<T> {
+        match what {
+            MaybeComb::Borrowed(b) => CombArc::new(b.clone()),
+            MaybeComb::Shared(s) => s
+        }
+    }
+}
+
+impl<T: Clone> Clone for MaybeComb<'_, T> {
+    fn clone(&self) -> Self {
+        match self {
+            MaybeComb::Borrowed(b) => MaybeComb::Borrowed(b),
+            MaybeComb::Shared(s) => MaybeComb::Shared(s.clone())
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for MaybeComb<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Clone + Eq> Eq for MaybeComb<'_, T> {}
+
+impl<T: Clone + PartialOrd> PartialOrd for MaybeComb<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: Clone + Ord> Ord for MaybeComb<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: Clone + Hash> Hash for MaybeComb<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug> core::fmt::Debug for MaybeComb<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MaybeComb::Borrowed(b) => f.debug_tuple("Borrowed").field(b).finish(),
+            MaybeComb::Shared(s) => f.debug_tuple("Shared").field(s).finish()
+        }
+    }
+}
+
+impl<T: core::fmt::Display + Clone> core::fmt::Display for MaybeComb<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<'a, T: Clone> From<&'a T> for MaybeComb<'a, T> {
+    fn from(value: &'a T) -> Self {
+        MaybeComb::Borrowed(value)
+    }
+}
+
+impl<T: Clone> From<CombArc<T>> for MaybeComb<'_, T> {
+    fn from(value: CombArc<T>) -> Self {
+        MaybeComb::Shared(value)
+    }
+}
+
+impl<T: Clone> core::ops::Deref for MaybeComb<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match self {
+            MaybeComb::Borrowed(b) => b,
+            MaybeComb::Shared(s) => s
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{CombArc, CombRc, CowPolicy};
+
+/// Helpers for an `Option` of a [`CombArc`] or [`CombRc`].
+///
+/// Some helpers are not needed here, since `Option` already provides them. For example,
+/// [`Option::as_deref`] already gets an `Option<&T>`.
+///
+/// The methods are not named after the `Option` methods they are similar to, such as
+/// [`Option::get_or_insert_with`], since those would take precedence over them.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, OptionCombExt};
+///
+/// let mut cache: Option<CombArc<Vec<u32>>> = None;
+/// cache.make_mut_or_default().push(1);
+///
+/// let another_cache = cache.clone();
+/// cache.make_mut_or_default().push(2);
+///
+/// assert_eq!(cache.as_deref(), Some(&vec![1, 2]));
+/// assert_eq!(another_cache.as_deref(), Some(&vec![1]));
+/// ```
+pub trait OptionCombExt {
+    /// The inner type of the reference.
+    type Target;
+
+    /// Get a mutable reference to the inner value, inserting a new reference to the default value
+    /// first if there is none.
+    ///
+    /// If there already is a reference, this clones the inner value if it is shared, like
+    /// `make_mut`.
+    fn make_mut_or_default(&mut self) -> &mut Self::Target where Self::Target: Default {
+        self.make_mut_or_insert_with(Default::default)
+    }
+
+    /// Get a mutable reference to the inner value, inserting a new reference to the result of `f`
+    /// first if there is none.
+    ///
+    /// If there already is a reference, this clones the inner value if it is shared, like
+    /// `make_mut`.
+    fn make_mut_or_insert_with<F: FnOnce() -> Self::Target>(&mut self, f: F) -> &mut Self::Target;
+}
+
+impl<T: Clone, P: CowPolicy> OptionCombExt for Option<CombArc<T, P>> {
+    type Target = T;
+
+    #[inline]
+    fn make_mut_or_insert_with<F: FnOnce() -> T>(&mut self, f: F) -> &mut T {
+        CombArc::make_mut(self.get_or_insert_with(|| CombArc::with_policy(CombArc::new(f()))))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> OptionCombExt for Option<CombRc<T, P>> {
+    type Target = T;
+
+    #[inline]
+    fn make_mut_or_insert_with<F: FnOnce() -> T>(&mut self, f: F) -> &mut T {
+        CombRc::make_mut(self.get_or_insert_with(|| CombRc::with_policy(CombRc::new(f()))))
+    }
+}
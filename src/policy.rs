@@ -0,0 +1,75 @@
+/// Decides what happens when a shared [`CombArc`] or [`CombRc`] is mutably borrowed.
+///
+/// This is a type parameter of `CombArc` and `CombRc`, so it is decided at compile time. It only
+/// affects mutable borrows which need to clone the inner value, such as with `make_mut` or
+/// [`DerefMut`]. Mutably borrowing a unique reference and cloning explicitly, such as with
+/// `clone_unique`, are unaffected. Use `with_policy` to convert a reference to another policy.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// use combarc::{CombArc, PanicOnClone};
+///
+/// let mut samples: CombArc<Vec<f32>, PanicOnClone> = CombArc::with_policy(CombArc::new(vec![0.0; 64]));
+/// samples[0] = 1.0; // unique, so this is fine
+///
+/// let another_samples = samples.clone();
+/// samples[0] = 2.0; // panics, since `samples` is shared with `another_samples`
+/// ```
+///
+/// [`CombArc`]: crate::CombArc
+/// [`CombRc`]: crate::CombRc
+/// [`DerefMut`]: core::ops::DerefMut
+pub trait CowPolicy {
+    /// Whether [`CowPolicy::before_clone`] needs to be called at all.
+    ///
+    /// If this is `false`, mutable borrows do not check whether the reference is shared beforehand.
+    const INTERCEPTS_CLONES: bool = true;
+
+    /// Called before the inner value, a `T`, is cloned because its reference is mutably borrowed
+    /// while it is shared.
+    ///
+    /// If this panics, the reference is left untouched.
+    fn before_clone<T>();
+}
+
+/// Silently clone shared references when mutably borrowed.
+///
+/// This is the default policy.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CloneOnWrite;
+
+impl CowPolicy for CloneOnWrite {
+    const INTERCEPTS_CLONES: bool = false;
+
+    #[inline]
+    fn before_clone<T>() {}
+}
+
+/// Panic when a shared reference is mutably borrowed.
+///
+/// This is for code where cloning is a bug, such as code which needs to be real-time.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PanicOnClone;
+
+impl CowPolicy for PanicOnClone {
+    #[track_caller]
+    fn before_clone<T>() {
+        panic!("a shared reference to `{}` was mutably borrowed with PanicOnClone", core::any::type_name::<T>());
+    }
+}
+
+/// Abort the process when a shared reference is mutably borrowed.
+///
+/// This is like [`PanicOnClone`], but it cannot be caught, and it does not unwind.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbortOnClone;
+
+#[cfg(feature = "std")]
+impl CowPolicy for AbortOnClone {
+    fn before_clone<T>() {
+        std::eprintln!("a shared reference to `{}` was mutably borrowed with AbortOnClone", core::any::type_name::<T>());
+        std::process::abort()
+    }
+}
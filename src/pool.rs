@@ -0,0 +1,268 @@
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::{RefCell, RefMut};
+use core::fmt::Formatter;
+use crate::CombRc;
+#[cfg(feature = "std")]
+use crate::CombArc;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A pool of allocations recycled from dropped [`CombArc`]s, for reusing them and their heap buffers.
+///
+/// Allocations are recycled by giving unique handles back to [`CombArcPool::recycle`]. The pool
+/// holds at most [`CombArcPool::capacity`] allocations. [`CombArcPool::new_in_pool`] moves a value
+/// into a recycled allocation. When a value is cloned through the pool, the recycled value is
+/// reused with [`Clone::clone_from`] instead of cloning from scratch, so types like `Vec` and
+/// `String` can keep their buffers.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombArcPool};
+///
+/// let pool = CombArcPool::new(4);
+/// let old = CombArc::new(Vec::<u32>::with_capacity(1000));
+/// assert!(pool.recycle(old));
+///
+/// let mut value = CombArc::new(vec![1u32, 2, 3]);
+/// let snapshot = value.clone();
+/// pool.make_mut(&mut value).push(4);
+/// assert!(value.capacity() >= 1000, "the recycled buffer is reused");
+/// assert_eq!(*snapshot, [1, 2, 3]);
+/// ```
+#[cfg(feature = "std")]
+pub struct CombArcPool<T: Clone> {
+    values: Mutex<Vec<Arc<T>>>,
+    capacity: usize
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> CombArcPool<T> {
+    /// Constructs an empty `CombArcPool` which holds at most `capacity` allocations.
+    #[inline]
+    pub fn new(capacity: usize) -> CombArcPool<T> {
+        Self {
+            values: Mutex::new(Vec::new()),
+            capacity
+        }
+    }
+
+    /// Get the maximum number of allocations in the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of allocations in the pool.
+    pub fn len(&self) -> usize {
+        self.values().len()
+    }
+
+    /// Returns `true` if there are no allocations in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.values().is_empty()
+    }
+
+    /// Move the allocation into the pool if this is a unique reference without weak references.
+    ///
+    /// Returns `false` if the reference was shared, had weak references, or the pool was full, in
+    /// which case the reference is just dropped.
+    pub fn recycle(&self, what: CombArc<T>) -> bool {
+        let mut allocation = Arc::from(what);
+        if Arc::get_mut(&mut allocation).is_none() {
+            return false
+        }
+        let mut values = self.values();
+        if values.len() >= self.capacity {
+            return false
+        }
+        values.push(allocation);
+        true
+    }
+
+    /// Move a value into a new `CombArc`, reusing an allocation from the pool if there is one.
+    pub fn new_in_pool(&self, what: T) -> CombArc<T> {
+        let recycled = self.values().pop();
+        match recycled {
+            Some(mut allocation) => {
+                *Arc::make_mut(&mut allocation) = what;
+                CombArc::from(allocation)
+            },
+            None => CombArc::new(what)
+        }
+    }
+
+    /// Clone a value into a new `CombArc`, reusing an allocation and its value from the pool if there
+    /// is one.
+    pub fn clone_in_pool(&self, what: &T) -> CombArc<T> {
+        let recycled = self.values().pop();
+        match recycled {
+            Some(mut allocation) => {
+                Arc::make_mut(&mut allocation).clone_from(what);
+                CombArc::from(allocation)
+            },
+            None => CombArc::new(what.clone())
+        }
+    }
+
+    /// Get a mutable reference to the inner value like [`CombArc::make_mut`], but reusing a value
+    /// from the pool if it has to be cloned.
+    pub fn make_mut<'a>(&self, what: &'a mut CombArc<T>) -> &'a mut T {
+        CombArc::make_mut_with(what, |value| self.clone_value(value))
+    }
+
+    fn clone_value(&self, what: &T) -> T {
+        let recycled = self.values().pop().and_then(Arc::into_inner);
+        match recycled {
+            Some(mut value) => {
+                value.clone_from(what);
+                value
+            },
+            None => what.clone()
+        }
+    }
+
+    fn values(&self) -> MutexGuard<'_, Vec<Arc<T>>> {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> core::fmt::Debug for CombArcPool<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcPool")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// A pool of allocations recycled from dropped [`CombRc`]s, for reusing them and their heap buffers.
+///
+/// Allocations are recycled by giving unique handles back to [`CombRcPool::recycle`]. The pool
+/// holds at most [`CombRcPool::capacity`] allocations. [`CombRcPool::new_in_pool`] moves a value
+/// into a recycled allocation. When a value is cloned through the pool, the recycled value is
+/// reused with [`Clone::clone_from`] instead of cloning from scratch, so types like `Vec` and
+/// `String` can keep their buffers.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcPool};
+///
+/// let pool = CombRcPool::new(4);
+/// let old = CombRc::new(Vec::<u32>::with_capacity(1000));
+/// assert!(pool.recycle(old));
+///
+/// let mut value = CombRc::new(vec![1u32, 2, 3]);
+/// let snapshot = value.clone();
+/// pool.make_mut(&mut value).push(4);
+/// assert!(value.capacity() >= 1000, "the recycled buffer is reused");
+/// assert_eq!(*snapshot, [1, 2, 3]);
+/// ```
+pub struct CombRcPool<T: Clone> {
+    values: RefCell<Vec<Rc<T>>>,
+    capacity: usize
+}
+
+impl<T: Clone> CombRcPool<T> {
+    /// Constructs an empty `CombRcPool` which holds at most `capacity` allocations.
+    #[inline]
+    pub fn new(capacity: usize) -> CombRcPool<T> {
+        Self {
+            values: RefCell::new(Vec::new()),
+            capacity
+        }
+    }
+
+    /// Get the maximum number of allocations in the pool.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of allocations in the pool.
+    pub fn len(&self) -> usize {
+        self.values().len()
+    }
+
+    /// Returns `true` if there are no allocations in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.values().is_empty()
+    }
+
+    /// Move the allocation into the pool if this is a unique reference without weak references.
+    ///
+    /// Returns `false` if the reference was shared, had weak references, or the pool was full, in
+    /// which case the reference is just dropped.
+    pub fn recycle(&self, what: CombRc<T>) -> bool {
+        let mut allocation = Rc::from(what);
+        if Rc::get_mut(&mut allocation).is_none() {
+            return false
+        }
+        let mut values = self.values();
+        if values.len() >= self.capacity {
+            return false
+        }
+        values.push(allocation);
+        true
+    }
+
+    /// Move a value into a new `CombRc`, reusing an allocation from the pool if there is one.
+    pub fn new_in_pool(&self, what: T) -> CombRc<T> {
+        let recycled = self.values().pop();
+        match recycled {
+            Some(mut allocation) => {
+                *Rc::make_mut(&mut allocation) = what;
+                CombRc::from(allocation)
+            },
+            None => CombRc::new(what)
+        }
+    }
+
+    /// Clone a value into a new `CombRc`, reusing an allocation and its value from the pool if there
+    /// is one.
+    pub fn clone_in_pool(&self, what: &T) -> CombRc<T> {
+        let recycled = self.values().pop();
+        match recycled {
+            Some(mut allocation) => {
+                Rc::make_mut(&mut allocation).clone_from(what);
+                CombRc::from(allocation)
+            },
+            None => CombRc::new(what.clone())
+        }
+    }
+
+    /// Get a mutable reference to the inner value like [`CombRc::make_mut`], but reusing a value
+    /// from the pool if it has to be cloned.
+    pub fn make_mut<'a>(&self, what: &'a mut CombRc<T>) -> &'a mut T {
+        CombRc::make_mut_with(what, |value| self.clone_value(value))
+    }
+
+    fn clone_value(&self, what: &T) -> T {
+        let recycled = self.values().pop().and_then(Rc::into_inner);
+        match recycled {
+            Some(mut value) => {
+                value.clone_from(what);
+                value
+            },
+            None => what.clone()
+        }
+    }
+
+    fn values(&self) -> RefMut<'_, Vec<Rc<T>>> {
+        self.values.borrow_mut()
+    }
+}
+
+impl<T: Clone> core::fmt::Debug for CombRcPool<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombRcPool")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
@@ -0,0 +1,16 @@
+//! Re-exports of the most commonly used types and traits.
+//!
+//! ```rust
+//! use combarc::prelude::*;
+//!
+//! let settings = vec![1u32].comb();
+//! let mut another_settings = settings.clone();
+//! another_settings.push(2);
+//! assert_eq!(*settings, [1]);
+//!
+//! let mut cache: Option<CombRc<Vec<u32>>> = None;
+//! cache.make_mut_or_default().push(1);
+//! assert_eq!(cache.as_deref(), Some(&vec![1]));
+//! ```
+
+pub use crate::{ArcExt, CombArc, CombRc, IntoComb, OptionCombExt, RcExt};
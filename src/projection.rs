@@ -0,0 +1,272 @@
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A [`CombArc`] which dereferences to a part of its inner value, such as a field.
+///
+/// This holds the whole `CombArc`, so the inner value is kept alive, and cloning this just clones
+/// the `CombArc`. The projection is a function pointer which is called on every dereference, so it
+/// should be cheap, and closures passed to [`CombArc::project`] must not capture anything.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombArcRef};
+///
+/// #[derive(Clone)]
+/// struct Document {
+///     title: String,
+///     body: String
+/// }
+///
+/// let document = CombArc::new(Document { title: "Hello".to_owned(), body: "World".to_owned() });
+/// let title = CombArc::project(document.clone(), |d| &d.title);
+///
+/// assert_eq!(*title, "Hello");
+/// assert!(CombArc::ptr_eq(CombArcRef::root(&title), &document));
+/// ```
+pub struct CombArcRef<T: Clone, U: ?Sized, P: CowPolicy = CloneOnWrite> {
+    root: CombArc<T, P>,
+    projection: fn(&T) -> &U
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> CombArcRef<T, U, P> {
+    /// Constructs a `CombArcRef` from a `CombArc` and a projection.
+    ///
+    /// This is the same as [`CombArc::project`].
+    #[inline]
+    pub fn new(root: CombArc<T, P>, projection: fn(&T) -> &U) -> CombArcRef<T, U, P> {
+        Self {
+            root,
+            projection
+        }
+    }
+
+    /// Get the `CombArc` this projects from.
+    #[inline]
+    pub fn root(what: &CombArcRef<T, U, P>) -> &CombArc<T, P> {
+        &what.root
+    }
+
+    /// Get a mutable reference to the `CombArc` this projects from.
+    ///
+    /// This can be used with [`CombArc::project_mut`] to mutate what this projects to.
+    #[inline]
+    pub fn root_mut(what: &mut CombArcRef<T, U, P>) -> &mut CombArc<T, P> {
+        &mut what.root
+    }
+
+    /// Get the `CombArc` this projects from, discarding the projection.
+    #[inline]
+    pub fn into_root(what: CombArcRef<T, U, P>) -> CombArc<T, P> {
+        what.root
+    }
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> Clone for CombArcRef<T, U, P> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            projection: self.projection
+        }
+    }
+}
+
+impl<T: Clone, U: ?Sized + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcRef<T, U, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> core::ops::Deref for CombArcRef<T, U, P> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        (self.projection)(&self.root)
+    }
+}
+
+/// A [`CombRc`] which dereferences to a part of its inner value, such as a field.
+///
+/// This holds the whole `CombRc`, so the inner value is kept alive, and cloning this just clones
+/// the `CombRc`. The projection is a function pointer which is called on every dereference, so it
+/// should be cheap, and closures passed to [`CombRc::project`] must not capture anything.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcRef};
+///
+/// #[derive(Clone)]
+/// struct Document {
+///     title: String,
+///     body: String
+/// }
+///
+/// let document = CombRc::new(Document { title: "Hello".to_owned(), body: "World".to_owned() });
+/// let title = CombRc::project(document.clone(), |d| &d.title);
+///
+/// assert_eq!(*title, "Hello");
+/// assert!(CombRc::ptr_eq(CombRcRef::root(&title), &document));
+/// ```
+pub struct CombRcRef<T: Clone, U: ?Sized, P: CowPolicy = CloneOnWrite> {
+    root: CombRc<T, P>,
+    projection: fn(&T) -> &U
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> CombRcRef<T, U, P> {
+    /// Constructs a `CombRcRef` from a `CombRc` and a projection.
+    ///
+    /// This is the same as [`CombRc::project`].
+    #[inline]
+    pub fn new(root: CombRc<T, P>, projection: fn(&T) -> &U) -> CombRcRef<T, U, P> {
+        Self {
+            root,
+            projection
+        }
+    }
+
+    /// Get the `CombRc` this projects from.
+    #[inline]
+    pub fn root(what: &CombRcRef<T, U, P>) -> &CombRc<T, P> {
+        &what.root
+    }
+
+    /// Get a mutable reference to the `CombRc` this projects from.
+    ///
+    /// This can be used with [`CombRc::project_mut`] to mutate what this projects to.
+    #[inline]
+    pub fn root_mut(what: &mut CombRcRef<T, U, P>) -> &mut CombRc<T, P> {
+        &mut what.root
+    }
+
+    /// Get the `CombRc` this projects from, discarding the projection.
+    #[inline]
+    pub fn into_root(what: CombRcRef<T, U, P>) -> CombRc<T, P> {
+        what.root
+    }
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> Clone for CombRcRef<T, U, P> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            projection: self.projection
+        }
+    }
+}
+
+impl<T: Clone, U: ?Sized + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcRef<T, U, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: Clone, U: ?Sized, P: CowPolicy> core::ops::Deref for CombRcRef<T, U, P> {
+    type Target = U;
+    fn deref(&self) -> &U {
+        (self.projection)(&self.root)
+    }
+}
+
+/// Project a [`CombArc`] or [`CombRc`] to a field path.
+///
+/// `comb_project!(handle => .path)` takes the handle by value and expands to a call to
+/// [`CombArc::project`] or [`CombRc::project`] with a closure returning `&handle.path`, so it gets
+/// a [`CombArcRef`] or [`CombRcRef`]. `comb_project!(mut handle => .path)` expands to a call to
+/// [`CombArc::project_mut`] or [`CombRc::project_mut`] instead, getting a mutable reference.
+///
+/// The path can be anything that can follow a value, such as nested fields, tuple indices, and
+/// indexing. If the path ends with a method call, such as for getting a variant of an enum, the
+/// method must return a reference, which is used as is. Paths which do not exist are compile errors
+/// like in any other field access.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{comb_project, CombArc};
+///
+/// #[derive(Clone)]
+/// struct Config {
+///     theme: (String, u32),
+///     fonts: Vec<String>
+/// }
+///
+/// #[derive(Clone)]
+/// struct State {
+///     config: Config
+/// }
+///
+/// let mut state = CombArc::new(State {
+///     config: Config { theme: ("dark".to_owned(), 1), fonts: vec!["mono".to_owned()] }
+/// });
+///
+/// let theme = comb_project!(state.clone() => .config.theme.0);
+/// assert_eq!(*theme, "dark");
+///
+/// comb_project!(mut state => .config.fonts[0]).push_str("space");
+/// assert_eq!(state.config.fonts[0], "monospace");
+/// assert_eq!(combarc::CombArcRef::root(&theme).config.fonts[0], "mono");
+/// ```
+#[macro_export]
+macro_rules! comb_project {
+    (mut $handle:expr => $($path:tt)+) => {
+        $crate::__Project::project_mut(&mut $handle, |value| $crate::comb_project!(@path value [&mut] [] $($path)+))
+    };
+    (@path $value:ident [$($borrow:tt)+] [$($done:tt)*] ($($arguments:tt)*)) => {
+        $value $($done)* ($($arguments)*)
+    };
+    (@path $value:ident [$($borrow:tt)+] [$($done:tt)*] $last:tt) => {
+        $($borrow)+ $value $($done)* $last
+    };
+    (@path $value:ident [$($borrow:tt)+] [$($done:tt)*] $next:tt $($path:tt)+) => {
+        $crate::comb_project!(@path $value [$($borrow)+] [$($done)* $next] $($path)+)
+    };
+    ($handle:expr => $($path:tt)+) => {
+        $crate::__Project::project($handle, |value| $crate::comb_project!(@path value [&] [] $($path)+))
+    };
+}
+
+/// Used by [`comb_project!`] to project either a [`CombArc`] or a [`CombRc`].
+#[doc(hidden)]
+pub trait Project: Sized {
+    /// The inner type of the reference.
+    type Target: Clone;
+
+    /// The type of a projection to `U`.
+    type Ref<U: ?Sized>;
+
+    /// Create a projection.
+    fn project<U: ?Sized>(self, projection: fn(&Self::Target) -> &U) -> Self::Ref<U>;
+
+    /// Get a mutable reference to a part of the inner value.
+    fn project_mut<U: ?Sized, F: FnOnce(&mut Self::Target) -> &mut U>(&mut self, projection: F) -> &mut U;
+}
+
+impl<T: Clone, P: CowPolicy> Project for CombArc<T, P> {
+    type Target = T;
+    type Ref<U: ?Sized> = CombArcRef<T, U, P>;
+
+    #[inline]
+    fn project<U: ?Sized>(self, projection: fn(&T) -> &U) -> CombArcRef<T, U, P> {
+        CombArc::project(self, projection)
+    }
+
+    #[inline]
+    fn project_mut<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(&mut self, projection: F) -> &mut U {
+        CombArc::project_mut(self, projection)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Project for CombRc<T, P> {
+    type Target = T;
+    type Ref<U: ?Sized> = CombRcRef<T, U, P>;
+
+    #[inline]
+    fn project<U: ?Sized>(self, projection: fn(&T) -> &U) -> CombRcRef<T, U, P> {
+        CombRc::project(self, projection)
+    }
+
+    #[inline]
+    fn project_mut<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(&mut self, projection: F) -> &mut U {
+        CombRc::project_mut(self, projection)
+    }
+}
@@ -0,0 +1,74 @@
+//! Functions for reconstructing references from raw pointers.
+//!
+//! This is the only library module allowed to use unsafe code, and only when the `raw` feature is
+//! enabled.
+
+#![allow(unsafe_code)]
+
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use crate::{CombArc, CombRc};
+
+impl<T: Clone> CombArc<T> {
+    /// Constructs a `CombArc` from a raw pointer returned by [`CombArc::into_raw`].
+    ///
+    /// This just calls [`Arc::from_raw`].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been previously returned by a call to `Arc<U>::into_raw` with the
+    /// following requirements:
+    ///
+    /// * If `U` is sized, it must have the same size and alignment as `T`. This is trivially true
+    ///   if `U` is `T`.
+    /// * If `U` is unsized, its data pointer must have the same size and alignment as `T`. This is
+    ///   trivially true if `Arc<U>` was constructed through `Arc<T>` and then converted to `Arc<U>`
+    ///   through an unsized coercion.
+    ///
+    /// Note that if `U` or `U`'s data pointer is not `T` but has the same size and alignment, this
+    /// is basically like transmuting references of different types. See [`core::mem::transmute`]
+    /// for more information on what restrictions apply in this case.
+    ///
+    /// The raw pointer must point to a block of memory allocated by the global allocator.
+    ///
+    /// The user of `from_raw` has to make sure a specific value of `T` is only dropped once.
+    ///
+    /// This function is unsafe because improper use may lead to memory unsafety, even if the
+    /// returned `CombArc<T>` is never accessed.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> CombArc<T> {
+        CombArc::from_arc(unsafe { Arc::from_raw(ptr) })
+    }
+}
+
+impl<T: Clone> CombRc<T> {
+    /// Constructs a `CombRc` from a raw pointer returned by [`CombRc::into_raw`].
+    ///
+    /// This just calls [`Rc::from_raw`].
+    ///
+    /// # Safety
+    ///
+    /// The raw pointer must have been previously returned by a call to `Rc<U>::into_raw` with the
+    /// following requirements:
+    ///
+    /// * If `U` is sized, it must have the same size and alignment as `T`. This is trivially true
+    ///   if `U` is `T`.
+    /// * If `U` is unsized, its data pointer must have the same size and alignment as `T`. This is
+    ///   trivially true if `Rc<U>` was constructed through `Rc<T>` and then converted to `Rc<U>`
+    ///   through an unsized coercion.
+    ///
+    /// Note that if `U` or `U`'s data pointer is not `T` but has the same size and alignment, this
+    /// is basically like transmuting references of different types. See [`core::mem::transmute`]
+    /// for more information on what restrictions apply in this case.
+    ///
+    /// The raw pointer must point to a block of memory allocated by the global allocator.
+    ///
+    /// The user of `from_raw` has to make sure a specific value of `T` is only dropped once.
+    ///
+    /// This function is unsafe because improper use may lead to memory unsafety, even if the
+    /// returned `CombRc<T>` is never accessed.
+    #[inline]
+    pub unsafe fn from_raw(ptr: *const T) -> CombRc<T> {
+        CombRc::from_rc(unsafe { Rc::from_raw(ptr) })
+    }
+}
@@ -1,110 +1,1570 @@
-use alloc::borrow::ToOwned;
-use alloc::rc::Rc;
+#[cfg(feature = "allocator_api")]
+use alloc::alloc::{Allocator, Global};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::boxed::Box;
+use alloc::rc::{Rc, Weak};
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::any::{Any, TypeId};
+#[cfg(feature = "std")]
+use core::cell::RefCell;
 use core::cmp::Ordering;
 use core::fmt::Formatter;
+use core::iter::{FromIterator, Product, Sum};
+use core::marker::PhantomData;
+use crate::{CloneOnWrite, CombArc, CombRcRef, CombRcTransaction, CombRcWeak, CombRcWriteGuard, CowPolicy, FrozenCombRc, TryClone, UniqueCombRc};
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// A wrapper around an [`Rc`] that clones when mutably borrowed if it is not unique.
 ///
 /// Like [`Rc`], this is not thread-safe.
 ///
+/// What happens when a shared `CombRc` is mutably borrowed is decided by the [`CowPolicy`] `P`. By
+/// default, it is [`CloneOnWrite`], which just clones.
+///
 /// # Examples
 ///
 /// See the crate documentation for examples.
-#[derive(Clone, Default, Debug, Ord, PartialOrd, Eq, PartialEq)]
-#[repr(transparent)]
-pub struct CombRc<T: Clone> {
-    inner: Rc<T>
+#[cfg_attr(not(feature = "debug_hooks"), repr(transparent))]
+pub struct CombRc<T: Clone, P: CowPolicy = CloneOnWrite, #[cfg(feature = "allocator_api")] A: Allocator = Global> {
+    #[cfg(not(feature = "allocator_api"))]
+    inner: Rc<T>,
+    #[cfg(feature = "allocator_api")]
+    inner: Rc<T, A>,
+    policy: PhantomData<P>,
+    #[cfg(feature = "debug_hooks")]
+    budget: crate::debug_hooks::BudgetSlot
 }
 
 impl<T: Clone> CombRc<T> {
     /// Constructs a `CombRc`.
     #[inline]
     pub fn new(what: T) -> CombRc<T> {
+        Self::from_rc(Rc::new(what))
+    }
+
+    /// Constructs a `CombRc` with a clone of a value.
+    ///
+    /// This is the same as converting from `&T`.
+    #[inline]
+    pub fn from_ref(what: &T) -> CombRc<T> {
+        Self::new(what.clone())
+    }
+
+    /// Constructs a `CombRc` which holds a weak reference to itself.
+    ///
+    /// This just calls [`Rc::new_cyclic`], so the weak reference cannot be upgraded until this
+    /// function returns.
+    ///
+    /// Keep in mind that mutably borrowing affects the weak reference like any other:
+    /// * If the `CombRc` is shared, the clone will contain a weak reference to the original
+    ///   allocation, not to itself.
+    /// * If the `CombRc` is unique, the value is moved to a new allocation and the weak reference
+    ///   will no longer upgrade.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    /// use std::rc::{Rc, Weak};
+    ///
+    /// #[derive(Clone)]
+    /// struct Node {
+    ///     this: Weak<Node>,
+    ///     value: u32
+    /// }
+    ///
+    /// let mut node = CombRc::new_cyclic(|this| Node { this: this.clone(), value: 1 });
+    /// let original = node.clone();
+    /// assert!(Rc::ptr_eq(&node.this.upgrade().unwrap(), CombRc::get_rc(&node)));
+    ///
+    /// // `node` is shared, so it gets cloned, but its weak reference still points to `original`.
+    /// node.value = 2;
+    /// assert!(Rc::ptr_eq(&node.this.upgrade().unwrap(), CombRc::get_rc(&original)));
+    /// ```
+    #[inline]
+    pub fn new_cyclic<F: FnOnce(&Weak<T>) -> T>(data_fn: F) -> CombRc<T> {
+        Self::from_rc(Rc::new_cyclic(data_fn))
+    }
+
+    /// Get a `CombRc` of the default value which is shared by every call with the same type on the
+    /// current thread.
+    ///
+    /// The default value is constructed on the first call on each thread and is dropped when the
+    /// thread exits. Since every result shares one allocation, the first mutable borrow of each one
+    /// will clone it, as with any other shared `CombRc`. This is useful if the default value is
+    /// expensive to construct, such as a large lookup table.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    ///
+    /// let mut a = CombRc::<Vec<u32>>::default_shared();
+    /// let b = CombRc::<Vec<u32>>::default_shared();
+    /// assert!(CombRc::ptr_eq(&a, &b));
+    ///
+    /// a.push(1);
+    /// assert!(!CombRc::ptr_eq(&a, &b));
+    /// assert!(b.is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn default_shared() -> CombRc<T> where T: Default + 'static {
+        std::thread_local! {
+            static DEFAULTS: RefCell<BTreeMap<TypeId, Box<dyn Any>>> = const { RefCell::new(BTreeMap::new()) };
+        }
+
+        // The map is not borrowed while constructing the default value, since it may call this too.
+        let lookup = || DEFAULTS.with(|d| d.borrow()
+            .get(&TypeId::of::<T>())
+            .and_then(|d| d.downcast_ref::<Rc<T>>())
+            .cloned());
+
+        let inner = lookup().unwrap_or_else(|| {
+            let value = Rc::new(T::default());
+            DEFAULTS.with(|d| d.borrow_mut()
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Box::new(value))
+                .downcast_ref::<Rc<T>>()
+                .expect("default values should be stored by their type")
+                .clone())
+        });
+
+        Self::from_rc(inner)
+    }
+
+    /// Constructs a `CombRc` which panics if mutably borrowing it and its clones makes more than
+    /// `budget` copy-on-write clones.
+    ///
+    /// The budget is shared with every clone of this reference, and is kept after a copy-on-write
+    /// clone, so it catches references which are unexpectedly shared and then mutated. Use
+    /// [`CombRc::try_make_mut_within_budget`] to get an error instead of panicking, and
+    /// [`CombRc::clones_used`] to see how much of the budget is used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    ///
+    /// let mut frame = CombRc::with_clone_budget(vec![0u8; 16], 1);
+    /// let snapshot = frame.clone();
+    ///
+    /// frame[0] = 1; // clones, using up the budget
+    /// assert_eq!(CombRc::clones_used(&frame), Some(1));
+    ///
+    /// let another_snapshot = frame.clone();
+    /// assert!(CombRc::try_make_mut_within_budget(&mut frame).is_err());
+    /// ```
+    #[cfg(feature = "debug_hooks")]
+    pub fn with_clone_budget(what: T, budget: usize) -> CombRc<T> {
         Self {
-            inner: Rc::new(what)
+            inner: Rc::new(what),
+            policy: PhantomData,
+            budget: crate::debug_hooks::BudgetSlot::new(budget)
         }
     }
 
     /// Constructs a `CombRc` from an already created `Rc`.
     #[inline]
     pub fn from_rc(what: Rc<T>) -> CombRc<T> {
+        Self::from_inner(what)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, A: Allocator + Clone> CombRc<T, CloneOnWrite, A> {
+    /// Constructs a `CombRc` in the given allocator.
+    ///
+    /// Copy-on-write clones are allocated with a clone of the same allocator. Only [`Clone`],
+    /// [`Deref`], [`DerefMut`], and [`CombRc::allocator`] are available for allocators other than
+    /// [`Global`] so far.
+    ///
+    /// This just calls [`Rc::new_in`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// #![feature(allocator_api)]
+    /// use combarc::CombRc;
+    /// use std::alloc::{AllocError, Allocator, Global, Layout};
+    /// use std::ptr::NonNull;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Counting(Arc<AtomicUsize>);
+    ///
+    /// unsafe impl Allocator for Counting {
+    ///     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    ///         self.0.fetch_add(1, Ordering::Relaxed);
+    ///         Global.allocate(layout)
+    ///     }
+    ///
+    ///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+    ///         unsafe { Global.deallocate(ptr, layout) }
+    ///     }
+    /// }
+    ///
+    /// let allocator = Counting::default();
+    /// let mut my_value = CombRc::new_in(1u32, allocator.clone());
+    /// let another_value = my_value.clone();
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 1);
+    ///
+    /// *my_value += 1; // clones into `allocator`
+    /// assert_eq!(allocator.0.load(Ordering::Relaxed), 2);
+    /// assert_eq!((*my_value, *another_value), (2, 1));
+    /// ```
+    ///
+    /// [`Deref`]: core::ops::Deref
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn new_in(what: T, alloc: A) -> CombRc<T, CloneOnWrite, A> {
         Self {
-            inner: what
+            inner: Rc::new_in(what, alloc),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: Default::default()
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> CombRc<T, P, A> {
+    /// Get a reference to the allocator of the inner value.
+    ///
+    /// This just calls [`Rc::allocator`].
+    #[inline]
+    pub fn allocator(what: &CombRc<T, P, A>) -> &A {
+        Rc::allocator(&what.inner)
+    }
+
+    /// [`CombRc::make_mut`] for any allocator.
+    fn make_mut_in(what: &mut CombRc<T, P, A>) -> &mut T {
+        let shared = Rc::strong_count(&what.inner) != 1;
+        if P::INTERCEPTS_CLONES && shared {
+            P::before_clone::<T>();
+        }
+        #[cfg(feature = "debug_hooks")]
+        if shared {
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+        }
+        Rc::make_mut(&mut what.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombRc<T, P> {
+    fn from_inner(inner: Rc<T>) -> CombRc<T, P> {
+        Self {
+            inner,
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: Default::default()
+        }
+    }
+
+    /// Convert this reference to one with a different [`CowPolicy`].
+    ///
+    /// This does not clone or allocate, so the new reference still shares the inner value with any
+    /// other references.
+    #[inline]
+    pub fn with_policy<Q: CowPolicy>(what: CombRc<T, P>) -> CombRc<T, Q> {
+        CombRc {
+            inner: what.inner,
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: what.budget
         }
     }
 
     /// Clones the inner value stored in the `CombRc`, returning a unique clone of it.
     #[inline]
-    pub fn clone_unique(what: &CombRc<T>) -> CombRc<T> {
-        Self::new(what.inner.as_ref().clone())
+    pub fn clone_unique(what: &CombRc<T, P>) -> CombRc<T, P> {
+        Self::from_inner(Rc::new(what.inner.as_ref().clone()))
     }
 
     /// Attempt to get the inner value inside of the `CombRc`.
     ///
     /// If this is a unique reference, the inner value will be moved. Otherwise, the reference will
     /// be re-returned.
+    ///
+    /// There is no `TryFrom<CombRc<T>>` implementation for `T`, since the orphan rules do not allow
+    /// it.
+    #[inline]
+    pub fn try_unwrap(what: CombRc<T, P>) -> Result<T, Self> {
+        Rc::try_unwrap(what.inner).map_err(Self::from_inner)
+    }
+
+    /// Get the inner value inside of the `CombRc` if this is a unique reference.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, the reference will
+    /// be dropped and `None` is returned.
+    ///
+    /// Unlike [`CombRc::try_unwrap`], this does not give the reference back on failure, and unlike
+    /// [`CombRc::make_inner`], this never clones.
     #[inline]
-    pub fn try_unwrap(what: CombRc<T>) -> Result<T, Self> {
-        Rc::try_unwrap(what.inner).map_err(Self::from_rc)
+    pub fn into_inner(what: CombRc<T, P>) -> Option<T> {
+        Rc::try_unwrap(what.inner).ok()
     }
 
     /// Try to get the inner value inside of the `CombArc` or clone otherwise.
     ///
     /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
     #[inline]
-    pub fn make_inner(what: CombRc<T>) -> T {
+    pub fn make_inner(what: CombRc<T, P>) -> T {
         Rc::try_unwrap(what.inner).unwrap_or_else(|e| T::to_owned(e.as_ref()))
     }
 
+    /// Move the inner value into a new [`Box`].
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    ///
+    /// There is no `From<CombRc<T>>` implementation for `Box<T>`, since the orphan rules do not
+    /// allow it.
+    #[inline]
+    pub fn into_box(what: CombRc<T, P>) -> Box<T> {
+        Box::new(Self::make_inner(what))
+    }
+
+    /// Borrow the inner value as a [`Cow`], without cloning it.
+    ///
+    /// This is the same as converting from `&CombRc<T>`.
+    #[inline]
+    pub fn to_cow(what: &CombRc<T, P>) -> Cow<'_, T> {
+        Cow::Borrowed(&what.inner)
+    }
+
+    /// Get the inner value as an owned [`Cow`].
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    #[inline]
+    pub fn into_cow(what: CombRc<T, P>) -> Cow<'static, T> {
+        Cow::Owned(Self::make_inner(what))
+    }
+
+    /// Move the inner value into a new [`Box`] and leak it, returning a mutable reference to it
+    /// which lives for the rest of the program.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned,
+    /// and any other references are unaffected.
+    ///
+    /// This always allocates, and the memory is intentionally never freed, so it should only be
+    /// used for values which are needed until the program exits, such as configuration.
+    #[inline]
+    pub fn leak_inner(what: CombRc<T, P>) -> &'static mut T where T: 'static {
+        Box::leak(Self::into_box(what))
+    }
+
+    /// Move the inner value into a new [`Box`] and leak it, returning a reference to it which lives
+    /// for the rest of the program.
+    ///
+    /// This is the same as [`CombRc::leak_inner`], except the returned reference is immutable.
+    #[inline]
+    pub fn leak(what: CombRc<T, P>) -> &'static T where T: 'static {
+        Self::leak_inner(what)
+    }
+
+    /// Convert this into a [`FrozenCombRc`], which cannot be mutably borrowed.
+    ///
+    /// This does not clone, so it still shares the inner value with any other references.
+    #[inline]
+    pub fn freeze(what: CombRc<T, P>) -> FrozenCombRc<T> {
+        FrozenCombRc::from_rc(what.inner)
+    }
+
+    /// Convert this into a [`UniqueCombRc`], which can be mutably borrowed without checking whether it
+    /// is shared.
+    ///
+    /// If this is a unique reference, the inner value will be moved. Otherwise, it will be cloned.
+    #[inline]
+    pub fn into_unique(what: CombRc<T, P>) -> UniqueCombRc<T, P> {
+        UniqueCombRc::from_inner(Self::make_inner(what))
+    }
+
+    /// Make equal values in a slice share one allocation, returning how many allocations were
+    /// eliminated.
+    ///
+    /// For each set of equal values, every reference is pointed to the allocation of the first one
+    /// in the slice. References which already point to it are not touched, and no value is ever
+    /// mutated or cloned. Allocations which are no longer referenced are dropped, along with any
+    /// weak references to them.
+    ///
+    /// The returned count is the number of distinct allocations referenced by the slice before,
+    /// minus the number referenced after.
+    #[cfg(feature = "std")]
+    pub fn unify(handles: &mut [CombRc<T, P>]) -> usize where T: Eq + core::hash::Hash {
+        let allocations_before: HashSet<*const T> = handles.iter().map(Self::as_ptr).collect();
+
+        let mut representatives: HashMap<&T, usize> = HashMap::with_capacity(handles.len());
+        let indices: Vec<usize> = handles.iter()
+            .enumerate()
+            .map(|(index, handle)| *representatives.entry(handle.inner.as_ref()).or_insert(index))
+            .collect();
+        let allocations_after = representatives.len();
+
+        for (index, representative) in indices.into_iter().enumerate() {
+            if !Self::ptr_eq(&handles[index], &handles[representative]) {
+                handles[index] = handles[representative].clone();
+            }
+        }
+
+        allocations_before.len() - allocations_after
+    }
+
     /// Get the inner `Rc` value.
     #[inline]
-    pub fn get_rc(what: &CombRc<T>) -> &Rc<T> {
+    pub fn get_rc(what: &CombRc<T, P>) -> &Rc<T> {
         &what.inner
     }
-}
 
-impl<T: Clone + PartialEq> PartialEq<T> for CombRc<T> {
-    fn eq(&self, other: &T) -> bool {
-        Rc::as_ref(&self.inner) == other
+    /// Get a mutable reference to the inner `Rc` value.
+    ///
+    /// This is an escape hatch for using APIs which need a `&mut Rc<T>`. Anything done through it
+    /// bypasses copy-on-write, such as replacing the `Rc` with one shared elsewhere. However, the
+    /// next mutable borrow of this `CombRc` will still clone if the new `Rc` is shared.
+    #[inline]
+    pub fn get_rc_mut(what: &mut CombRc<T, P>) -> &mut Rc<T> {
+        &mut what.inner
     }
-}
 
-impl<T: Clone + PartialOrd> PartialOrd<T> for CombRc<T> {
-    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
-        Rc::as_ref(&self.inner).partial_cmp(other)
+    /// Replace the inner `Rc` value.
+    ///
+    /// This bypasses copy-on-write for this assignment, so other holders of `value` will share the
+    /// allocation with this `CombRc` until either of them is mutably borrowed.
+    #[inline]
+    pub fn set_rc(what: &mut CombRc<T, P>, value: Rc<T>) {
+        what.inner = value;
     }
-}
 
-impl<T: Clone> From<CombRc<T>> for Rc<T> {
-    fn from(value: CombRc<T>) -> Self {
-        value.inner
+    /// Get a raw pointer to the inner value.
+    ///
+    /// This is the address of the shared allocation, so it stays the same until the value is
+    /// cloned by a mutable borrow. Unlike calling `as_ptr` as a method, this never resolves to a
+    /// method of the inner type.
+    #[inline]
+    pub fn as_ptr(what: &CombRc<T, P>) -> *const T {
+        Rc::as_ptr(&what.inner)
     }
-}
 
-impl<T: Clone> From<Rc<T>> for CombRc<T> {
-    fn from(value: Rc<T>) -> Self {
-        CombRc::from_rc(value)
+    /// Get a raw pointer to the inner value, for passing across FFI boundaries.
+    ///
+    /// This is the same as [`CombRc::as_ptr`]. The pointer is only valid for as long as a strong
+    /// reference to this allocation exists.
+    #[inline]
+    pub fn as_raw(what: &CombRc<T, P>) -> *const T {
+        Self::as_ptr(what)
     }
-}
 
-impl<T: Clone> core::ops::Deref for CombRc<T> {
-    type Target = T;
-    fn deref(&self) -> &T {
-        self.inner.as_ref()
+    /// Consume the `CombRc`, returning a raw pointer to the inner value.
+    ///
+    /// This just calls [`Rc::into_raw`] on the inner value, so the strong reference is not
+    /// dropped. If the pointer is never turned back into a strong reference with
+    /// [`Rc::from_raw`], the inner value will be leaked.
+    #[inline]
+    pub fn into_raw(what: CombRc<T, P>) -> *const T {
+        Rc::into_raw(what.inner)
     }
-}
 
-impl<T: Clone> core::ops::DerefMut for CombRc<T> {
-    /// If the inner [`Rc`] has no strong references, get a mutable reference. Otherwise, clone the
-    /// [`Rc`].
-    /// 
-    /// This just calls [`Rc::make_mut`] on the inner value.
+    /// Returns `true` if both `CombRc`s point to the same allocation.
+    ///
+    /// This is unrelated to value equality: two `CombRc`s can be equal without sharing an
+    /// allocation, and a value that does not implement [`PartialEq`] can still be compared this
+    /// way.
+    #[inline]
+    pub fn ptr_eq(a: &CombRc<T, P>, b: &CombRc<T, P>) -> bool {
+        Rc::ptr_eq(&a.inner, &b.inner)
+    }
+
+    /// Get the number of strong references to the inner value.
+    #[inline]
+    pub fn strong_count(what: &CombRc<T, P>) -> usize {
+        Rc::strong_count(&what.inner)
+    }
+
+    /// Get the number of weak references to the inner value.
+    #[inline]
+    pub fn weak_count(what: &CombRc<T, P>) -> usize {
+        Rc::weak_count(&what.inner)
+    }
+
+    /// Returns `true` if this is the only strong reference to the inner value.
+    ///
+    /// If this returns `true`, mutably borrowing will not clone the inner value. However, if there
+    /// are any weak references, mutably borrowing will still dissociate them, as the value is
+    /// moved to a new allocation.
+    #[inline]
+    pub fn is_unique(what: &CombRc<T, P>) -> bool {
+        Rc::strong_count(&what.inner) == 1
+    }
+
+    /// Returns `true` if there are other strong references to the inner value.
+    ///
+    /// If this returns `true`, mutably borrowing will clone the inner value. This is the opposite
+    /// of [`CombRc::is_unique`].
+    #[inline]
+    pub fn is_shared(what: &CombRc<T, P>) -> bool {
+        !Self::is_unique(what)
+    }
+
+    /// Get a mutable reference to the inner value if it is not shared, without ever cloning it.
+    ///
+    /// Returns `None` if there are other strong references or any weak references, in which case
+    /// nothing is changed. This just calls [`Rc::get_mut`] on the inner value.
+    #[inline]
+    pub fn get_mut_if_unique(what: &mut CombRc<T, P>) -> Option<&mut T> {
+        Rc::get_mut(&mut what.inner)
+    }
+
+    /// Mutate the inner value with a closure only if it is not shared, returning `true` if the
+    /// closure was called.
+    ///
+    /// This uses [`CombRc::get_mut_if_unique`], so it never clones or dissociates weak references.
+    /// This is useful for opportunistic cleanup which is not worth cloning for.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    ///
+    /// let mut buffer = CombRc::new(Vec::<u8>::with_capacity(1024));
+    /// buffer.extend_from_slice(b"hello");
+    ///
+    /// // Shared, so trimming it is not worth cloning for.
+    /// let another_buffer = buffer.clone();
+    /// assert!(!CombRc::apply_if_unique(&mut buffer, Vec::shrink_to_fit));
+    /// assert!(buffer.capacity() >= 1024);
+    ///
+    /// // Unique, so it can be trimmed for free.
+    /// drop(another_buffer);
+    /// assert!(CombRc::apply_if_unique(&mut buffer, Vec::shrink_to_fit));
+    /// assert!(buffer.capacity() < 1024);
+    /// ```
+    #[inline]
+    pub fn apply_if_unique<F: FnOnce(&mut T)>(what: &mut CombRc<T, P>, f: F) -> bool {
+        match Self::get_mut_if_unique(what) {
+            Some(inner) => {
+                f(inner);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Mutate the inner value with a closure only if a predicate on the current value returns
+    /// `true`, returning `true` if the closure was called.
+    ///
+    /// The predicate only gets a shared reference, so if it returns `false`, the inner value is left
+    /// untouched and never cloned. Otherwise, this calls [`CombRc::make_mut`] and then the closure.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut counts = CombRc::new(HashMap::from([("apples", 1u32)]));
+    /// let another_counts = counts.clone();
+    ///
+    /// // The key is absent, so the map is not cloned.
+    /// assert!(!CombRc::mutate_if(&mut counts, |m| m.contains_key("pears"), |m| *m.get_mut("pears").unwrap() += 1));
+    /// assert!(CombRc::ptr_eq(&counts, &another_counts));
+    ///
+    /// // The key is present, so the map is cloned and then modified.
+    /// assert!(CombRc::mutate_if(&mut counts, |m| m.contains_key("apples"), |m| *m.get_mut("apples").unwrap() += 1));
+    /// assert_eq!(counts["apples"], 2);
+    /// assert_eq!(another_counts["apples"], 1);
+    /// ```
+    #[inline]
+    pub fn mutate_if<C: FnOnce(&T) -> bool, F: FnOnce(&mut T)>(what: &mut CombRc<T, P>, predicate: C, f: F) -> bool {
+        if predicate(&what.inner) {
+            f(Self::make_mut(what));
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get a mutable reference to the inner value, cloning it first if it is shared.
+    ///
+    /// This is what mutably borrowing through [`DerefMut`] does, but named so it can be called
+    /// explicitly. If there are no other strong references but there are weak references, the
+    /// weak references will be dissociated.
+    ///
+    /// This just calls [`Rc::make_mut`] on the inner value, after calling
+    /// [`CowPolicy::before_clone`] if it is shared.
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn make_mut(what: &mut CombRc<T, P>) -> &mut T {
+        if P::INTERCEPTS_CLONES && Self::is_shared(what) {
+            P::before_clone::<T>();
+        }
+        #[cfg(feature = "debug_hooks")]
+        if Self::is_shared(what) {
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+        }
+        Rc::make_mut(&mut what.inner)
+    }
+
+    /// Get a mutable reference to the inner value, attempting to clone it first if it is shared.
+    ///
+    /// This is the same as [`CombRc::make_mut`], but the inner value is cloned with
+    /// [`TryClone::try_clone`]. If that fails, the error is returned and this reference still
+    /// points to the shared value. A clone budget is only used if cloning succeeds.
+    #[inline]
+    pub fn try_make_mut(what: &mut CombRc<T, P>) -> Result<&mut T, T::Error> where T: TryClone {
+        if Self::is_shared(what) {
+            P::before_clone::<T>();
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::check_clone::<T>();
+            let value = what.inner.try_clone()?;
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::spend_budget::<T>(&what.budget);
+            what.inner = Rc::new(value);
+        }
+        Ok(Self::make_mut(what))
+    }
+
+    /// Get a mutable reference to the inner value, cloning it first with `clone` if it is shared.
+    ///
+    /// This is the same as [`CombRc::make_mut`], but `clone` is called instead of [`Clone::clone`]
+    /// when a clone is needed, e.g. to reset cached data rather than copying it. If this is a
+    /// unique reference, `clone` is not called.
+    ///
+    /// The result of `clone` is used as-is, so it should be a logically equivalent starting point
+    /// for the mutation.
+    #[inline]
+    pub fn make_mut_with<F: FnOnce(&T) -> T>(what: &mut CombRc<T, P>, clone: F) -> &mut T {
+        if Self::is_shared(what) {
+            P::before_clone::<T>();
+            #[cfg(feature = "debug_hooks")]
+            crate::debug_hooks::before_clone::<T>(&what.budget);
+            what.inner = Rc::new(clone(&what.inner));
+        }
+        Self::make_mut(what)
+    }
+
+    /// Get a mutable reference to the inner value like [`CombRc::make_mut`], but return an error
+    /// instead of panicking if cloning it would exceed its clone budget.
+    ///
+    /// See [`CombRc::with_clone_budget`]. References without a clone budget never return an error.
+    #[cfg(feature = "debug_hooks")]
+    pub fn try_make_mut_within_budget(what: &mut CombRc<T, P>) -> Result<&mut T, crate::CloneBudgetExceeded> {
+        if Self::is_shared(what) {
+            what.budget.try_spend()?;
+            P::before_clone::<T>();
+            crate::debug_hooks::check_clone::<T>();
+        }
+        Ok(Rc::make_mut(&mut what.inner))
+    }
+
+    /// Get the number of copy-on-write clones used from the clone budget of this reference.
+    ///
+    /// Returns `None` if this reference has no clone budget. See [`CombRc::with_clone_budget`].
+    #[cfg(feature = "debug_hooks")]
+    pub fn clones_used(what: &CombRc<T, P>) -> Option<usize> {
+        what.budget.used()
+    }
+
+    /// Get a guard which only calls [`CombRc::make_mut`] once it is mutably dereferenced.
+    ///
+    /// Reading through the guard never clones, so if it is never mutably dereferenced, this
+    /// reference is left untouched. See [`CombRcWriteGuard`].
+    #[inline]
+    pub fn write(what: &mut CombRc<T, P>) -> CombRcWriteGuard<'_, T, P> {
+        CombRcWriteGuard::new(what)
+    }
+
+    /// Start a transaction which puts back the current value unless it is committed.
+    ///
+    /// See [`CombRcTransaction`].
+    #[inline]
+    pub fn transaction(what: &mut CombRc<T, P>) -> CombRcTransaction<'_, T, P> {
+        CombRcTransaction::new(what)
+    }
+
+    /// Edit the inner value with a closure, putting back the previous value if it returns an error
+    /// or panics.
+    ///
+    /// Like [`CombRc::transaction`], this always clones the inner value if it is mutably borrowed.
+    pub fn transact<R, E, F: FnOnce(&mut T) -> Result<R, E>>(what: &mut CombRc<T, P>, f: F) -> Result<R, E> {
+        let mut transaction = Self::transaction(what);
+        let result = f(&mut transaction)?;
+        CombRcTransaction::commit(transaction);
+        Ok(result)
+    }
+
+    /// Get a mutable reference to the inner value like [`CombRc::make_mut`], but without ever
+    /// dissociating weak references.
+    ///
+    /// * If there are no weak references, this is the same as [`CombRc::make_mut`].
+    /// * If there are other strong references, the inner value is cloned. Weak references keep
+    ///   pointing to the old allocation, which the other strong references keep alive.
+    /// * If this is the only strong reference but there are weak references, `None` is returned
+    ///   and nothing is changed.
+    ///
+    /// The last case cannot mutate in place, since a weak reference could be upgraded while the
+    /// mutable reference is still in use, and cloning would not help either, since the old
+    /// allocation would be dropped along with this reference.
+    #[inline]
+    pub fn make_mut_keep_weak(what: &mut CombRc<T, P>) -> Option<&mut T> {
+        if Self::is_unique(what) && Self::weak_count(what) != 0 {
+            None
+        } else {
+            Some(Self::make_mut(what))
+        }
+    }
+
+    /// Make sure this is a unique reference, returning `true` if a new allocation was made.
+    ///
+    /// This does the same thing as [`CombRc::make_mut`] without returning the reference, so it can be
+    /// called before a series of mutations. Afterwards, [`CombRc::is_unique`] will return `true`.
+    ///
+    /// A new allocation is made if the inner value is shared (in which case it is cloned), or if
+    /// there are weak references (in which case it is moved and the weak references dissociated).
+    #[inline]
+    pub fn ensure_unique(what: &mut CombRc<T, P>) -> bool {
+        let address = Self::as_ptr(what);
+        Self::make_mut(what);
+        address != Self::as_ptr(what)
+    }
+
+    /// Take the inner value out, leaving a default value in its place.
+    ///
+    /// If this is a unique reference, the inner value is moved out and replaced with
+    /// [`T::default`](Default::default). Otherwise, the inner value is cloned first like with any
+    /// other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn take(what: &mut CombRc<T, P>) -> T where T: Default {
+        core::mem::take(Self::make_mut(what))
+    }
+
+    /// Replace the inner value, returning the old value.
+    ///
+    /// If this is a unique reference, the inner value is swapped in place. Otherwise, the old value
+    /// is cloned first like with any other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn replace(what: &mut CombRc<T, P>, value: T) -> T {
+        core::mem::replace(Self::make_mut(what), value)
+    }
+
+    /// Swap the inner value with `other`.
+    ///
+    /// If this is a unique reference, the values are swapped in place. Otherwise, the inner value
+    /// is cloned first like with any other mutable borrow, leaving other references untouched.
+    #[inline]
+    pub fn swap_inner(what: &mut CombRc<T, P>, other: &mut T) {
+        core::mem::swap(Self::make_mut(what), other)
+    }
+
+    /// Overwrite the inner value without cloning the old one.
+    ///
+    /// If this is a unique reference with no weak references, the value is assigned in place.
+    /// Otherwise, this reference is pointed to a new allocation containing `value`, leaving other
+    /// references untouched. In both cases, [`Clone::clone`] is never called, so this is the
+    /// preferred way to overwrite the whole value rather than assigning through [`DerefMut`].
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    #[inline]
+    pub fn set(what: &mut CombRc<T, P>, value: T) {
+        match Rc::get_mut(&mut what.inner) {
+            Some(inner) => *inner = value,
+            None => what.inner = Rc::new(value)
+        }
+    }
+
+    /// Overwrite the inner value if it is not equal to `value`, returning `true` if it was
+    /// overwritten.
+    ///
+    /// The comparison is done through a shared reference, so if the values are equal, nothing is
+    /// cloned and the address of the inner value stays the same. Otherwise, this does the same
+    /// thing as [`CombRc::set`].
+    #[inline]
+    pub fn set_if_ne(what: &mut CombRc<T, P>, value: T) -> bool where T: PartialEq {
+        if *what.inner == value {
+            false
+        } else {
+            Self::set(what, value);
+            true
+        }
+    }
+
+    /// Mutate the inner value with a closure, returning whatever the closure returns.
+    ///
+    /// This calls [`CombRc::make_mut`] exactly once, so the inner value is cloned first if it is
+    /// shared, and the closure is given a mutable reference to the result.
+    #[inline]
+    pub fn update<R, F: FnOnce(&mut T) -> R>(what: &mut CombRc<T, P>, f: F) -> R {
+        f(Self::make_mut(what))
+    }
+
+    /// Mutate the inner value with a closure and return the `CombRc`, for chaining.
+    ///
+    /// A newly constructed `CombRc` is unique, so this will not clone unless the `CombRc` was shared
+    /// beforehand, in which case it is cloned like with any other mutable borrow.
+    ///
+    /// Unlike most functions on `CombRc`, this is a method so that it can be chained. As such, it
+    /// takes priority over any `with` method on the inner type, which can still be called by
+    /// dereferencing (e.g. `(*value).with()`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Config {
+    ///     retries: u32,
+    ///     name: String
+    /// }
+    ///
+    /// let config = CombRc::new(Config::default())
+    ///     .with(|c| c.retries = 3)
+    ///     .with(|c| c.name = "x".into());
+    /// assert_eq!(config.retries, 3);
+    /// assert_eq!(config.name, "x");
+    /// ```
+    #[inline]
+    pub fn with<F: FnOnce(&mut T)>(mut self, f: F) -> CombRc<T, P> {
+        f(Self::make_mut(&mut self));
+        self
+    }
+
+    /// Create a new `CombRc` from a reference to the inner value.
+    ///
+    /// The result is always a new allocation. Other references to the inner value are untouched.
+    #[inline]
+    pub fn map<U: Clone, F: FnOnce(&T) -> U>(what: CombRc<T, P>, f: F) -> CombRc<U, P> {
+        CombRc::from_inner(Rc::new(f(&what)))
+    }
+
+    /// Create a new `CombRc` from the inner value.
+    ///
+    /// If this is a unique reference, the inner value will be moved into `f`. Otherwise, it will
+    /// be cloned, and other references to it are untouched. See [`CombRc::make_inner`].
+    #[inline]
+    pub fn map_owned<U: Clone, F: FnOnce(T) -> U>(what: CombRc<T, P>, f: F) -> CombRc<U, P> {
+        CombRc::from_inner(Rc::new(f(Self::make_inner(what))))
+    }
+
+    /// Attempt to create a new `CombRc` from a reference to the inner value.
+    ///
+    /// If `f` returns an error, the error is returned and nothing is allocated. Otherwise, the
+    /// result is a new allocation.
+    #[inline]
+    pub fn try_map<U: Clone, E, F: FnOnce(&T) -> Result<U, E>>(what: &CombRc<T, P>, f: F) -> Result<CombRc<U, P>, E> {
+        f(what).map(|v| CombRc::from_inner(Rc::new(v)))
+    }
+
+    /// Attempt to create a new `CombRc` from a reference to the inner value.
+    ///
+    /// If `f` returns `None`, `None` is returned and nothing is allocated. Otherwise, the result
+    /// is a new allocation.
+    #[inline]
+    pub fn filter_map<U: Clone, F: FnOnce(&T) -> Option<U>>(what: &CombRc<T, P>, f: F) -> Option<CombRc<U, P>> {
+        f(what).map(|v| CombRc::from_inner(Rc::new(v)))
+    }
+
+    /// Transform the inner value by value, returning a reference to the result.
+    ///
+    /// If this is a unique reference, the inner value is moved into `f`, so it is never cloned.
+    /// Otherwise, it is cloned once like with any other mutable borrow, leaving other references
+    /// untouched.
+    ///
+    /// Unlike [`CombRc::map_owned`], the clone goes through the [`CowPolicy`], and the result keeps
+    /// the clone budget of this reference, if any. This takes the `CombRc` by value since nothing
+    /// can be left in its place while `f` runs.
+    #[inline]
+    pub fn map_in_place<F: FnOnce(T) -> T>(what: CombRc<T, P>, f: F) -> CombRc<T, P> {
+        let mut what = what;
+        Self::make_mut(&mut what);
+        let value = Rc::try_unwrap(what.inner).unwrap_or_else(|inner| T::clone(&inner));
+        Self {
+            inner: Rc::new(f(value)),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: what.budget
+        }
+    }
+
+    /// Clone the inner value, mutate the clone with a closure, and return it as a new `CombRc`.
+    ///
+    /// This reference and any other references to the inner value are untouched.
+    #[inline]
+    pub fn modify_cloned<F: FnOnce(&mut T)>(what: &CombRc<T, P>, f: F) -> CombRc<T, P> {
+        let mut value = what.inner.as_ref().clone();
+        f(&mut value);
+        Self::from_inner(Rc::new(value))
+    }
+
+    /// Build a new inner value from the current one, store it in a new allocation, and return the
+    /// previous reference.
+    ///
+    /// The previous inner value is never cloned, and any other references to it keep sharing it
+    /// with the returned `CombRc`. This is useful for keeping old snapshots around, such as for undo
+    /// history.
+    #[inline]
+    pub fn fetch_update<F: FnOnce(&T) -> T>(what: &mut CombRc<T, P>, f: F) -> CombRc<T, P> {
+        let value = f(&what.inner);
+        core::mem::replace(what, Self::from_inner(Rc::new(value)))
+    }
+
+    /// Create a [`CombRcRef`] which dereferences to a part of the inner value, such as a field, while
+    /// keeping this reference.
+    ///
+    /// The projection must not capture anything, since it is stored as a function pointer.
+    #[inline]
+    pub fn project<U: ?Sized>(what: CombRc<T, P>, projection: fn(&T) -> &U) -> CombRcRef<T, U, P> {
+        CombRcRef::new(what, projection)
+    }
+
+    /// Get a mutable reference to a part of the inner value, such as a field, cloning the inner
+    /// value first if it is shared.
+    ///
+    /// This is the same as calling `projection` on [`CombRc::make_mut`]. The returned reference can
+    /// be used for any number of writes, and whether this is shared is only checked once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    ///
+    /// #[derive(Clone)]
+    /// struct Document {
+    ///     title: String,
+    ///     paragraphs: Vec<String>
+    /// }
+    ///
+    /// let mut document = CombRc::new(Document { title: "Hello".to_owned(), paragraphs: vec!["World".to_owned()] });
+    /// let another_document = document.clone();
+    ///
+    /// let paragraph = CombRc::project_mut(&mut document, |d| &mut d.paragraphs[0]);
+    /// paragraph.push('!');
+    /// paragraph.push('?');
+    ///
+    /// assert_eq!(document.paragraphs[0], "World!?");
+    /// assert_eq!(another_document.paragraphs[0], "World");
+    /// ```
+    #[inline]
+    pub fn project_mut<U: ?Sized, F: FnOnce(&mut T) -> &mut U>(what: &mut CombRc<T, P>, projection: F) -> &mut U {
+        projection(Self::make_mut(what))
+    }
+
+    /// Call the inner closure with an argument.
+    ///
+    /// Closures taking more than one argument can be called with the `fn_traits` feature, which
+    /// implements the [`Fn`] traits for `CombRc`.
+    #[inline]
+    pub fn call<A, R>(what: &CombRc<T, P>, argument: A) -> R where T: Fn(A) -> R {
+        (what.inner)(argument)
+    }
+
+    /// Call the inner closure with an argument, allowing it to mutate what it captured.
+    ///
+    /// This goes through [`CombRc::make_mut`], so if the closure is shared, it is cloned first, along
+    /// with everything it captured. Each reference which called it then has its own copy of the
+    /// captures, so for example, a counter captured by the closure will be counted separately by
+    /// each reference after the first call.
+    #[inline]
+    pub fn call_mut<A, R>(what: &mut CombRc<T, P>, argument: A) -> R where T: FnMut(A) -> R {
+        Self::make_mut(what)(argument)
+    }
+
+    /// Merge another reference into this one with a closure, returning the merged reference.
+    ///
+    /// The inner value of `what` is made mutable with [`CombRc::make_mut`], so it is not cloned if it
+    /// is unique. If both references share the same allocation, `what` is not unique, so the inner
+    /// value is cloned first and `f` is still called with the clone and the original.
+    #[inline]
+    pub fn merge_with<F: FnOnce(&mut T, &T)>(mut what: CombRc<T, P>, other: &CombRc<T, P>, f: F) -> CombRc<T, P> {
+        f(Self::make_mut(&mut what), &other.inner);
+        what
+    }
+
+    /// Create a [`Weak`] reference to the inner value.
+    ///
+    /// The weak reference refers to the current allocation, not to the `CombRc`, so:
+    /// * If the inner value is cloned by a mutable borrow, it will keep pointing to the old
+    ///   allocation which the other strong references still share.
+    /// * If there are no other strong references, a mutable borrow will move the value into a new
+    ///   allocation, and the weak reference will no longer be able to upgrade.
+    #[inline]
+    pub fn downgrade(what: &CombRc<T, P>) -> Weak<T> {
+        Rc::downgrade(&what.inner)
+    }
+
+    /// Create a [`CombRcWeak`] reference to the inner value.
+    ///
+    /// This is the same as [`CombRc::downgrade`], but upgrading it gives a `CombRc`.
+    #[inline]
+    pub fn downgrade_comb(what: &CombRc<T, P>) -> CombRcWeak<T> {
+        CombRcWeak::from_weak(Self::downgrade(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombRc<Option<T>, P> {
+    /// Convert a `CombRc<Option<T>>` into an `Option<CombRc<T>>`.
+    ///
+    /// If this is a unique reference, the inner value will be moved into a new allocation.
+    /// Otherwise, it will be cloned. If the inner value is `None`, the reference is simply dropped.
+    #[inline]
+    pub fn transpose(what: CombRc<Option<T>, P>) -> Option<CombRc<T, P>> {
+        Self::make_inner(what).map(|v| CombRc::from_inner(Rc::new(v)))
+    }
+}
+
+impl<T: Clone, E: Clone, P: CowPolicy> CombRc<Result<T, E>, P> {
+    /// Convert a `CombRc<Result<T, E>>` into a `Result<CombRc<T>, E>`.
+    ///
+    /// If this is a unique reference, the inner value will be moved, with `T` moved into a new
+    /// allocation. Otherwise, it will be cloned. The error is always returned by value.
+    ///
+    /// This is the `Result` version of [`CombRc::transpose`]. It has a different name, since calling
+    /// `CombRc::transpose` would otherwise be ambiguous.
+    #[inline]
+    pub fn into_result(what: CombRc<Result<T, E>, P>) -> Result<CombRc<T, P>, E> {
+        Self::make_inner(what).map(|v| CombRc::from_inner(Rc::new(v)))
+    }
+
+    /// Borrow the inner value as a `Result` of references without cloning or restructuring.
+    #[inline]
+    pub fn as_result(what: &CombRc<Result<T, E>, P>) -> Result<&T, &E> {
+        what.inner.as_ref().as_ref()
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> Clone for CombRc<T, P> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: self.budget.clone()
+        }
+    }
+
+    /// Make this reference equal to `source`.
+    ///
+    /// If this is a unique reference, the inner value of `source` is cloned into the existing
+    /// allocation with [`Clone::clone_from`], so its resources (such as the capacity of a `Vec`)
+    /// may be reused. Otherwise, this reference is pointed to the allocation of `source`.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        match Rc::get_mut(&mut self.inner) {
+            Some(inner) => inner.clone_from(&source.inner),
+            None => *self = source.clone()
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> Clone for CombRc<T, P, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: PhantomData,
+            #[cfg(feature = "debug_hooks")]
+            budget: self.budget.clone()
+        }
+    }
+
+    /// Make this reference equal to `source`.
+    ///
+    /// If this is a unique reference, the inner value of `source` is cloned into the existing
+    /// allocation with [`Clone::clone_from`], so its resources (such as the capacity of a `Vec`)
+    /// may be reused. Otherwise, this reference is pointed to the allocation of `source`.
+    #[inline]
+    fn clone_from(&mut self, source: &Self) {
+        match Rc::get_mut(&mut self.inner) {
+            Some(inner) => inner.clone_from(&source.inner),
+            None => *self = source.clone()
+        }
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for CombRc<T, P> {
+    fn default() -> Self {
+        Self::from_inner(Rc::default())
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation. This
+/// assumes that the inner value is equal to itself, which is not true of values such as `NaN`.
+///
+/// This cannot be generalized to `PartialEq<CombRc<U>>` for `T: PartialEq<U>`, since it would
+/// conflict with [`PartialEq<T>`] when `T` is itself a `CombRc`. To compare references with
+/// different inner types, compare the inner values instead, like `*a == *b`.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq for CombRc<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner) || *self.inner == *other.inner
+    }
+}
+
+impl<T: Clone + Eq, P: CowPolicy> Eq for CombRc<T, P> {}
+
+/// Compares by value.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombRc};
+///
+/// let actual = CombRc::new(vec![1, 2, 3]);
+/// let expected = CombArc::new(vec![1, 2, 3]);
+/// assert_eq!(actual, expected);
+/// ```
+impl<T: Clone + PartialEq, P: CowPolicy, Q: CowPolicy> PartialEq<CombArc<T, Q>> for CombRc<T, P> {
+    fn eq(&self, other: &CombArc<T, Q>) -> bool {
+        *self.inner == **other
+    }
+}
+
+/// Compares by value, returning [`Ordering::Equal`] without comparing if both sides share an
+/// allocation, like [`PartialEq`].
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd for CombRc<T, P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Rc::ptr_eq(&self.inner, &other.inner) {
+            Some(Ordering::Equal)
+        } else {
+            self.inner.partial_cmp(&other.inner)
+        }
+    }
+}
+
+/// Compares by value, returning [`Ordering::Equal`] without comparing if both sides share an
+/// allocation.
+impl<T: Clone + Ord, P: CowPolicy> Ord for CombRc<T, P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if Rc::ptr_eq(&self.inner, &other.inner) {
+            Ordering::Equal
+        } else {
+            self.inner.cmp(&other.inner)
+        }
+    }
+}
+
+impl<T: Clone + core::hash::Hash, P: CowPolicy> core::hash::Hash for CombRc<T, P> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<T: Clone + core::hash::Hasher, P: CowPolicy> core::hash::Hasher for CombRc<T, P> {
+    fn finish(&self) -> u64 {
+        self.inner.finish()
+    }
+
+    /// This goes through [`CombRc::make_mut`], so if the hasher is shared, its state is cloned first,
+    /// and the other references keep the state from before the write.
+    fn write(&mut self, bytes: &[u8]) {
+        Self::make_mut(self).write(bytes)
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        Self::make_mut(self).write_u8(i)
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        Self::make_mut(self).write_u16(i)
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        Self::make_mut(self).write_u32(i)
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        Self::make_mut(self).write_u64(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        Self::make_mut(self).write_u128(i)
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        Self::make_mut(self).write_usize(i)
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        Self::make_mut(self).write_i8(i)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        Self::make_mut(self).write_i16(i)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        Self::make_mut(self).write_i32(i)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        Self::make_mut(self).write_i64(i)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        Self::make_mut(self).write_i128(i)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        Self::make_mut(self).write_isize(i)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::Borrow<T> for CombRc<T, P> {
+    fn borrow(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::BorrowMut<T> for CombRc<T, P> {
+    /// This just calls [`CombRc::make_mut`], so it clones the inner value if it is shared.
+    fn borrow_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+impl<P: CowPolicy> core::borrow::Borrow<str> for CombRc<String, P> {
+    fn borrow(&self) -> &str {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::borrow::Borrow<[T]> for CombRc<Vec<T>, P> {
+    fn borrow(&self) -> &[T] {
+        &self.inner
+    }
+}
+
+impl<T: Clone + AsRef<U>, U: ?Sized, P: CowPolicy> AsRef<U> for CombRc<T, P> {
+    /// Unlike [`Rc`], this forwards to the inner value, so a `CombRc<String>` can be referenced as a
+    /// `str`, just like the `String` itself. As such, it cannot also be referenced as the inner
+    /// value, since the implementations would conflict. Use [`Deref`] or [`Borrow`] for that.
+    ///
+    /// Since this is a method of `CombRc`, calling `as_ref` as a method calls this, not the `as_ref`
+    /// of the inner value, such as [`Option::as_ref`]. Call those like `Option::as_ref(&value)`.
+    ///
+    /// [`Deref`]: core::ops::Deref
+    /// [`Borrow`]: core::borrow::Borrow
+    fn as_ref(&self) -> &U {
+        T::as_ref(&self.inner)
+    }
+}
+
+impl<T: Clone + AsMut<U>, U: ?Sized, P: CowPolicy> AsMut<U> for CombRc<T, P> {
+    /// This calls [`CombRc::make_mut`] and forwards to the inner value, so it clones the inner value
+    /// if it is shared. Like `AsRef`, it cannot also get the inner value itself. Use [`DerefMut`] or
+    /// [`BorrowMut`] for that.
+    ///
+    /// Whether this is shared is checked on every call, so calling this in a loop only clones the
+    /// inner value the first time.
+    ///
+    /// [`DerefMut`]: core::ops::DerefMut
+    /// [`BorrowMut`]: core::borrow::BorrowMut
+    fn as_mut(&mut self) -> &mut U {
+        T::as_mut(Self::make_mut(self))
+    }
+}
+
+/// Formats the inner value, like [`Display`](core::fmt::Display).
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Rc::as_ref(&self.inner).fmt(f)
+    }
+}
+
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<T> for CombRc<T, P> {
+    fn eq(&self, other: &T) -> bool {
+        Rc::as_ref(&self.inner) == other
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<T> for CombRc<T, P> {
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        Rc::as_ref(&self.inner).partial_cmp(other)
+    }
+}
+
+impl<'a, T: Clone + PartialEq, P: CowPolicy> PartialEq<&'a T> for CombRc<T, P> {
+    fn eq(&self, other: &&'a T) -> bool {
+        Rc::as_ref(&self.inner) == *other
+    }
+}
+
+impl<'a, 'b, T: Clone + PartialEq, P: CowPolicy> PartialEq<&'a &'b T> for CombRc<T, P> {
+    fn eq(&self, other: &&'a &'b T) -> bool {
+        Rc::as_ref(&self.inner) == **other
+    }
+}
+
+impl<'a, T: Clone + PartialOrd, P: CowPolicy> PartialOrd<&'a T> for CombRc<T, P> {
+    fn partial_cmp(&self, other: &&'a T) -> Option<Ordering> {
+        Rc::as_ref(&self.inner).partial_cmp(*other)
+    }
+}
+
+impl<'a, 'b, T: Clone + PartialOrd, P: CowPolicy> PartialOrd<&'a &'b T> for CombRc<T, P> {
+    fn partial_cmp(&self, other: &&'a &'b T) -> Option<Ordering> {
+        Rc::as_ref(&self.inner).partial_cmp(**other)
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<Rc<T>> for CombRc<T, P> {
+    fn eq(&self, other: &Rc<T>) -> bool {
+        Rc::ptr_eq(&self.inner, other) || *self.inner == **other
+    }
+}
+
+/// Compares by value, returning `true` without comparing if both sides share an allocation.
+impl<T: Clone + PartialEq, P: CowPolicy> PartialEq<CombRc<T, P>> for Rc<T> {
+    fn eq(&self, other: &CombRc<T, P>) -> bool {
+        Rc::ptr_eq(self, &other.inner) || **self == *other.inner
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<Rc<T>> for CombRc<T, P> {
+    fn partial_cmp(&self, other: &Rc<T>) -> Option<Ordering> {
+        self.inner.partial_cmp(other)
+    }
+}
+
+impl<T: Clone + PartialOrd, P: CowPolicy> PartialOrd<CombRc<T, P>> for Rc<T> {
+    fn partial_cmp(&self, other: &CombRc<T, P>) -> Option<Ordering> {
+        self.partial_cmp(&other.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for Rc<T> {
+    fn from(value: CombRc<T, P>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<T> for CombRc<T, P> {
+    /// Moves the value into a new allocation, like [`CombRc::new`].
+    ///
+    /// This allows taking an `impl Into<CombRc<T>>`, which accepts either a value or a reference to
+    /// one. If `T` is itself an `Rc`, the type has to be specified, since the `Rc` could also be
+    /// converted to a `CombRc` without allocating. The same goes for an `Option<CombRc<T>>`, which could
+    /// also be converted to a `CombRc<Option<T>>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use combarc::CombRc;
+    /// use std::rc::Rc;
+    ///
+    /// fn store(value: impl Into<CombRc<Vec<u32>>>) -> CombRc<Vec<u32>> {
+    ///     value.into()
+    /// }
+    ///
+    /// let stored = store(vec![1]);
+    /// assert_eq!(*store(stored.clone()), [1]);
+    ///
+    /// let inner = Rc::new(1u32);
+    /// let wrapped: CombRc<Rc<u32>> = CombRc::from(inner.clone());
+    /// let converted: CombRc<u32> = CombRc::from(inner);
+    /// assert!(Rc::ptr_eq(&*wrapped, CombRc::get_rc(&converted)));
+    /// ```
+    fn from(value: T) -> Self {
+        Self::from_inner(Rc::new(value))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<&T> for CombRc<T, P> {
+    /// Clones the value into a new allocation, like [`CombRc::from_ref`].
+    ///
+    /// Since a reference can also be moved into a `CombRc<&T>`, the type may have to be specified.
+    fn from(value: &T) -> Self {
+        Self::from_inner(Rc::new(value.clone()))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Box<T>> for CombRc<T, P> {
+    /// Moves the value into a new allocation with [`Rc::from`], copying it from the box directly.
+    ///
+    /// Unlike `CombRc::new(*value)`, this does not move the value through the stack, which matters for
+    /// large values.
+    fn from(value: Box<T>) -> Self {
+        Self::from_inner(Rc::from(value))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Cow<'_, T>> for CombRc<T, P> {
+    /// Moves an owned value into a new allocation, or clones a borrowed one.
+    ///
+    /// Since `CombRc` needs a sized value, there is no conversion from a `Cow<str>` or `Cow<[T]>`
+    /// to a `CombRc<str>` or `CombRc<[T]>`. Convert those to a `CombRc<String>` or `CombRc<Vec<T>>` with
+    /// [`Cow::into_owned`] instead.
+    fn from(value: Cow<'_, T>) -> Self {
+        Self::from_inner(Rc::new(value.into_owned()))
+    }
+}
+
+impl<'a, T: Clone, P: CowPolicy> From<&'a CombRc<T, P>> for Cow<'a, T> {
+    /// Borrows the inner value, like [`CombRc::to_cow`].
+    fn from(value: &'a CombRc<T, P>) -> Self {
+        CombRc::to_cow(value)
+    }
+}
+
+impl<T: Clone + core::str::FromStr, P: CowPolicy> core::str::FromStr for CombRc<T, P> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, T::Err> {
+        T::from_str(s).map(|value| Self::from_inner(Rc::new(value)))
+    }
+}
+
+/// Collects into the inner value, then moves it into a new allocation.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRc;
+///
+/// let shouted: CombRc<String> = "hello".chars().map(|c| c.to_ascii_uppercase()).collect();
+/// assert_eq!(*shouted, "HELLO");
+/// ```
+impl<A, T: Clone + FromIterator<A>, P: CowPolicy> FromIterator<A> for CombRc<T, P> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::from_inner(Rc::new(T::from_iter(iter)))
+    }
+}
+
+/// Extends the inner value, cloning it first if this reference is not unique.
+///
+/// The iterator is peeked first, so extending with an empty iterator never clones.
+impl<A, T: Clone + Extend<A>, P: CowPolicy> Extend<A> for CombRc<T, P> {
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        if iter.peek().is_some() {
+            Self::make_mut(self).extend(iter)
+        }
+    }
+}
+
+/// Iterates over a reference to the inner value. This never clones.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRc;
+///
+/// let numbers = CombRc::new(vec![1, 2, 3]);
+/// let mut sum = 0;
+/// for number in &numbers {
+///     sum += number;
+/// }
+/// assert_eq!(sum, 6);
+/// ```
+impl<'a, T: Clone, P: CowPolicy> IntoIterator for &'a CombRc<T, P> where &'a T: IntoIterator {
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (&*self.inner).into_iter()
+    }
+}
+
+/// Iterates over the inner value by value.
+///
+/// This goes through [`CombRc::make_inner`], so if this reference is not unique, the whole inner
+/// value is cloned first. Iterate over `&CombRc` instead to avoid this.
+impl<T: Clone + IntoIterator, P: CowPolicy> IntoIterator for CombRc<T, P> {
+    type Item = T::Item;
+    type IntoIter = T::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Self::make_inner(self).into_iter()
+    }
+}
+
+/// Sums the inner values, moving them out of unique references and cloning them otherwise.
+impl<T: Clone + Sum, P: CowPolicy> Sum for CombRc<T, P> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::from_inner(Rc::new(iter.map(Self::make_inner).sum()))
+    }
+}
+
+/// Sums references to the inner values. This never clones.
+impl<'a, T: Clone + Sum<&'a T>, P: CowPolicy> Sum<&'a CombRc<T, P>> for CombRc<T, P> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self::from_inner(Rc::new(iter.map(|what| &*what.inner).sum()))
+    }
+}
+
+/// Multiplies the inner values, moving them out of unique references and cloning them otherwise.
+impl<T: Clone + Product, P: CowPolicy> Product for CombRc<T, P> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::from_inner(Rc::new(iter.map(Self::make_inner).product()))
+    }
+}
+
+/// Multiplies references to the inner values. This never clones.
+impl<'a, T: Clone + Product<&'a T>, P: CowPolicy> Product<&'a CombRc<T, P>> for CombRc<T, P> {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        Self::from_inner(Rc::new(iter.map(|what| &*what.inner).product()))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Rc<T>> for CombRc<T, P> {
+    fn from(value: Rc<T>) -> Self {
+        Self::from_inner(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<Option<CombRc<T, P>>> for CombRc<Option<T>, P> {
+    /// Convert an `Option<CombRc<T>>` into a `CombRc<Option<T>>`.
+    ///
+    /// This is the reverse of [`CombRc::transpose`], so the inner value is moved if it is unique.
+    fn from(value: Option<CombRc<T, P>>) -> Self {
+        Self::from_inner(Rc::new(value.map(CombRc::make_inner)))
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombRc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.as_ref()
+    }
+}
+
+#[cfg(not(feature = "allocator_api"))]
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombRc<T, P> {
+    /// If the inner [`Rc`] has no strong references, get a mutable reference. Otherwise, clone the
+    /// [`Rc`].
+    /// 
+    /// This just calls [`CombRc::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator> core::ops::Deref for CombRc<T, P, A> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.inner.as_ref()
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl<T: Clone, P: CowPolicy, A: Allocator + Clone> core::ops::DerefMut for CombRc<T, P, A> {
+    /// If the inner [`Rc`] has no strong references, get a mutable reference. Otherwise, clone the
+    /// [`Rc`] into the same allocator.
+    ///
+    /// This does the same thing as [`CombRc::make_mut`], but for any allocator.
     fn deref_mut(&mut self) -> &mut T {
-        Rc::make_mut(&mut self.inner)
+        Self::make_mut_in(self)
     }
 }
 
-impl<T: core::fmt::Display + Clone> core::fmt::Display for CombRc<T> {
+impl<T: core::fmt::Display + Clone, P: CowPolicy> core::fmt::Display for CombRc<T, P> {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         Rc::as_ref(&self.inner).fmt(f)
     }
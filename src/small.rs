@@ -0,0 +1,202 @@
+use core::cmp::Ordering;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::CombArc;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+/// A copy-on-write value which is stored inline until it is cloned.
+///
+/// This is for small values, where allocating a [`CombArc`] for every value costs more than it
+/// saves. A new `CombSmall` holds its value inline, so constructing, reading, and mutating it never
+/// allocates. The first time it is cloned, the value is copied into a `CombArc`, which this and
+/// every clone then share, so cloning allocates once. Mutably borrowing this afterwards moves the
+/// shared value back inline, which clones it if any clones still share it, like `CombArc`.
+///
+/// Without the `std` feature, there is nowhere to keep the `CombArc` through a shared reference,
+/// so each clone of an inline value allocates its own `CombArc`, which its clones then share.
+///
+/// Comparisons are by value. Like `CombArc`, this is [`Send`] and [`Sync`] if `T` is both.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombSmall;
+///
+/// let mut id = CombSmall::new([0u8; 16]);
+/// id[0] = 1;
+/// assert!(CombSmall::is_inline(&id));
+///
+/// let another_id = id.clone();
+/// assert!(!CombSmall::is_inline(&another_id));
+/// assert_eq!(id, another_id);
+///
+/// id[0] = 2; // moves the value back inline
+/// assert!(CombSmall::is_inline(&id));
+/// assert_ne!(id, another_id);
+/// ```
+pub struct CombSmall<T: Clone> {
+    inner: Inner<T>
+}
+
+enum Inner<T: Clone> {
+    Inline {
+        value: T,
+        #[cfg(feature = "std")]
+        shared: OnceLock<CombArc<T>>
+    },
+    Shared(CombArc<T>)
+}
+
+impl<T: Clone> CombSmall<T> {
+    /// Constructs a `CombSmall` which holds its value inline.
+    #[inline]
+    pub fn new(what: T) -> CombSmall<T> {
+        Self {
+            inner: Inner::Inline {
+                value: what,
+                #[cfg(feature = "std")]
+                shared: OnceLock::new()
+            }
+        }
+    }
+
+    /// Returns `true` if this holds its value inline and does not share it with any clones.
+    #[inline]
+    pub fn is_inline(what: &CombSmall<T>) -> bool {
+        match &what.inner {
+            #[cfg(feature = "std")]
+            Inner::Inline { shared, .. } => shared.get().is_none(),
+            #[cfg(not(feature = "std"))]
+            Inner::Inline { .. } => true,
+            Inner::Shared(_) => false
+        }
+    }
+
+    /// Get the inner value.
+    ///
+    /// If this holds its value inline or in a unique `CombArc`, it will be moved. Otherwise, it
+    /// will be cloned.
+    #[inline]
+    pub fn make_inner(what: CombSmall<T>) -> T {
+        match what.inner {
+            Inner::Inline { value, .. } => value,
+            Inner::Shared(s) => CombArc::make_inner(s)
+        }
+    }
+
+    /// Get a mutable reference to the inner value.
+    ///
+    /// If this holds its value inline, this never allocates. If it shares the value with its
+    /// clones, the shared value is moved back inline, or cloned if any clones are left, so changes
+    /// made through interior mutability are kept. Otherwise, this calls [`CombArc::make_mut`].
+    #[inline]
+    pub fn make_mut(what: &mut CombSmall<T>) -> &mut T {
+        match &mut what.inner {
+            #[cfg(feature = "std")]
+            Inner::Inline { value, shared } => {
+                if let Some(shared) = shared.take() {
+                    *value = CombArc::make_inner(shared);
+                }
+                value
+            },
+            #[cfg(not(feature = "std"))]
+            Inner::Inline { value } => value,
+            Inner::Shared(s) => CombArc::make_mut(s)
+        }
+    }
+}
+
+impl<T: Clone> Clone for CombSmall<T> {
+    /// Clone the reference.
+    ///
+    /// If this holds its value inline, the first clone copies it into a `CombArc`, which this and
+    /// every clone share until this is mutably borrowed.
+    fn clone(&self) -> Self {
+        let shared = match &self.inner {
+            #[cfg(feature = "std")]
+            Inner::Inline { value, shared } => shared.get_or_init(|| CombArc::new(value.clone())).clone(),
+            #[cfg(not(feature = "std"))]
+            Inner::Inline { value } => CombArc::new(value.clone()),
+            Inner::Shared(s) => s.clone()
+        };
+        Self {
+            inner: Inner::Shared(shared)
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombSmall<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for CombSmall<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Clone + Eq> Eq for CombSmall<T> {}
+
+impl<T: Clone + PartialOrd> PartialOrd for CombSmall<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<T: Clone + Ord> Ord for CombSmall<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<T: Clone + Hash> Hash for CombSmall<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl<T: Clone + core::fmt::Debug> core::fmt::Debug for CombSmall<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombSmall")
+            .field("inner", &**self)
+            .field("inline", &Self::is_inline(self))
+            .finish()
+    }
+}
+
+impl<T: core::fmt::Display + Clone> core::fmt::Display for CombSmall<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<T: Clone> From<CombArc<T>> for CombSmall<T> {
+    fn from(value: CombArc<T>) -> Self {
+        Self {
+            inner: Inner::Shared(value)
+        }
+    }
+}
+
+impl<T: Clone> core::ops::Deref for CombSmall<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        match &self.inner {
+            #[cfg(feature = "std")]
+            Inner::Inline { value, shared } => shared.get().map_or(value, |s| &**s),
+            #[cfg(not(feature = "std"))]
+            Inner::Inline { value } => value,
+            Inner::Shared(s) => s
+        }
+    }
+}
+
+impl<T: Clone> core::ops::DerefMut for CombSmall<T> {
+    /// This just calls [`CombSmall::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
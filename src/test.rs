@@ -1,5 +1,50 @@
+extern crate std;
+
 use alloc::rc::Rc;
 use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts how many times it, or any clone of it, has been cloned.
+#[derive(Debug, Default)]
+struct CloneCounter<T> {
+    value: T,
+    clones: Arc<AtomicUsize>
+}
+
+impl<T> CloneCounter<T> {
+    fn new(value: T) -> Self {
+        Self { value, clones: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    fn clones(&self) -> usize {
+        self.clones.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> AsRef<T> for CloneCounter<T> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> AsMut<T> for CloneCounter<T> {
+    fn as_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<A, T: Extend<A>> Extend<A> for CloneCounter<T> {
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        self.value.extend(iter)
+    }
+}
+
+impl<T: Clone> Clone for CloneCounter<T> {
+    fn clone(&self) -> Self {
+        self.clones.fetch_add(1, Ordering::Relaxed);
+        Self { value: self.value.clone(), clones: self.clones.clone() }
+    }
+}
 
 macro_rules! make_test {
     ($test_name:tt, $t:tt, $get_strong:tt, $strong:tt) => {
@@ -43,9 +88,3517 @@ macro_rules! make_test {
             let downgrade_still_works = $strong::downgrade(ReferenceCounter::$get_strong(&another_value));
             let _ = ReferenceCounter::make_inner(another_value);
             assert!(downgrade_still_works.upgrade().is_none());
+
+            // The same goes for mutable borrows through weak references made with downgrade.
+            let mut my_value = ReferenceCounter::new(Cell::new(false));
+            let downgrade_disassociated = ReferenceCounter::downgrade(&my_value);
+            my_value.get_mut();
+            assert!(downgrade_disassociated.upgrade().is_none(), "mutable borrow when unique should dissociate weak references");
+
+            // If the value is cloned instead, the weak reference stays with the old allocation.
+            let another_value = my_value.clone();
+            let downgrade_still_works = ReferenceCounter::downgrade(&another_value);
+            *my_value.get_mut() = true;
+            let upgraded = downgrade_still_works.upgrade().expect("mutable borrow when NOT unique should not dissociate weak references");
+            assert!($strong::ptr_eq(&upgraded, ReferenceCounter::$get_strong(&another_value)));
+            assert!(!upgraded.get());
         }
     };
 }
 
 make_test!(test_arc, CombArc, get_arc, Arc);
 make_test!(test_rc, CombRc, get_rc, Rc);
+
+macro_rules! make_test_as_ptr {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Dereferencing does not move anything.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert_eq!(*my_value, 1);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "deref should not change the address");
+
+            // A clone shares the allocation until it is mutably borrowed.
+            let another_value = my_value.clone();
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "cloning a reference should yield equal addresses");
+
+            *my_value = 2;
+            assert_ne!(address, ReferenceCounter::as_ptr(&my_value), "mutable borrow when NOT unique makes a unique one with different address");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "mutable borrow should not move the other reference");
+        }
+    };
+}
+
+make_test_as_ptr!(test_arc_as_ptr, CombArc);
+make_test_as_ptr!(test_rc_as_ptr, CombRc);
+
+macro_rules! make_test_ptr_eq {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(1u32);
+            let another_value = my_value.clone();
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "cloning a reference should share the allocation");
+
+            // Equal values in different allocations are not pointer-equal.
+            let unrelated_value = ReferenceCounter::new(1u32);
+            assert_eq!(my_value, unrelated_value);
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &unrelated_value), "equal values are not necessarily the same allocation");
+
+            // A mutable borrow when NOT unique breaks the connection, even if nothing changes.
+            *my_value = 1;
+            assert_eq!(my_value, another_value);
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &another_value), "mutable borrow when NOT unique should make a new allocation");
+        }
+    };
+}
+
+make_test_ptr_eq!(test_arc_ptr_eq, CombArc);
+make_test_ptr_eq!(test_rc_ptr_eq, CombRc);
+
+macro_rules! make_test_counts {
+    ($test_name:tt, $t:tt, $get_strong:tt, $strong:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(1u32);
+            assert_eq!(ReferenceCounter::strong_count(&my_value), 1);
+            assert_eq!(ReferenceCounter::weak_count(&my_value), 0);
+
+            let another_value = my_value.clone();
+            assert_eq!(ReferenceCounter::strong_count(&my_value), 2, "cloning a reference should increase the strong count");
+            assert_eq!(ReferenceCounter::strong_count(&another_value), 2);
+
+            let weak = $strong::downgrade(ReferenceCounter::$get_strong(&another_value));
+            assert_eq!(ReferenceCounter::weak_count(&my_value), 1, "downgrading should increase the weak count");
+
+            // The clone made by a mutable borrow is its own allocation.
+            *my_value = 2;
+            assert_eq!(ReferenceCounter::strong_count(&my_value), 1, "mutable borrow when NOT unique makes a unique one");
+            assert_eq!(ReferenceCounter::weak_count(&my_value), 0);
+            assert_eq!(ReferenceCounter::strong_count(&another_value), 1);
+            assert_eq!(ReferenceCounter::weak_count(&another_value), 1);
+            drop(weak);
+        }
+    };
+}
+
+make_test_counts!(test_arc_counts, CombArc, get_arc, Arc);
+make_test_counts!(test_rc_counts, CombRc, get_rc, Rc);
+
+macro_rules! make_test_is_unique {
+    ($test_name:tt, $t:tt, $get_strong:tt, $strong:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(1u32);
+            assert!(ReferenceCounter::is_unique(&my_value), "a new reference should be unique");
+            assert!(!ReferenceCounter::is_shared(&my_value));
+
+            let another_value = my_value.clone();
+            assert!(!ReferenceCounter::is_unique(&my_value), "cloning a reference should share it");
+            assert!(ReferenceCounter::is_shared(&another_value));
+
+            drop(another_value);
+            assert!(ReferenceCounter::is_unique(&my_value), "dropping the other reference should make it unique again");
+
+            // Weak references do not count, but they still get dissociated by a mutable borrow.
+            let mut my_value = my_value;
+            let weak = $strong::downgrade(ReferenceCounter::$get_strong(&my_value));
+            assert!(ReferenceCounter::is_unique(&my_value), "weak references should not affect uniqueness");
+            *my_value = 2;
+            assert!(weak.upgrade().is_none());
+        }
+    };
+}
+
+make_test_is_unique!(test_arc_is_unique, CombArc, get_arc, Arc);
+make_test_is_unique!(test_rc_is_unique, CombRc, get_rc, Rc);
+
+macro_rules! make_test_get_mut_if_unique {
+    ($test_name:tt, $t:tt, $get_strong:tt, $strong:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            *ReferenceCounter::get_mut_if_unique(&mut my_value).expect("a new reference should be unique") = 2;
+            assert_eq!(*my_value, 2);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "mutating a unique reference should not move it");
+
+            let another_value = my_value.clone();
+            assert!(ReferenceCounter::get_mut_if_unique(&mut my_value).is_none(), "a shared reference should not be mutable");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "failing to get a mutable reference should not clone");
+            drop(another_value);
+
+            // Weak references also prevent getting a mutable reference, but are left intact.
+            let weak = $strong::downgrade(ReferenceCounter::$get_strong(&my_value));
+            assert!(ReferenceCounter::get_mut_if_unique(&mut my_value).is_none(), "weak references should prevent mutable access");
+            assert!(weak.upgrade().is_some(), "failing to get a mutable reference should not dissociate weak references");
+        }
+    };
+}
+
+make_test_get_mut_if_unique!(test_arc_get_mut_if_unique, CombArc, get_arc, Arc);
+make_test_get_mut_if_unique!(test_rc_get_mut_if_unique, CombRc, get_rc, Rc);
+
+macro_rules! make_test_make_mut {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec::Vec;
+            use crate::$t as ReferenceCounter;
+
+            let mut explicit = ReferenceCounter::new(Vec::new());
+            let mut implicit = ReferenceCounter::new(Vec::new());
+
+            // Unique, so neither one should move.
+            let explicit_address = ReferenceCounter::as_ptr(&explicit);
+            let implicit_address = ReferenceCounter::as_ptr(&implicit);
+            ReferenceCounter::make_mut(&mut explicit).push(1);
+            implicit.push(1);
+            assert_eq!(explicit_address, ReferenceCounter::as_ptr(&explicit), "make_mut should not clone a unique reference");
+            assert_eq!(implicit_address, ReferenceCounter::as_ptr(&implicit), "deref_mut should not clone a unique reference");
+            assert_eq!(explicit, implicit);
+
+            // Shared, so both should clone and leave the other reference alone.
+            let explicit_clone = explicit.clone();
+            let implicit_clone = implicit.clone();
+            ReferenceCounter::make_mut(&mut explicit).push(2);
+            implicit.push(2);
+            assert!(!ReferenceCounter::ptr_eq(&explicit, &explicit_clone), "make_mut should clone a shared reference");
+            assert!(!ReferenceCounter::ptr_eq(&implicit, &implicit_clone), "deref_mut should clone a shared reference");
+            assert_eq!(explicit, implicit);
+            assert_eq!(explicit_clone, implicit_clone);
+            assert_eq!(*explicit_clone, [1]);
+        }
+    };
+}
+
+make_test_make_mut!(test_arc_make_mut, CombArc);
+make_test_make_mut!(test_rc_make_mut, CombRc);
+
+macro_rules! make_test_comb_weak {
+    ($test_name:tt, $t:tt, $w:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$w as WeakReference;
+
+            // An empty weak reference never upgrades.
+            let empty = WeakReference::<u32>::new();
+            assert!(empty.upgrade().is_none(), "an empty weak reference should not upgrade");
+            assert_eq!(empty.strong_count(), 0);
+
+            // Upgrading gives a reference to the same allocation.
+            let my_value = ReferenceCounter::new(1u32);
+            let weak = ReferenceCounter::downgrade_comb(&my_value);
+            assert_eq!(weak.strong_count(), 1);
+            assert_eq!(weak.weak_count(), 1);
+            let upgraded = weak.upgrade().expect("weak reference should upgrade while a strong reference exists");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &upgraded));
+            assert_eq!(weak.strong_count(), 2);
+
+            // Once all strong references are gone, it no longer upgrades.
+            drop(upgraded);
+            drop(my_value);
+            assert!(weak.upgrade().is_none(), "weak reference should not upgrade after the strong references are dropped");
+            assert_eq!(weak.strong_count(), 0);
+
+            // Mutably borrowing the last strong reference dissociates the weak reference.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let weak = ReferenceCounter::downgrade_comb(&my_value);
+            *my_value = 2;
+            assert!(weak.upgrade().is_none(), "mutable borrow when unique should dissociate weak references");
+
+            // An upgraded reference is copy-on-write like any other.
+            let weak = ReferenceCounter::downgrade_comb(&my_value);
+            let mut upgraded = weak.upgrade().unwrap();
+            *upgraded = 3;
+            assert_eq!(*my_value, 2, "mutating an upgraded reference should clone it");
+            assert!(ReferenceCounter::ptr_eq(&weak.upgrade().unwrap(), &my_value));
+        }
+    };
+}
+
+make_test_comb_weak!(test_arc_comb_weak, CombArc, CombWeak);
+make_test_comb_weak!(test_rc_comb_weak, CombRc, CombRcWeak);
+
+macro_rules! make_test_comb_weak_traits {
+    ($test_name:tt, $t:tt, $w:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::format;
+            use alloc::vec::Vec;
+            use std::collections::HashSet;
+            use crate::$t as ReferenceCounter;
+            use crate::$w as WeakReference;
+
+            // This should be derivable without manual impls.
+            #[derive(Clone, Debug, Default)]
+            struct Observers {
+                observers: Vec<WeakReference<u32>>
+            }
+
+            let my_value = ReferenceCounter::new(1u32);
+            let mut observers = Observers::default();
+            observers.observers.push(ReferenceCounter::downgrade_comb(&my_value));
+            let observers_clone = observers.clone();
+            assert!(observers.observers[0].ptr_eq(&observers_clone.observers[0]), "cloning a weak reference should point to the same allocation");
+
+            let live = &observers.observers[0];
+            assert_eq!(format!("{live:?}"), concat!(stringify!($w), " { live: true, strong_count: 1, weak_count: 2 }"));
+
+            // Upgrading and downgrading again gives the same allocation.
+            let upgraded = live.upgrade().unwrap();
+            assert!(ReferenceCounter::downgrade_comb(&upgraded).ptr_eq(live), "upgrading and downgrading should point to the same allocation");
+            assert!(!ReferenceCounter::downgrade_comb(&ReferenceCounter::new(1u32)).ptr_eq(live), "equal values in different allocations should not be equal");
+
+            // Equality and hashing are by allocation.
+            let mut set = HashSet::new();
+            set.insert(live.clone());
+            set.insert(ReferenceCounter::downgrade_comb(&upgraded));
+            set.insert(WeakReference::new());
+            set.insert(WeakReference::default());
+            assert_eq!(set.len(), 2, "weak references to the same allocation should be deduplicated");
+
+            drop(upgraded);
+            drop(my_value);
+            let dead = &observers.observers[0];
+            assert_eq!(format!("{dead:?}"), concat!(stringify!($w), " { live: false, strong_count: 0, weak_count: 0 }"));
+            assert_eq!(format!("{:?}", WeakReference::<u32>::new()), concat!(stringify!($w), " { live: false, strong_count: 0, weak_count: 0 }"));
+        }
+    };
+}
+
+make_test_comb_weak_traits!(test_arc_comb_weak_traits, CombArc, CombWeak);
+make_test_comb_weak_traits!(test_rc_comb_weak_traits, CombRc, CombRcWeak);
+
+macro_rules! make_test_new_cyclic {
+    ($test_name:tt, $t:tt, $get_strong:tt, $strong:tt, $weak:path) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            #[derive(Clone)]
+            struct Node {
+                this: $weak,
+                value: u32
+            }
+
+            // While unique, the node can upgrade its own weak reference.
+            let node = ReferenceCounter::new_cyclic(|this| Node { this: this.clone(), value: 1 });
+            let this = node.this.upgrade().expect("node should be able to upgrade its own weak reference");
+            assert!($strong::ptr_eq(&this, ReferenceCounter::$get_strong(&node)));
+            drop(this);
+
+            // The clone keeps a weak reference to the original allocation.
+            let original = node.clone();
+            let mut node = node;
+            node.value = 2;
+            assert_eq!(original.value, 1);
+            assert!($strong::ptr_eq(&node.this.upgrade().unwrap(), ReferenceCounter::$get_strong(&original)), "clone should point to the original allocation");
+
+            // Mutating the original while unique dissociates its weak reference to itself.
+            drop(node);
+            let mut original = original;
+            original.value = 3;
+            assert!(original.this.upgrade().is_none(), "mutable borrow when unique should dissociate weak references");
+        }
+    };
+}
+
+make_test_new_cyclic!(test_arc_new_cyclic, CombArc, get_arc, Arc, alloc::sync::Weak<Node>);
+make_test_new_cyclic!(test_rc_new_cyclic, CombRc, get_rc, Rc, alloc::rc::Weak<Node>);
+
+macro_rules! make_test_into_inner {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(1u32);
+            assert_eq!(ReferenceCounter::into_inner(my_value), Some(1), "a unique reference should give its inner value");
+
+            let my_value = ReferenceCounter::new(2u32);
+            let another_value = my_value.clone();
+            assert_eq!(ReferenceCounter::into_inner(my_value), None, "a shared reference should not give its inner value");
+            assert_eq!(ReferenceCounter::into_inner(another_value), Some(2), "the last reference should give its inner value");
+        }
+    };
+}
+
+make_test_into_inner!(test_arc_into_inner, CombArc);
+make_test_into_inner!(test_rc_into_inner, CombRc);
+
+#[test]
+fn test_arc_into_inner_race() {
+    use alloc::vec::Vec;
+    use crate::CombArc;
+
+    for _ in 0..100 {
+        let my_value = CombArc::new(1u32);
+        let another_value = my_value.clone();
+        let threads: Vec<_> = alloc::vec![my_value, another_value]
+            .into_iter()
+            .map(|value| std::thread::spawn(move || CombArc::into_inner(value)))
+            .collect();
+        let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(results.iter().filter(|r| r.is_some()).count(), 1, "exactly one thread should get the inner value");
+    }
+}
+
+macro_rules! make_test_take {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the value is moved out and the allocation is reused.
+            let mut my_value = ReferenceCounter::new(vec![1, 2, 3]);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert_eq!(ReferenceCounter::take(&mut my_value), [1, 2, 3]);
+            assert_eq!(*my_value, Vec::<i32>::new(), "taking should leave a default value");
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "taking from a unique reference should not move it");
+
+            // Shared, so the value is cloned out and the other reference keeps it.
+            let mut my_value = ReferenceCounter::new(vec![4, 5, 6]);
+            let another_value = my_value.clone();
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert_eq!(ReferenceCounter::take(&mut my_value), [4, 5, 6]);
+            assert_eq!(*my_value, Vec::<i32>::new(), "taking should leave a default value");
+            assert_ne!(address, ReferenceCounter::as_ptr(&my_value), "taking from a shared reference should make a new allocation");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "taking from a shared reference should not move the other reference");
+            assert_eq!(*another_value, [4, 5, 6], "taking from a shared reference should not change the other reference");
+        }
+    };
+}
+
+make_test_take!(test_arc_take, CombArc);
+make_test_take!(test_rc_take, CombRc);
+
+macro_rules! make_test_replace {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the allocation is reused.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert_eq!(ReferenceCounter::replace(&mut my_value, 2), 1);
+            assert_eq!(*my_value, 2);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "replacing a unique reference should not move it");
+
+            // Shared, so the other reference keeps the old value.
+            let another_value = my_value.clone();
+            assert_eq!(ReferenceCounter::replace(&mut my_value, 3), 2);
+            assert_eq!(*my_value, 3);
+            assert_eq!(*another_value, 2, "replacing a shared reference should not change the other reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "replacing a shared reference should not move the other reference");
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &another_value), "replacing a shared reference should make a new allocation");
+        }
+    };
+}
+
+make_test_replace!(test_arc_replace, CombArc);
+make_test_replace!(test_rc_replace, CombRc);
+
+macro_rules! make_test_set {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the value is assigned in place.
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let clones = my_value.clones.clone();
+            let address = ReferenceCounter::as_ptr(&my_value);
+            ReferenceCounter::set(&mut my_value, CloneCounter { value: 2, clones: clones.clone() });
+            assert_eq!(my_value.value, 2);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "setting a unique reference should not move it");
+            assert_eq!(my_value.clones(), 0, "setting a unique reference should not clone");
+
+            // Shared, so a new allocation is made, but still without cloning.
+            let another_value = my_value.clone();
+            ReferenceCounter::set(&mut my_value, CloneCounter { value: 3, clones: clones.clone() });
+            assert_eq!(my_value.value, 3);
+            assert_eq!(another_value.value, 2, "setting a shared reference should not change the other reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "setting a shared reference should not move the other reference");
+            assert_eq!(my_value.clones(), 0, "setting a shared reference should not clone");
+        }
+    };
+}
+
+make_test_set!(test_arc_set, CombArc);
+make_test_set!(test_rc_set, CombRc);
+
+macro_rules! make_test_update {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec;
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the closure runs on the original allocation.
+            let mut my_value = ReferenceCounter::new(vec![1]);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            let len = ReferenceCounter::update(&mut my_value, |v| {
+                assert_eq!(address, &*v as *const _, "closure should run on the original allocation if unique");
+                v.push(2);
+                v.len()
+            });
+            assert_eq!(len, 2, "closure return value should be returned");
+            assert_eq!(*my_value, [1, 2]);
+
+            // Shared, so the closure runs on a fresh clone.
+            let another_value = my_value.clone();
+            let popped = ReferenceCounter::update(&mut my_value, |v| {
+                assert_ne!(address, &*v as *const _, "closure should run on a clone if NOT unique");
+                v.pop()
+            });
+            assert_eq!(popped, Some(2), "closure return value should be returned");
+            assert_eq!(*my_value, [1]);
+            assert_eq!(*another_value, [1, 2], "updating a shared reference should not change the other reference");
+        }
+    };
+}
+
+make_test_update!(test_arc_update, CombArc);
+make_test_update!(test_rc_update, CombRc);
+
+macro_rules! make_test_with {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec::Vec;
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so chaining never clones.
+            let my_value = ReferenceCounter::new(Vec::new());
+            let address = ReferenceCounter::as_ptr(&my_value);
+            let my_value = my_value.with(|v| v.push(1)).with(|v| v.push(2)).with(|v| v.push(3));
+            assert_eq!(*my_value, [1, 2, 3]);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "with should not clone a unique reference");
+
+            // Shared, so the other reference is untouched.
+            let another_value = my_value.clone();
+            let my_value = my_value.with(|v| v.clear());
+            assert!(my_value.is_empty());
+            assert_eq!(*another_value, [1, 2, 3], "with should not change the other reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "with should not move the other reference");
+        }
+    };
+}
+
+make_test_with!(test_arc_with, CombArc);
+make_test_with!(test_rc_with, CombRc);
+
+macro_rules! make_test_map {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Mapping by reference never clones.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let another_value = my_value.clone();
+            let mapped = ReferenceCounter::map(my_value, |v| v.value + 1);
+            assert_eq!(*mapped, 2);
+            assert_eq!(another_value.value, 1, "mapping should not change other references");
+            assert_eq!(another_value.clones(), 0, "mapping by reference should not clone");
+
+            // Unique, so the value is moved.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let mapped = ReferenceCounter::map_owned(my_value, |v| { assert_eq!(v.clones(), 0); v });
+            assert_eq!(mapped.value, 1);
+            assert_eq!(mapped.clones(), 0, "mapping a unique reference by value should move it");
+
+            // Shared, so the value is cloned.
+            let another_value = mapped.clone();
+            let mapped = ReferenceCounter::map_owned(mapped, |mut v| { v.value += 1; v });
+            assert_eq!(mapped.value, 2);
+            assert_eq!(another_value.value, 1, "mapping should not change other references");
+            assert_eq!(mapped.clones(), 1, "mapping a shared reference by value should clone it");
+        }
+    };
+}
+
+make_test_map!(test_arc_map, CombArc);
+make_test_map!(test_rc_map, CombRc);
+
+macro_rules! make_test_try_map {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::string::String;
+            use core::num::ParseIntError;
+            use crate::$t as ReferenceCounter;
+
+            let raw = ReferenceCounter::new(String::from("123"));
+            let parsed = ReferenceCounter::try_map(&raw, |s| s.parse::<u32>()).expect("parsing should succeed");
+            assert_eq!(*parsed, 123);
+            let parsed_again: Result<ReferenceCounter<u32>, ParseIntError> = ReferenceCounter::try_map(&raw, |s| s.parse());
+            assert!(!ReferenceCounter::ptr_eq(&parsed, &parsed_again.unwrap()), "each successful map should be a new allocation");
+
+            let bad = ReferenceCounter::new(String::from("abc"));
+            assert!(ReferenceCounter::try_map(&bad, |s| s.parse::<u32>()).is_err(), "errors should be returned");
+
+            let first_char = ReferenceCounter::filter_map(&raw, |s| s.chars().next()).expect("string should not be empty");
+            assert_eq!(*first_char, '1');
+            let empty = ReferenceCounter::new(String::new());
+            assert!(ReferenceCounter::filter_map(&empty, |s| s.chars().next()).is_none(), "None should be returned");
+            assert_eq!(*raw, "123", "mapping should not change the original reference");
+        }
+    };
+}
+
+make_test_try_map!(test_arc_try_map, CombArc);
+make_test_try_map!(test_rc_try_map, CombRc);
+
+macro_rules! make_test_map_in_place {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so there are no clones.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let my_value = ReferenceCounter::map_in_place(my_value, |mut v| { v.value *= 10; v });
+            assert_eq!(my_value.value, 10);
+            assert_eq!(my_value.clones(), 0, "transforming a unique reference should not clone");
+
+            // Shared, so there is exactly one clone.
+            let another_value = my_value.clone();
+            let my_value = ReferenceCounter::map_in_place(my_value, |mut v| { v.value += 1; v });
+            assert_eq!(my_value.value, 11);
+            assert_eq!(another_value.value, 10, "transforming a shared reference should not change the other reference");
+            assert_eq!(my_value.clones(), 1, "transforming a shared reference should clone exactly once");
+
+            // No `Default` is needed.
+            let non_zero = ReferenceCounter::new(core::num::NonZeroU32::new(1).unwrap());
+            let non_zero = ReferenceCounter::map_in_place(non_zero, |v| v.saturating_add(1));
+            assert_eq!(non_zero.get(), 2);
+        }
+    };
+}
+
+make_test_map_in_place!(test_arc_map_in_place, CombArc);
+make_test_map_in_place!(test_rc_map_in_place, CombRc);
+
+macro_rules! make_test_modify_cloned {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec;
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(vec![1, 2]);
+            let another_value = my_value.clone();
+            let address = ReferenceCounter::as_ptr(&my_value);
+
+            let modified = ReferenceCounter::modify_cloned(&my_value, |v| v.push(3));
+            assert_eq!(*modified, [1, 2, 3]);
+            assert_ne!(address, ReferenceCounter::as_ptr(&modified), "the modified copy should be a new allocation");
+            assert_eq!(ReferenceCounter::strong_count(&modified), 1);
+
+            assert_eq!(*my_value, [1, 2], "the original reference should be untouched");
+            assert_eq!(*another_value, [1, 2], "other references should be untouched");
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value));
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value));
+        }
+    };
+}
+
+make_test_modify_cloned!(test_arc_modify_cloned, CombArc);
+make_test_modify_cloned!(test_rc_modify_cloned, CombRc);
+
+macro_rules! make_test_ensure_unique {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Already unique, so nothing happens.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert!(!ReferenceCounter::ensure_unique(&mut my_value), "a unique reference should not be cloned");
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value));
+            assert!(ReferenceCounter::is_unique(&my_value));
+
+            // Shared, so it gets cloned.
+            let another_value = my_value.clone();
+            assert!(ReferenceCounter::ensure_unique(&mut my_value), "a shared reference should be cloned");
+            assert_ne!(address, ReferenceCounter::as_ptr(&my_value));
+            assert!(ReferenceCounter::is_unique(&my_value));
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "the other reference should be untouched");
+            assert_eq!(*another_value, 1);
+        }
+    };
+}
+
+make_test_ensure_unique!(test_arc_ensure_unique, CombArc);
+make_test_ensure_unique!(test_rc_ensure_unique, CombRc);
+
+macro_rules! make_test_set_if_ne {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Equal, so nothing happens even though it is shared.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let another_value = my_value.clone();
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert!(!ReferenceCounter::set_if_ne(&mut my_value, 1), "setting an equal value should not write");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "setting an equal value should not clone");
+
+            // Not equal and shared, so a new allocation is made.
+            assert!(ReferenceCounter::set_if_ne(&mut my_value, 2), "setting a different value should write");
+            assert_eq!(*my_value, 2);
+            assert_eq!(*another_value, 1, "setting a shared reference should not change the other reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value));
+
+            // Not equal and unique, so it is written in place.
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert!(ReferenceCounter::set_if_ne(&mut my_value, 3), "setting a different value should write");
+            assert_eq!(*my_value, 3);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "setting a unique reference should not move it");
+        }
+    };
+}
+
+make_test_set_if_ne!(test_arc_set_if_ne, CombArc);
+make_test_set_if_ne!(test_rc_set_if_ne, CombRc);
+
+macro_rules! make_test_make_mut_keep_weak {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // No weak references, so it works like make_mut.
+            let mut my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            *ReferenceCounter::make_mut_keep_weak(&mut my_value).unwrap() = 2;
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "mutating a unique reference should not move it");
+
+            // Unique with a weak reference, so nothing happens and the weak reference still works.
+            let weak = ReferenceCounter::downgrade(&my_value);
+            assert!(ReferenceCounter::make_mut_keep_weak(&mut my_value).is_none(), "mutating would dissociate weak references");
+            assert_eq!(*weak.upgrade().expect("weak reference should still upgrade"), 2);
+
+            // Shared with a weak reference, so it gets cloned and the weak reference still works.
+            let another_value = my_value.clone();
+            *ReferenceCounter::make_mut_keep_weak(&mut my_value).unwrap() = 3;
+            assert_eq!(*my_value, 3);
+            assert_eq!(*weak.upgrade().expect("weak reference should still upgrade"), 2);
+            drop(another_value);
+
+            // Whereas make_mut dissociates it when unique.
+            let weak = ReferenceCounter::downgrade(&my_value);
+            *ReferenceCounter::make_mut(&mut my_value) = 4;
+            assert!(weak.upgrade().is_none());
+        }
+    };
+}
+
+make_test_make_mut_keep_weak!(test_arc_make_mut_keep_weak, CombArc);
+make_test_make_mut_keep_weak!(test_rc_make_mut_keep_weak, CombRc);
+
+macro_rules! make_test_raw {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert_eq!(address, ReferenceCounter::as_raw(&my_value));
+
+            // The strong reference is not dropped, so the weak reference can still upgrade.
+            let weak = ReferenceCounter::downgrade_comb(&my_value);
+            assert_eq!(address, ReferenceCounter::into_raw(my_value));
+            let upgraded = weak.upgrade().expect("into_raw should not drop the strong reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&upgraded));
+            assert_eq!(ReferenceCounter::strong_count(&upgraded), 2);
+        }
+    };
+}
+
+make_test_raw!(test_arc_raw, CombArc);
+make_test_raw!(test_rc_raw, CombRc);
+
+#[cfg(feature = "raw")]
+macro_rules! make_test_from_raw {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        #[allow(unsafe_code)]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(1u32);
+            let canary = ReferenceCounter::downgrade(&my_value);
+            let raw = ReferenceCounter::into_raw(my_value);
+            assert!(canary.upgrade().is_some(), "into_raw should not drop the strong reference");
+
+            // SAFETY: `raw` was returned by into_raw and is only reconstructed once.
+            let my_value = unsafe { ReferenceCounter::from_raw(raw) };
+            assert_eq!(*my_value, 1);
+            assert_eq!(raw, ReferenceCounter::as_ptr(&my_value));
+            assert_eq!(ReferenceCounter::strong_count(&my_value), 1, "from_raw should not add a strong reference");
+
+            drop(my_value);
+            assert!(canary.upgrade().is_none(), "dropping the reconstructed reference should drop the value");
+        }
+    };
+}
+
+#[cfg(feature = "raw")]
+make_test_from_raw!(test_arc_from_raw, CombArc);
+#[cfg(feature = "raw")]
+make_test_from_raw!(test_rc_from_raw, CombRc);
+
+#[cfg(feature = "allocator_api")]
+macro_rules! make_test_try_new {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::AllocError;
+
+            let my_value: Result<ReferenceCounter<u32>, AllocError> = ReferenceCounter::try_new(1);
+            let my_value = my_value.expect("allocating a u32 should succeed");
+            assert_eq!(*my_value, 1);
+            assert!(ReferenceCounter::is_unique(&my_value));
+        }
+    };
+}
+
+#[cfg(feature = "allocator_api")]
+make_test_try_new!(test_arc_try_new, CombArc);
+#[cfg(feature = "allocator_api")]
+make_test_try_new!(test_rc_try_new, CombRc);
+
+/// Fails to clone while `fail` is set.
+#[derive(Clone, Debug, PartialEq)]
+struct FallibleClone {
+    value: u32,
+    fail: core::cell::Cell<bool>
+}
+
+impl crate::TryClone for FallibleClone {
+    type Error = &'static str;
+    fn try_clone(&self) -> Result<Self, Self::Error> {
+        if self.fail.get() {
+            Err("quota exceeded")
+        } else {
+            Ok(self.clone())
+        }
+    }
+}
+
+macro_rules! make_test_try_make_mut {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use core::cell::Cell;
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so nothing is cloned even if cloning would fail.
+            let mut my_value = ReferenceCounter::new(FallibleClone { value: 1, fail: Cell::new(true) });
+            let address = ReferenceCounter::as_ptr(&my_value);
+            ReferenceCounter::try_make_mut(&mut my_value).expect("a unique reference should not be cloned").value = 2;
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value));
+
+            // Shared and cloning fails, so nothing changes.
+            let another_value = my_value.clone();
+            assert_eq!(ReferenceCounter::try_make_mut(&mut my_value).unwrap_err(), "quota exceeded");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "failing to clone should leave the reference shared");
+
+            // Shared and cloning succeeds, so it works like make_mut.
+            my_value.fail.set(false);
+            ReferenceCounter::try_make_mut(&mut my_value).expect("cloning should succeed").value = 3;
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &another_value));
+            assert_eq!(my_value.value, 3);
+            assert_eq!(another_value.value, 2, "the other reference should be untouched");
+        }
+    };
+}
+
+make_test_try_make_mut!(test_arc_try_make_mut, CombArc);
+make_test_try_make_mut!(test_rc_try_make_mut, CombRc);
+
+#[cfg(feature = "std")]
+macro_rules! make_test_unify {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::string::String;
+            use alloc::vec;
+            use crate::$t as ReferenceCounter;
+
+            let a = ReferenceCounter::new(String::from("a"));
+            let b = ReferenceCounter::new(String::from("b"));
+            let a_duplicate = ReferenceCounter::new(String::from("a"));
+            let b_duplicate = ReferenceCounter::new(String::from("b"));
+            let a_duplicate_weak = ReferenceCounter::downgrade(&a_duplicate);
+            let b_duplicate_weak = ReferenceCounter::downgrade(&b_duplicate);
+            let a_address = ReferenceCounter::as_ptr(&a);
+
+            let mut handles = vec![a.clone(), b.clone(), a_duplicate, a.clone(), b_duplicate.clone(), b_duplicate];
+            drop(a);
+            assert_eq!(ReferenceCounter::unify(&mut handles), 2, "two duplicate allocations should be eliminated");
+
+            // The first occurrence of each value wins.
+            assert_eq!(a_address, ReferenceCounter::as_ptr(&handles[0]));
+            assert!(ReferenceCounter::ptr_eq(&handles[0], &handles[2]));
+            assert!(ReferenceCounter::ptr_eq(&handles[0], &handles[3]));
+            assert!(ReferenceCounter::ptr_eq(&handles[1], &handles[4]));
+            assert!(ReferenceCounter::ptr_eq(&handles[1], &handles[5]));
+            assert!(ReferenceCounter::ptr_eq(&handles[1], &b));
+            assert!(!ReferenceCounter::ptr_eq(&handles[0], &handles[1]), "different values should not be unified");
+            assert_eq!(*handles[2], "a");
+            assert_eq!(*handles[5], "b");
+
+            // The duplicates are gone.
+            assert!(a_duplicate_weak.upgrade().is_none(), "the duplicate allocation should be dropped");
+            assert!(b_duplicate_weak.upgrade().is_none(), "the duplicate allocation should be dropped");
+
+            // Nothing left to do.
+            assert_eq!(ReferenceCounter::unify(&mut handles), 0, "unifying again should do nothing");
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_unify!(test_arc_unify, CombArc);
+#[cfg(feature = "std")]
+make_test_unify!(test_rc_unify, CombRc);
+
+#[cfg(feature = "std")]
+macro_rules! make_test_interner {
+    ($test_name:tt, $t:tt, $i:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::format;
+            use alloc::string::String;
+            use alloc::vec::Vec;
+            use crate::$t as ReferenceCounter;
+            use crate::$i as Interner;
+
+            let mut interner = Interner::new();
+            assert!(interner.is_empty());
+
+            // Equal values share an allocation.
+            let a = interner.intern(String::from("a"));
+            let a_again = interner.intern(String::from("a"));
+            let a_ref = interner.intern_ref(&String::from("a"));
+            assert!(ReferenceCounter::ptr_eq(&a, &a_again), "interning an equal value should return the same allocation");
+            assert!(ReferenceCounter::ptr_eq(&a, &a_ref), "interning an equal value by reference should return the same allocation");
+            let b = interner.intern_ref(&String::from("b"));
+            assert!(!ReferenceCounter::ptr_eq(&a, &b), "different values should not share an allocation");
+            assert_eq!(interner.len(), 2);
+
+            // The interner does not keep values alive.
+            let b_weak = ReferenceCounter::downgrade(&b);
+            drop(b);
+            assert!(b_weak.upgrade().is_none(), "the interner should not keep values alive");
+            assert_eq!(interner.len(), 1);
+            interner.purge();
+            assert_eq!(interner.len(), 1);
+            let b = interner.intern(String::from("b"));
+            assert_eq!(*b, "b");
+
+            // Mutating an interned value does not change what the interner sees.
+            drop(a_again);
+            drop(a_ref);
+            let mut a = a;
+            a.push('!');
+            assert_eq!(*interner.intern_ref(&String::from("a")), "a");
+            assert!(!ReferenceCounter::ptr_eq(&a, &interner.intern(String::from("a!"))), "mutated values should not be interned");
+
+            // Lots of dead entries get purged.
+            for i in 0..100 {
+                interner.intern(format!("{i}"));
+            }
+            let kept: Vec<_> = (0..10).map(|i| interner.intern(format!("kept {i}"))).collect();
+            assert_eq!(interner.len(), kept.len() + 1);
+            drop(b);
+            assert_eq!(interner.len(), kept.len());
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_interner!(test_arc_interner, CombArc, CombArcInterner);
+#[cfg(feature = "std")]
+make_test_interner!(test_rc_interner, CombRc, CombRcInterner);
+
+macro_rules! make_test_make_mut_with {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use core::cell::Cell;
+            use crate::$t as ReferenceCounter;
+
+            #[derive(Clone)]
+            struct Cached {
+                data: Vec<u32>,
+                cache: Option<u32>
+            }
+
+            let calls = Cell::new(0);
+            let clone = |value: &Cached| {
+                calls.set(calls.get() + 1);
+                Cached { data: value.data.clone(), cache: None }
+            };
+
+            // Unique, so the closure is never called.
+            let mut my_value = ReferenceCounter::new(Cached { data: vec![1], cache: Some(1) });
+            let address = ReferenceCounter::as_ptr(&my_value);
+            ReferenceCounter::make_mut_with(&mut my_value, clone).data.push(2);
+            assert_eq!(calls.get(), 0, "the closure should not be called if unique");
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value));
+            assert_eq!(my_value.cache, Some(1));
+
+            // Shared, so the closure is called exactly once.
+            let another_value = my_value.clone();
+            ReferenceCounter::make_mut_with(&mut my_value, clone).data.push(3);
+            assert_eq!(calls.get(), 1, "the closure should be called once if NOT unique");
+            assert_eq!(my_value.data, [1, 2, 3]);
+            assert_eq!(my_value.cache, None, "the result of the closure should be used");
+            assert_eq!(another_value.data, [1, 2], "the other reference should be untouched");
+            assert_eq!(another_value.cache, Some(1));
+        }
+    };
+}
+
+make_test_make_mut_with!(test_arc_make_mut_with, CombArc);
+make_test_make_mut_with!(test_rc_make_mut_with, CombRc);
+
+macro_rules! make_test_swap_inner {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the values are swapped in place without cloning.
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let mut scratch = CloneCounter::new(2u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            ReferenceCounter::swap_inner(&mut my_value, &mut scratch);
+            assert_eq!(my_value.value, 2);
+            assert_eq!(scratch.value, 1);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value), "swapping a unique reference should not move it");
+            assert_eq!(my_value.clones() + scratch.clones(), 0, "swapping a unique reference should not clone");
+
+            // Shared, so the other reference is untouched.
+            let another_value = my_value.clone();
+            ReferenceCounter::swap_inner(&mut my_value, &mut scratch);
+            assert_eq!(my_value.value, 1);
+            assert_eq!(scratch.value, 2);
+            assert_eq!(another_value.value, 2, "swapping a shared reference should not change the other reference");
+            assert_eq!(address, ReferenceCounter::as_ptr(&another_value), "swapping a shared reference should not move the other reference");
+        }
+    };
+}
+
+make_test_swap_inner!(test_arc_swap_inner, CombArc);
+make_test_swap_inner!(test_rc_swap_inner, CombRc);
+
+macro_rules! make_test_apply_if_unique {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so the closure runs in place.
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert!(ReferenceCounter::apply_if_unique(&mut my_value, |v| v.value = 2), "the closure should run if unique");
+            assert_eq!(my_value.value, 2);
+            assert_eq!(address, ReferenceCounter::as_ptr(&my_value));
+
+            // Shared, so the closure does not run.
+            let another_value = my_value.clone();
+            assert!(!ReferenceCounter::apply_if_unique(&mut my_value, |_| panic!("the closure should not run if shared")));
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value));
+            assert_eq!(my_value.clones(), 0, "applying should never clone");
+        }
+    };
+}
+
+make_test_apply_if_unique!(test_arc_apply_if_unique, CombArc);
+make_test_apply_if_unique!(test_rc_apply_if_unique, CombRc);
+
+macro_rules! make_test_mutate_if {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let another_value = my_value.clone();
+
+            // A false predicate leaves the value alone.
+            assert!(!ReferenceCounter::mutate_if(&mut my_value, |v| v.value == 2, |_| panic!("the closure should not run if the predicate is false")));
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "a false predicate should not dissociate");
+            assert_eq!(my_value.clones(), 0, "a false predicate should never clone");
+
+            // A true predicate clones since it is shared.
+            assert!(ReferenceCounter::mutate_if(&mut my_value, |v| v.value == 1, |v| v.value = 2));
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &another_value));
+            assert_eq!(my_value.value, 2);
+            assert_eq!(another_value.value, 1);
+            assert_eq!(my_value.clones(), 1);
+        }
+    };
+}
+
+make_test_mutate_if!(test_arc_mutate_if, CombArc);
+make_test_mutate_if!(test_rc_mutate_if, CombRc);
+
+macro_rules! make_test_fetch_update {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let another_value = my_value.clone();
+            let address = ReferenceCounter::as_ptr(&my_value);
+
+            let previous = ReferenceCounter::fetch_update(&mut my_value, |v| CloneCounter::new(v.value + 1));
+            assert_eq!(address, ReferenceCounter::as_ptr(&previous), "the previous reference should be returned");
+            assert!(ReferenceCounter::ptr_eq(&previous, &another_value), "other references should still share the previous value");
+            assert_eq!(previous.value, 1);
+            assert_eq!(my_value.value, 2);
+            assert_ne!(address, ReferenceCounter::as_ptr(&my_value), "the new value should be a new allocation");
+            assert_eq!(previous.clones(), 0, "the previous value should not be cloned");
+        }
+    };
+}
+
+make_test_fetch_update!(test_arc_fetch_update, CombArc);
+make_test_fetch_update!(test_rc_fetch_update, CombRc);
+
+macro_rules! make_test_transpose_option {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so it is moved.
+            let my_value = ReferenceCounter::new(Some(CloneCounter::new(1u32)));
+            let clones = Option::as_ref(&my_value).unwrap().clones.clone();
+            let transposed = ReferenceCounter::transpose(my_value).expect("should be some");
+            assert_eq!(transposed.value, 1);
+            assert_eq!(clones.load(Ordering::Relaxed), 0, "a unique reference should be moved");
+
+            // Shared, so it is cloned.
+            let my_value = ReferenceCounter::new(Some(CloneCounter::new(2u32)));
+            let another_value = my_value.clone();
+            let clones = Option::as_ref(&my_value).unwrap().clones.clone();
+            let transposed = ReferenceCounter::transpose(my_value).expect("should be some");
+            assert_eq!(transposed.value, 2);
+            assert_eq!(Option::as_ref(&another_value).unwrap().value, 2);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "a shared reference should be cloned");
+
+            // None is just dropped.
+            let my_value = ReferenceCounter::<Option<CloneCounter<u32>>>::new(None);
+            assert!(ReferenceCounter::transpose(my_value).is_none());
+
+            // And back again.
+            let round_trip: ReferenceCounter<Option<CloneCounter<u32>>> = ReferenceCounter::from(Some(transposed));
+            assert_eq!(Option::as_ref(&round_trip).unwrap().value, 2);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "a unique reference should be moved back");
+            let none = ReferenceCounter::<Option<u32>>::from(None::<ReferenceCounter<u32>>);
+            assert!(none.is_none());
+        }
+    };
+}
+
+make_test_transpose_option!(test_arc_transpose_option, CombArc);
+make_test_transpose_option!(test_rc_transpose_option, CombRc);
+
+macro_rules! make_test_into_result {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so it is moved.
+            let my_value = ReferenceCounter::<Result<CloneCounter<u32>, CloneCounter<u32>>>::new(Ok(CloneCounter::new(1u32)));
+            let clones = Result::as_ref(&my_value).ok().unwrap().clones.clone();
+            let transposed = ReferenceCounter::into_result(my_value).expect("should be ok");
+            assert_eq!(transposed.value, 1);
+            assert_eq!(clones.load(Ordering::Relaxed), 0, "a unique reference should be moved");
+
+            // Shared, so it is cloned.
+            let my_value = ReferenceCounter::<Result<CloneCounter<u32>, CloneCounter<u32>>>::new(Ok(CloneCounter::new(2u32)));
+            let another_value = my_value.clone();
+            let clones = Result::as_ref(&my_value).ok().unwrap().clones.clone();
+            let transposed = ReferenceCounter::into_result(my_value).expect("should be ok");
+            assert_eq!(transposed.value, 2);
+            assert_eq!(ReferenceCounter::as_result(&another_value).ok().unwrap().value, 2);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "a shared reference should be cloned");
+
+            // Errors are returned by value, moved if unique and cloned if shared.
+            let my_value = ReferenceCounter::<Result<CloneCounter<u32>, CloneCounter<u32>>>::new(Err(CloneCounter::new(3u32)));
+            let clones = Result::as_ref(&my_value).err().unwrap().clones.clone();
+            let another_value = my_value.clone();
+            assert_eq!(ReferenceCounter::into_result(my_value).err().expect("should be err").value, 3);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "a shared error should be cloned");
+            assert_eq!(ReferenceCounter::into_result(another_value).err().expect("should be err").value, 3);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "a unique error should be moved");
+        }
+    };
+}
+
+make_test_into_result!(test_arc_into_result, CombArc);
+make_test_into_result!(test_rc_into_result, CombRc);
+
+macro_rules! make_test_clone_from {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let source = ReferenceCounter::new(alloc::vec![1u8, 2, 3]);
+
+            // Unique, so the existing buffer is reused.
+            let mut my_value = ReferenceCounter::new(alloc::vec::Vec::<u8>::with_capacity(1024));
+            let buffer = my_value.as_ptr();
+            my_value.clone_from(&source);
+            assert_eq!(*my_value, [1, 2, 3]);
+            assert_eq!(buffer, my_value.as_ptr(), "a unique reference should reuse its buffer");
+            assert!(my_value.capacity() >= 1024, "a unique reference should keep its capacity");
+            assert!(!ReferenceCounter::ptr_eq(&my_value, &source));
+
+            // Shared, so the source's allocation is shared instead.
+            let another_value = my_value.clone();
+            my_value.clone_from(&source);
+            assert!(ReferenceCounter::ptr_eq(&my_value, &source), "a shared reference should share the source");
+            assert_eq!(*another_value, [1, 2, 3]);
+        }
+    };
+}
+
+make_test_clone_from!(test_arc_clone_from, CombArc);
+make_test_clone_from!(test_rc_clone_from, CombRc);
+
+macro_rules! make_test_merge_with {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let merge = |a: &mut CloneCounter<u32>, b: &CloneCounter<u32>| a.value += b.value;
+            let other = ReferenceCounter::new(CloneCounter::new(10u32));
+
+            // Unique, so it is not cloned.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let address = ReferenceCounter::as_ptr(&my_value);
+            let merged = ReferenceCounter::merge_with(my_value, &other, merge);
+            assert_eq!(merged.value, 11);
+            assert_eq!(address, ReferenceCounter::as_ptr(&merged), "a unique reference should be merged in place");
+            assert_eq!(merged.clones(), 0, "a unique reference should not be cloned");
+
+            // Shared, so it is cloned once.
+            let another_value = merged.clone();
+            let merged = ReferenceCounter::merge_with(merged, &other, merge);
+            assert_eq!(merged.value, 21);
+            assert_eq!(another_value.value, 11);
+            assert_eq!(merged.clones(), 1, "a shared reference should be cloned once");
+
+            // Aliased, so it is cloned and merged with itself.
+            let aliased = ReferenceCounter::merge_with(other.clone(), &other, merge);
+            assert_eq!(aliased.value, 20);
+            assert_eq!(other.value, 10);
+            assert!(!ReferenceCounter::ptr_eq(&aliased, &other));
+        }
+    };
+}
+
+make_test_merge_with!(test_arc_merge_with, CombArc);
+make_test_merge_with!(test_rc_merge_with, CombRc);
+
+macro_rules! make_test_leak {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let my_value = ReferenceCounter::new(alloc::string::String::from("hello"));
+            let another_value = my_value.clone();
+            let leaked: &'static mut alloc::string::String = ReferenceCounter::leak_inner(my_value);
+            leaked.push_str(" world");
+            assert_eq!(*another_value, "hello", "other references should be unaffected");
+            drop(another_value);
+            assert_eq!(leaked, "hello world", "the leaked value should outlive the references");
+
+            let my_value = ReferenceCounter::new(5u32);
+            let leaked: &'static u32 = ReferenceCounter::leak(my_value);
+            assert_eq!(*leaked, 5);
+        }
+    };
+}
+
+make_test_leak!(test_arc_leak, CombArc);
+make_test_leak!(test_rc_leak, CombRc);
+
+macro_rules! make_test_into_box {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Unique, so it is moved.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let boxed: alloc::boxed::Box<CloneCounter<u32>> = ReferenceCounter::into_box(my_value);
+            assert_eq!(boxed.value, 1);
+            assert_eq!(boxed.clones(), 0, "a unique reference should be moved");
+
+            // Shared, so it is cloned.
+            let my_value = ReferenceCounter::new(CloneCounter::new(2u32));
+            let another_value = my_value.clone();
+            let boxed = ReferenceCounter::into_box(my_value);
+            assert_eq!(boxed.value, 2);
+            assert_eq!(another_value.value, 2);
+            assert_eq!(boxed.clones(), 1, "a shared reference should be cloned");
+        }
+    };
+}
+
+make_test_into_box!(test_arc_into_box, CombArc);
+make_test_into_box!(test_rc_into_box, CombRc);
+
+#[cfg(feature = "std")]
+macro_rules! make_test_default_shared {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // A type only used by this test, so other tests do not share its default value.
+            #[derive(Clone, Default)]
+            struct LookupTable {
+                entries: alloc::vec::Vec<u32>
+            }
+
+            let mut a = ReferenceCounter::<LookupTable>::default_shared();
+            let b = ReferenceCounter::<LookupTable>::default_shared();
+            assert!(ReferenceCounter::ptr_eq(&a, &b), "default values should be shared");
+
+            a.entries.push(1);
+            assert!(!ReferenceCounter::ptr_eq(&a, &b), "mutating should unshare the default value");
+            assert!(b.entries.is_empty());
+            assert!(ReferenceCounter::ptr_eq(&b, &ReferenceCounter::<LookupTable>::default_shared()));
+            assert!(ReferenceCounter::<u32>::default_shared() == 0);
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_default_shared!(test_arc_default_shared, CombArc);
+#[cfg(feature = "std")]
+make_test_default_shared!(test_rc_default_shared, CombRc);
+
+macro_rules! make_test_get_set_inner {
+    ($test_name:tt, $t:tt, $inner:tt, $get_mut:tt, $set:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+
+            // Install a reference-counted value shared elsewhere.
+            let elsewhere = $inner::new(CloneCounter::new(2u32));
+            ReferenceCounter::$set(&mut my_value, elsewhere.clone());
+            assert!($inner::ptr_eq(&elsewhere, ReferenceCounter::$get_mut(&mut my_value)));
+
+            // It is shared, so mutably borrowing must still clone.
+            my_value.value = 3;
+            assert_eq!(elsewhere.value, 2, "the shared value should be untouched");
+            assert_eq!(my_value.value, 3);
+            assert_eq!(my_value.clones(), 1);
+
+            // Replacing it through the mutable reference works too.
+            *ReferenceCounter::$get_mut(&mut my_value) = elsewhere.clone();
+            assert_eq!(my_value.value, 2);
+            my_value.value = 4;
+            assert_eq!(elsewhere.value, 2, "the shared value should be untouched");
+            assert_eq!(my_value.clones(), 2);
+        }
+    };
+}
+
+make_test_get_set_inner!(test_arc_get_set_arc, CombArc, Arc, get_arc_mut, set_arc);
+make_test_get_set_inner!(test_rc_get_set_rc, CombRc, Rc, get_rc_mut, set_rc);
+
+macro_rules! make_test_option_comb_ext {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::OptionCombExt;
+
+            // None, so a new reference is inserted.
+            let mut my_value: Option<ReferenceCounter<CloneCounter<u32>>> = None;
+            my_value.make_mut_or_insert_with(|| CloneCounter::new(1)).value += 1;
+            assert_eq!(my_value.as_deref().map(|v| v.value), Some(2));
+            let mut my_number: Option<ReferenceCounter<u32>> = None;
+            *my_number.make_mut_or_default() += 1;
+            assert_eq!(my_number.as_deref(), Some(&1));
+
+            // Some and unique, so it is mutated in place.
+            let address = my_value.as_ref().map(ReferenceCounter::as_ptr);
+            my_value.make_mut_or_insert_with(|| panic!("a value should not be inserted if there is one")).value += 1;
+            assert_eq!(address, my_value.as_ref().map(ReferenceCounter::as_ptr));
+            assert_eq!(my_value.as_deref().map(CloneCounter::clones), Some(0), "a unique reference should not be cloned");
+
+            // Some and shared, so it is cloned.
+            let another_value = my_value.clone();
+            my_value.make_mut_or_insert_with(|| panic!("a value should not be inserted if there is one")).value += 1;
+            assert_eq!(my_value.as_deref().map(|v| v.value), Some(4));
+            assert_eq!(another_value.as_deref().map(|v| v.value), Some(3));
+            assert_eq!(my_value.as_deref().map(CloneCounter::clones), Some(1), "a shared reference should be cloned");
+        }
+    };
+}
+
+make_test_option_comb_ext!(test_arc_option_comb_ext, CombArc);
+make_test_option_comb_ext!(test_rc_option_comb_ext, CombRc);
+
+#[cfg(feature = "debug_hooks")]
+macro_rules! make_test_no_clone_scope {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use std::panic::AssertUnwindSafe;
+
+            let mut my_value = ReferenceCounter::new(1u32);
+
+            // Unique, so mutating is fine.
+            crate::no_clone_scope(|| *my_value += 1);
+            assert_eq!(*my_value, 2);
+
+            // Shared, so mutating panics.
+            let another_value = my_value.clone();
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| *my_value += 1)));
+            assert!(result.is_err(), "cloning inside the scope should panic");
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                let _guard = crate::NoCloneGuard::new();
+                ReferenceCounter::make_mut_with(&mut my_value, |v| *v);
+            }));
+            assert!(result.is_err(), "cloning while a guard is alive should panic");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value));
+
+            // Outside of the scope, cloning is fine again.
+            *my_value += 1;
+            assert_eq!(*my_value, 3);
+            assert_eq!(*another_value, 2);
+        }
+    };
+}
+
+#[cfg(feature = "debug_hooks")]
+make_test_no_clone_scope!(test_arc_no_clone_scope, CombArc);
+#[cfg(feature = "debug_hooks")]
+make_test_no_clone_scope!(test_rc_no_clone_scope, CombRc);
+
+#[cfg(feature = "debug_hooks")]
+macro_rules! make_test_no_clone_scope_paths {
+    ($test_name:tt, $t:tt, $pool:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::{$t as ReferenceCounter, $pool as Pool};
+            use std::panic::AssertUnwindSafe;
+
+            let mut my_value = ReferenceCounter::new(1u32);
+            let another_value = my_value.clone();
+            let pool = Pool::new(1);
+
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| ReferenceCounter::take(&mut my_value))));
+            assert!(result.is_err(), "taking from a shared reference inside the scope should panic");
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| ReferenceCounter::replace(&mut my_value, 2))));
+            assert!(result.is_err(), "replacing in a shared reference inside the scope should panic");
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| *pool.make_mut(&mut my_value) += 1)));
+            assert!(result.is_err(), "cloning in a pool inside the scope should panic");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value));
+
+            // Unique, so none of these clone.
+            drop(another_value);
+            crate::no_clone_scope(|| {
+                assert_eq!(ReferenceCounter::take(&mut my_value), 1);
+                assert_eq!(ReferenceCounter::replace(&mut my_value, 2), 0);
+                *pool.make_mut(&mut my_value) += 1;
+            });
+            assert_eq!(*my_value, 3);
+        }
+    };
+}
+
+#[cfg(feature = "debug_hooks")]
+make_test_no_clone_scope_paths!(test_arc_no_clone_scope_paths, CombArc, CombArcPool);
+#[cfg(feature = "debug_hooks")]
+make_test_no_clone_scope_paths!(test_rc_no_clone_scope_paths, CombRc, CombRcPool);
+
+#[cfg(feature = "debug_hooks")]
+#[test]
+fn test_no_clone_scope_borrowed() {
+    use crate::{CombStatic, MaybeComb};
+    use std::panic::AssertUnwindSafe;
+
+    static VALUE: u32 = 1;
+    let mut promoted = CombStatic::from_static(&VALUE);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| *CombStatic::make_mut(&mut promoted) += 1)));
+    assert!(result.is_err(), "promoting a static value inside the scope should panic");
+    assert!(CombStatic::is_static(&promoted));
+
+    let value = 1u32;
+    let mut borrowed = MaybeComb::Borrowed(&value);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| crate::no_clone_scope(|| *MaybeComb::to_mut(&mut borrowed) += 1)));
+    assert!(result.is_err(), "cloning a borrowed value inside the scope should panic");
+    assert!(MaybeComb::is_borrowed(&borrowed));
+}
+
+#[cfg(feature = "debug_hooks")]
+macro_rules! make_test_clone_budget {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use std::panic::AssertUnwindSafe;
+
+            let mut my_value = ReferenceCounter::with_clone_budget(1u32, 2);
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(0));
+            assert_eq!(ReferenceCounter::clones_used(&ReferenceCounter::new(1u32)), None);
+
+            // Unique mutations are free.
+            *my_value += 1;
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(0), "unique mutations should not use the budget");
+
+            // Shared mutations use the budget, which is shared with clones.
+            let mut another_value = my_value.clone();
+            *my_value += 1;
+            assert_eq!(ReferenceCounter::clones_used(&another_value), Some(1), "clones should share the budget");
+            let _snapshot = another_value.clone();
+            assert_eq!(ReferenceCounter::try_make_mut_within_budget(&mut another_value).copied(), Ok(2));
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(2));
+
+            // The budget is exhausted now.
+            let _snapshot = my_value.clone();
+            assert_eq!(ReferenceCounter::try_make_mut_within_budget(&mut my_value), Err(crate::CloneBudgetExceeded { budget: 2 }));
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| *my_value += 1));
+            assert!(result.is_err(), "exceeding the budget should panic");
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(2));
+            assert_eq!(*my_value, 3);
+        }
+    };
+}
+
+#[cfg(feature = "debug_hooks")]
+make_test_clone_budget!(test_arc_clone_budget, CombArc);
+#[cfg(feature = "debug_hooks")]
+make_test_clone_budget!(test_rc_clone_budget, CombRc);
+
+#[cfg(feature = "debug_hooks")]
+macro_rules! make_test_try_make_mut_budget {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use core::cell::Cell;
+            use crate::$t as ReferenceCounter;
+
+            let mut my_value = ReferenceCounter::with_clone_budget(FallibleClone { value: 1, fail: Cell::new(true) }, 1);
+            let another_value = my_value.clone();
+
+            // Cloning fails, so the budget is untouched.
+            assert!(ReferenceCounter::try_make_mut(&mut my_value).is_err());
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(0), "a failed clone should not use the budget");
+
+            // Cloning succeeds, so the budget is used.
+            my_value.fail.set(false);
+            ReferenceCounter::try_make_mut(&mut my_value).expect("cloning should succeed").value = 2;
+            assert_eq!(ReferenceCounter::clones_used(&my_value), Some(1));
+            assert_eq!(another_value.value, 1);
+        }
+    };
+}
+
+#[cfg(feature = "debug_hooks")]
+make_test_try_make_mut_budget!(test_arc_try_make_mut_budget, CombArc);
+#[cfg(feature = "debug_hooks")]
+make_test_try_make_mut_budget!(test_rc_try_make_mut_budget, CombRc);
+
+macro_rules! make_test_cow_policy {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::{CloneOnWrite, PanicOnClone};
+            use std::panic::AssertUnwindSafe;
+
+            // The default policy just clones.
+            let mut my_value: ReferenceCounter<u32, CloneOnWrite> = ReferenceCounter::new(1u32);
+            let another_value = my_value.clone();
+            *my_value += 1;
+            assert_eq!(*my_value, 2);
+            assert_eq!(*another_value, 1);
+
+            // Converting shares the allocation.
+            let mut my_value: ReferenceCounter<u32, PanicOnClone> = ReferenceCounter::with_policy(my_value);
+            *my_value += 1;
+            assert_eq!(*my_value, 3, "unique references should still be mutable");
+
+            let another_value = my_value.clone();
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| *my_value += 1));
+            let message = result.expect_err("mutably borrowing a shared reference should panic");
+            assert!(message.downcast_ref::<std::string::String>().expect("should have a message").contains("u32"), "the message should name the type");
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "the reference should be untouched");
+            drop(another_value);
+            *my_value += 1;
+            assert_eq!(*my_value, 4);
+        }
+    };
+}
+
+make_test_cow_policy!(test_arc_cow_policy, CombArc);
+make_test_cow_policy!(test_rc_cow_policy, CombRc);
+
+macro_rules! make_test_versioned {
+    ($test_name:tt, $t:tt, $versioned:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$versioned as Versioned;
+
+            let mut my_value = Versioned::new(1u32);
+            assert_eq!(Versioned::version(&my_value), 0);
+
+            // Reading and cloning do not change the version.
+            assert_eq!(*my_value, 1);
+            let another_value = my_value.clone();
+            assert_eq!(Versioned::version(&my_value), 0, "reading should not change the version");
+            assert_eq!(Versioned::version(&another_value), 0, "clones should have the same version");
+
+            // Shared mutation changes it.
+            *my_value += 1;
+            assert_eq!(Versioned::version(&my_value), 1, "shared mutations should change the version");
+            assert!(Versioned::has_changed_since(&my_value, 0));
+            assert!(!Versioned::has_changed_since(&another_value, 0), "other references have their own version");
+
+            // Unique mutation changes it too.
+            assert!(ReferenceCounter::is_unique(Versioned::get_comb(&my_value)));
+            *Versioned::make_mut(&mut my_value) += 1;
+            assert_eq!(Versioned::version(&my_value), 2, "unique mutations should change the version");
+            assert_eq!(*Versioned::into_comb(my_value), 3);
+        }
+    };
+}
+
+make_test_versioned!(test_arc_versioned, CombArc, VersionedCombArc);
+make_test_versioned!(test_rc_versioned, CombRc, VersionedCombRc);
+
+macro_rules! make_test_unique {
+    ($test_name:tt, $t:tt, $unique:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$unique as Unique;
+
+            // Unique, so it is moved.
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let mut unique = ReferenceCounter::into_unique(my_value);
+            for _ in 0..10 {
+                unique.value += 1;
+            }
+            assert_eq!(unique.clones(), 0, "a unique reference should be moved");
+
+            // Mutations are visible after converting back.
+            let my_value = Unique::into_shared(unique);
+            assert_eq!(my_value.value, 11, "mutations should be kept");
+
+            // Shared, so it is cloned, and the other reference is untouched.
+            let another_value = my_value.clone();
+            let mut unique = ReferenceCounter::into_unique(my_value);
+            unique.value += 1;
+            assert_eq!(unique.clones(), 1, "a shared reference should be cloned");
+            assert_eq!(another_value.value, 11);
+
+            // Downgrading clones again.
+            let shared = Unique::downgrade_to_shared(&unique);
+            assert_eq!(shared.value, 12);
+            assert_eq!(unique.clones(), 2);
+
+            // Weak references are dissociated, so they cannot share it again.
+            let my_value = Unique::into_shared(unique);
+            let weak = ReferenceCounter::downgrade(&my_value);
+            let mut unique = ReferenceCounter::into_unique(my_value);
+            assert!(weak.upgrade().is_none(), "weak references should be dissociated");
+            unique.value += 1;
+            assert_eq!(unique.clones(), 2, "dissociating weak references should not clone");
+            assert_eq!(Unique::into_inner(unique).value, 13);
+        }
+    };
+}
+
+make_test_unique!(test_arc_unique, CombArc, UniqueCombArc);
+make_test_unique!(test_rc_unique, CombRc, UniqueCombRc);
+
+macro_rules! make_test_by_ptr {
+    ($test_name:tt, $t:tt, $by_ptr:tt) => {
+        #[test]
+        #[allow(clippy::mutable_key_type)] // the hash does not depend on the inner value
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$by_ptr as ByPtr;
+            use std::collections::{BTreeSet, HashSet};
+
+            let mut my_value = ReferenceCounter::new(1u32);
+            let another_value = my_value.clone();
+
+            // Clones share an allocation, so they are one entry.
+            let mut visited = HashSet::new();
+            assert!(visited.insert(ByPtr(my_value.clone())));
+            assert!(!visited.insert(ByPtr(another_value.clone())), "clones should be the same entry");
+
+            // Copy-on-write makes a new allocation, so it is a new entry, even though it is equal.
+            *ReferenceCounter::make_mut(&mut my_value) += 0;
+            assert_eq!(my_value, another_value);
+            assert!(visited.insert(ByPtr(my_value.clone())), "copy-on-write clones should be a different entry");
+            assert_eq!(visited.len(), 2);
+
+            // Ordering is consistent with equality and stable.
+            let a = ByPtr(my_value.clone());
+            let b = ByPtr(another_value.clone());
+            assert_ne!(a.cmp(&b), core::cmp::Ordering::Equal);
+            assert_eq!(a.cmp(&b), a.cmp(&b));
+            assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            let ordered: BTreeSet<_> = alloc::vec![a.clone(), b.clone(), a.clone()].into_iter().collect();
+            assert_eq!(ordered.len(), 2);
+        }
+    };
+}
+
+make_test_by_ptr!(test_arc_by_ptr, CombArc, CombArcByPtr);
+make_test_by_ptr!(test_rc_by_ptr, CombRc, CombRcByPtr);
+
+macro_rules! make_test_project {
+    ($test_name:tt, $t:tt, $r:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$r as Projection;
+
+            #[derive(Clone)]
+            struct Document {
+                title: alloc::string::String,
+                paragraphs: alloc::vec::Vec<alloc::string::String>
+            }
+
+            let document = ReferenceCounter::new(Document {
+                title: "title".into(),
+                paragraphs: alloc::vec!["first".into(), "second".into()]
+            });
+            let title = ReferenceCounter::project(document.clone(), |d| d.title.as_str());
+            let paragraphs = ReferenceCounter::project(document.clone(), |d| d.paragraphs.as_slice());
+
+            // Mutating a clone of the root elsewhere does not affect the projections.
+            let mut another_document = document.clone();
+            another_document.title.push_str(" changed");
+            drop(document);
+            assert_eq!(&*title, "title", "the projection should stay valid");
+            assert_eq!(paragraphs.len(), 2);
+
+            // Clones project from the same root.
+            let another_title = title.clone();
+            assert!(ReferenceCounter::ptr_eq(Projection::root(&title), Projection::root(&another_title)));
+            assert!(ReferenceCounter::ptr_eq(&Projection::into_root(paragraphs), Projection::root(&title)), "the root should be recovered");
+        }
+    };
+}
+
+make_test_project!(test_arc_project, CombArc, CombArcRef);
+make_test_project!(test_rc_project, CombRc, CombRcRef);
+
+macro_rules! make_test_project_mut {
+    ($test_name:tt, $t:tt, $r:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$r as Projection;
+
+            #[derive(Clone)]
+            struct Document {
+                title: CloneCounter<u32>,
+                body: u32
+            }
+
+            // Unique, so it is not cloned.
+            let mut document = ReferenceCounter::new(Document { title: CloneCounter::new(1), body: 2 });
+            let title = ReferenceCounter::project_mut(&mut document, |d| &mut d.title);
+            title.value += 1;
+            title.value += 1;
+            assert_eq!(document.title.clones(), 0, "a unique reference should not be cloned");
+
+            // Shared, so the root is cloned exactly once.
+            let another_document = document.clone();
+            let title = ReferenceCounter::project_mut(&mut document, |d| &mut d.title);
+            title.value += 1;
+            title.value += 1;
+            assert_eq!(document.title.clones(), 1, "a shared reference should be cloned once");
+            assert_eq!(document.title.value, 5);
+            assert_eq!(document.body, 2, "sibling fields should be intact");
+            assert_eq!(another_document.title.value, 3);
+
+            // Read-only projections can be mutated through their root.
+            let mut projection = ReferenceCounter::project(document, |d| &d.body);
+            *ReferenceCounter::project_mut(Projection::root_mut(&mut projection), |d| &mut d.body) += 1;
+            assert_eq!(*projection, 3);
+        }
+    };
+}
+
+make_test_project_mut!(test_arc_project_mut, CombArc, CombArcRef);
+make_test_project_mut!(test_rc_project_mut, CombRc, CombRcRef);
+
+macro_rules! make_test_write_guard {
+    ($test_name:tt, $t:tt, $g:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$g as WriteGuard;
+
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            let another_value = my_value.clone();
+
+            // Reading only, so nothing is cloned.
+            {
+                let guard = ReferenceCounter::write(&mut my_value);
+                assert_eq!(guard.value, 1);
+                assert!(!WriteGuard::was_cloned(&guard));
+            }
+            assert!(ReferenceCounter::ptr_eq(&my_value, &another_value), "reading should not dissociate");
+            assert_eq!(my_value.clones(), 0, "reading should not clone");
+
+            // Writing clones once.
+            {
+                let mut guard = ReferenceCounter::write(&mut my_value);
+                guard.value += 1;
+                guard.value += 1;
+                assert!(WriteGuard::was_cloned(&guard));
+            }
+            assert_eq!(my_value.value, 3);
+            assert_eq!(another_value.value, 1);
+            assert_eq!(my_value.clones(), 1, "writing should clone once");
+
+            // Unique now, so writing does not clone.
+            let mut guard = ReferenceCounter::write(&mut my_value);
+            guard.value += 1;
+            assert!(!WriteGuard::was_cloned(&guard));
+        }
+    };
+}
+
+make_test_write_guard!(test_arc_write_guard, CombArc, CombArcWriteGuard);
+make_test_write_guard!(test_rc_write_guard, CombRc, CombRcWriteGuard);
+
+macro_rules! make_test_freeze {
+    ($test_name:tt, $t:tt, $frozen:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$frozen as Frozen;
+            use std::collections::{BTreeSet, HashMap};
+
+            let my_value = ReferenceCounter::new(alloc::string::String::from("key"));
+            let frozen = ReferenceCounter::freeze(my_value.clone());
+            let another_frozen = frozen.clone();
+            assert!(Frozen::ptr_eq(&frozen, &another_frozen));
+            assert_eq!(*frozen, "key");
+
+            // Thawing round-trips without cloning.
+            let thawed = Frozen::thaw(another_frozen);
+            assert!(ReferenceCounter::ptr_eq(&thawed, &my_value), "thawing should not clone");
+
+            // Frozen references can be used as keys.
+            let mut map = HashMap::new();
+            map.insert(frozen.clone(), 1);
+            assert_eq!(map.get(&ReferenceCounter::freeze(ReferenceCounter::new("key".into()))), Some(&1));
+            let set: BTreeSet<_> = alloc::vec![frozen.clone(), Frozen::from(ReferenceCounter::new("another key".into()))].into_iter().collect();
+            assert_eq!(set.len(), 2);
+        }
+    };
+}
+
+make_test_freeze!(test_arc_freeze, CombArc, FrozenCombArc);
+make_test_freeze!(test_rc_freeze, CombRc, FrozenCombRc);
+
+#[test]
+fn test_comb_static() {
+    use crate::CombStatic;
+
+    static TABLE: [u32; 4] = [1, 2, 4, 8];
+    static DEFAULT_TABLE: CombStatic<[u32; 4]> = CombStatic::from_static(&TABLE);
+
+    // Reading and cloning do not promote.
+    let mut table = DEFAULT_TABLE.clone();
+    assert_eq!(table[3], 8);
+    let another_table = table.clone();
+    assert!(CombStatic::is_static(&table), "reading should not promote");
+    assert!(CombStatic::is_static(&another_table), "cloning should not promote");
+    assert!(core::ptr::eq(&*table, &TABLE), "static values should be read directly");
+
+    // Writing promotes, leaving the static value and other references alone.
+    table[0] = 0;
+    assert!(!CombStatic::is_static(&table), "writing should promote");
+    assert_eq!(*table, [0, 2, 4, 8]);
+    assert_eq!(TABLE, [1, 2, 4, 8]);
+    assert!(CombStatic::is_static(&another_table));
+
+    // Static and allocated values compare by value.
+    assert_eq!(another_table, CombStatic::new([1, 2, 4, 8]));
+    assert_ne!(another_table, table);
+    assert_eq!(*CombStatic::into_comb(another_table), TABLE);
+}
+
+#[test]
+fn test_maybe_comb() {
+    use crate::{CombArc, MaybeComb};
+
+    let value = CloneCounter::new(1u32);
+
+    // Borrowed values are promoted by cloning.
+    let mut borrowed = MaybeComb::from(&value);
+    assert!(MaybeComb::is_borrowed(&borrowed));
+    MaybeComb::to_mut(&mut borrowed).value += 1;
+    assert!(!MaybeComb::is_borrowed(&borrowed), "mutating should promote");
+    assert_eq!(borrowed.value, 2);
+    assert_eq!(value.value, 1);
+    assert_eq!(value.clones(), 1, "promoting should clone once");
+
+    // Shared values use copy-on-write.
+    let shared_value = CombArc::new(CloneCounter::new(3u32));
+    let mut shared = MaybeComb::from(shared_value.clone());
+    MaybeComb::to_mut(&mut shared).value += 1;
+    assert_eq!(shared.value, 4);
+    assert_eq!(shared_value.value, 3);
+
+    // Converting into a shared value clones borrowed values only.
+    let shared = MaybeComb::into_shared(MaybeComb::from(shared_value.clone()));
+    assert!(CombArc::ptr_eq(&shared, &shared_value), "shared values should not be cloned");
+    let borrowed = MaybeComb::into_shared(MaybeComb::Borrowed(&*shared_value));
+    assert!(!CombArc::ptr_eq(&borrowed, &shared_value));
+    assert_eq!(borrowed.value, 3);
+
+    // Comparisons are by value across variants.
+    let number = 5u32;
+    assert_eq!(MaybeComb::from(&number), MaybeComb::from(CombArc::new(5u32)));
+    assert_ne!(MaybeComb::from(&number), MaybeComb::from(CombArc::new(6u32)));
+}
+
+/// Counts allocations on each thread, so tests running in parallel do not affect each other.
+#[cfg(feature = "std")]
+struct CountingAllocator;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static ALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+#[cfg(feature = "std")]
+#[allow(unsafe_code)] // a global allocator cannot be implemented without it
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        unsafe { std::alloc::System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        unsafe { std::alloc::System.dealloc(ptr, layout) }
+    }
+}
+
+#[cfg(feature = "std")]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(feature = "std")]
+fn allocations() -> usize {
+    ALLOCATIONS.with(core::cell::Cell::get)
+}
+
+#[test]
+fn test_comb_small() {
+    use crate::CombSmall;
+
+    // Never cloned, so it stays inline and is mutated in place.
+    let mut my_value = CombSmall::new(CloneCounter::new(1u32));
+    let address = &*my_value as *const CloneCounter<u32>;
+    my_value.value += 1;
+    assert!(CombSmall::is_inline(&my_value), "a value which was never cloned should stay inline");
+    assert_eq!(address, &*my_value as *const CloneCounter<u32>);
+    assert_eq!(my_value.clones(), 0);
+
+    // The first clone copies it once.
+    let another_value = my_value.clone();
+    assert!(!CombSmall::is_inline(&another_value));
+    assert_eq!(my_value.clones(), 1);
+
+    // This and every clone share that copy.
+    #[cfg(feature = "std")]
+    {
+        let yet_another_value = my_value.clone();
+        assert!(!CombSmall::is_inline(&my_value), "a value which was cloned should be shared");
+        assert!(core::ptr::eq(&*my_value, &*another_value), "the original should share with its clones");
+        assert!(core::ptr::eq(&*another_value, &*yet_another_value), "later clones should share");
+        assert_eq!(my_value.clones(), 1, "only the first clone should copy");
+    }
+    let yet_another_value = another_value.clone();
+    assert!(core::ptr::eq(&*another_value, &*yet_another_value), "clones of clones should share");
+
+    // Mutating the original moves it back inline, cloning it only if it is still shared.
+    my_value.value += 1;
+    assert!(CombSmall::is_inline(&my_value), "mutating the original should stop it from sharing");
+    assert_eq!(my_value.value, 3);
+    assert_eq!(another_value.value, 2);
+    let clones = if cfg!(feature = "std") { 2 } else { 1 };
+    assert_eq!(my_value.clones(), clones, "mutating the original should only clone if it is shared");
+    assert_eq!(CombSmall::new(1u32), CombSmall::new(1u32).clone(), "comparisons should be by value");
+
+    // Clones are shared, so mutating them clones.
+    let mut another_value = another_value;
+    another_value.value += 5;
+    assert_eq!(yet_another_value.value, 2);
+    assert_eq!(my_value.clones(), clones + 1);
+    assert_eq!(CombSmall::make_inner(my_value).value, 3);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_comb_small_interior_mutability() {
+    use crate::CombSmall;
+    use core::cell::Cell;
+
+    // Changes made through a clone are kept when the original is mutably borrowed.
+    let mut my_value = CombSmall::new(Cell::new(1u32));
+    let another_value = my_value.clone();
+    another_value.set(2);
+    assert_eq!(my_value.get(), 2, "the original should share with its clones");
+    assert_eq!(*CombSmall::make_mut(&mut my_value).get_mut(), 2, "mutably borrowing should not go back to the old value");
+    my_value.set(3);
+    assert_eq!(another_value.get(), 2, "the original should stop sharing");
+
+    // Unique values are moved back inline without cloning.
+    let mut my_value = CombSmall::new(Cell::new(1u32));
+    my_value.clone().set(2);
+    assert_eq!(*my_value.get_mut(), 2);
+    assert!(CombSmall::is_inline(&my_value));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_comb_small_allocations() {
+    use crate::CombSmall;
+
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<CombSmall<u32>>();
+
+    // Never cloned, so it never allocates.
+    let before = allocations();
+    let mut my_value = CombSmall::new([0u8; 16]);
+    my_value[0] = 1;
+    let mut expected = [0u8; 16];
+    expected[0] = 1;
+    assert!(my_value == CombSmall::new(expected));
+    let value = CombSmall::make_inner(my_value);
+    assert_eq!(allocations(), before, "a value which is never cloned should never allocate");
+
+    // Cloned, so it allocates once.
+    let my_value = CombSmall::new(value);
+    let another_value = my_value.clone();
+    let yet_another_value = my_value.clone();
+    assert_eq!(allocations(), before + 1, "cloning should allocate once");
+    drop((my_value, another_value, yet_another_value));
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[allow(clippy::mutable_key_type)] // the hash does not depend on the budget
+fn test_comb_arc_hashed() {
+    use crate::CombArcHashed;
+    use core::hash::{BuildHasher, BuildHasherDefault};
+    use std::collections::hash_map::{DefaultHasher, RandomState};
+    use std::collections::HashMap;
+
+    let state = RandomState::new();
+    let plain_hash = |value: &alloc::string::String| state.hash_one(value);
+
+    let mut key = CombArcHashed::with_hasher(alloc::string::String::from("key"), state.clone());
+    assert_eq!(CombArcHashed::cached_hash(&key), plain_hash(&key), "the hash should be the value's hash");
+
+    // Mutating discards the cached hash.
+    let old_key = key.clone();
+    key.push_str(" changed");
+    assert_eq!(CombArcHashed::cached_hash(&key), plain_hash(&key), "the hash should be correct after mutating");
+    assert_eq!(CombArcHashed::cached_hash(&old_key), plain_hash(&old_key));
+    assert_ne!(key, old_key);
+    assert_eq!(old_key, CombArcHashed::with_hasher("key".into(), state.clone()), "comparisons should be by value");
+
+    // Lookups work.
+    let mut map = HashMap::new();
+    map.insert(key.clone(), 1);
+    map.insert(old_key.clone(), 2);
+    assert_eq!(map.get(&CombArcHashed::with_hasher("key changed".into(), state.clone())), Some(&1));
+    assert_eq!(map.get(&old_key), Some(&2));
+    assert_eq!(map.get(&CombArcHashed::with_hasher("missing".into(), state.clone())), None);
+
+    // The default hasher is shared by every value in the process.
+    let a = CombArcHashed::new(alloc::string::String::from("key"));
+    let b = CombArcHashed::new(alloc::string::String::from("key"));
+    assert_eq!(CombArcHashed::cached_hash(&a), CombArcHashed::cached_hash(&b), "equal values should hash the same");
+    let mut map = HashMap::new();
+    map.insert(a, 1);
+    assert_eq!(map.get(&b), Some(&1));
+
+    // Comparisons do not depend on the hashers.
+    let a = CombArcHashed::with_hasher(alloc::string::String::from("key"), RandomState::new());
+    let b = CombArcHashed::with_hasher(alloc::string::String::from("key"), RandomState::new());
+    CombArcHashed::cached_hash(&a);
+    CombArcHashed::cached_hash(&b);
+    assert_eq!(a, b, "values hashed with different hashers should still be equal");
+
+    // A fixed-key hasher can be chosen.
+    let fixed = CombArcHashed::with_hasher(alloc::string::String::from("key"), BuildHasherDefault::<DefaultHasher>::default());
+    assert_eq!(CombArcHashed::cached_hash(&fixed), BuildHasherDefault::<DefaultHasher>::default().hash_one("key"));
+}
+
+macro_rules! make_test_history {
+    ($test_name:tt, $t:tt, $history:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$history as History;
+
+            let mut history = History::new(0u32);
+            for i in 1..=3 {
+                history.edit(|value| *value = i);
+            }
+
+            // Undo and redo in order.
+            assert!(history.undo());
+            assert_eq!(**history.current(), 2);
+            assert!(history.undo());
+            assert_eq!(**history.current(), 1);
+            assert!(history.redo());
+            assert_eq!(**history.current(), 2, "redo should undo the last undo");
+
+            // A new edit clears the redo stack.
+            history.edit(|value| *value = 10);
+            assert!(!history.can_redo(), "an edit should clear the redo stack");
+            assert!(!history.redo());
+            assert!(history.undo());
+            assert_eq!(**history.current(), 2);
+            assert!(history.undo());
+            assert!(history.undo());
+            assert_eq!(**history.current(), 0);
+            assert!(!history.undo(), "there should be nothing left to undo");
+
+            // The limit evicts the oldest snapshots.
+            let mut history = History::new(0u32);
+            history.set_limit(2);
+            for i in 1..=5 {
+                history.edit(|value| *value = i);
+            }
+            assert!(history.undo());
+            assert!(history.undo());
+            assert_eq!(**history.current(), 3);
+            assert!(!history.undo(), "the oldest snapshots should be evicted");
+            history.set_limit(0);
+            assert!(history.redo());
+            assert!(!history.can_undo(), "a limit of 0 should keep no snapshots");
+
+            // Clearing keeps the current value and shares nothing.
+            history.clear();
+            assert!(!history.can_redo());
+            assert_eq!(**history.current(), 4);
+            assert!(ReferenceCounter::is_unique(history.current()));
+        }
+    };
+}
+
+make_test_history!(test_arc_history, CombArc, CombHistory);
+make_test_history!(test_rc_history, CombRc, CombRcHistory);
+
+macro_rules! make_test_transaction {
+    ($test_name:tt, $t:tt, $transaction:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$transaction as Transaction;
+
+            let mut my_value = ReferenceCounter::new(alloc::vec![1u32]);
+            let before = my_value.clone();
+
+            // Commit keeps the edits.
+            let mut transaction = ReferenceCounter::transaction(&mut my_value);
+            transaction.push(2);
+            transaction.push(3);
+            assert_eq!(**Transaction::saved(&transaction), [1], "the saved value should not be edited");
+            Transaction::commit(transaction);
+            assert_eq!(*my_value, [1, 2, 3], "commit should keep the edits");
+            assert_eq!(*before, [1]);
+
+            // Dropping rolls back to the same allocation.
+            let committed = my_value.clone();
+            {
+                let mut transaction = ReferenceCounter::transaction(&mut my_value);
+                transaction.clear();
+                transaction.push(4);
+                assert_eq!(*transaction, [4]);
+            }
+            assert!(ReferenceCounter::ptr_eq(&my_value, &committed), "dropping should roll back");
+            Transaction::rollback(ReferenceCounter::transaction(&mut my_value));
+            assert!(ReferenceCounter::ptr_eq(&my_value, &committed), "rollback should roll back");
+
+            // Errors roll back, and successes commit.
+            let result: Result<(), &str> = ReferenceCounter::transact(&mut my_value, |value| {
+                value.push(5);
+                Err("no")
+            });
+            assert_eq!(result, Err("no"));
+            assert!(ReferenceCounter::ptr_eq(&my_value, &committed), "errors should roll back");
+            let result: Result<usize, ()> = ReferenceCounter::transact(&mut my_value, |value| {
+                value.push(5);
+                Ok(value.len())
+            });
+            assert_eq!(result, Ok(4));
+            assert_eq!(*my_value, [1, 2, 3, 5]);
+
+            // Editing the working copy many times only clones once.
+            let mut counted = ReferenceCounter::new(CloneCounter::new(0u32));
+            let mut transaction = ReferenceCounter::transaction(&mut counted);
+            for _ in 0..10 {
+                transaction.value += 1;
+            }
+            Transaction::commit(transaction);
+            assert_eq!(counted.value, 10);
+            assert_eq!(counted.clones(), 1, "the working copy should only be cloned once");
+
+            // Nested transactions on values inside the working copy.
+            let mut nested = ReferenceCounter::new(alloc::vec![ReferenceCounter::new(1u32), ReferenceCounter::new(2u32)]);
+            let mut outer = ReferenceCounter::transaction(&mut nested);
+            let result: Result<(), ()> = ReferenceCounter::transact(&mut outer[0], |value| {
+                *value = 10;
+                Err(())
+            });
+            assert!(result.is_err());
+            let mut inner = ReferenceCounter::transaction(&mut outer[1]);
+            *inner = 20;
+            Transaction::commit(inner);
+            Transaction::commit(outer);
+            assert_eq!(*nested[0], 1, "the failed inner transaction should be rolled back");
+            assert_eq!(*nested[1], 20, "the committed inner transaction should be kept");
+        }
+    };
+}
+
+make_test_transaction!(test_arc_transaction, CombArc, CombArcTransaction);
+make_test_transaction!(test_rc_transaction, CombRc, CombRcTransaction);
+
+macro_rules! make_test_watched {
+    ($test_name:tt, $t:tt, $watched:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$watched as Watched;
+
+            let first = Arc::new(AtomicUsize::new(0));
+            let second = Arc::new(AtomicUsize::new(0));
+            let mut my_value = Watched::new(1u32);
+            let before = my_value.current().clone();
+
+            let first_counter = first.clone();
+            let first_id = my_value.subscribe(move |value| {
+                assert_eq!(**value, 1 + first_counter.fetch_add(1, Ordering::Relaxed) as u32 + 1, "subscribers should see the new value");
+            });
+            let second_counter = second.clone();
+            my_value.subscribe(move |_| {
+                second_counter.fetch_add(1, Ordering::Relaxed);
+            });
+
+            // Reads do not notify.
+            assert_eq!(**my_value.current(), 1);
+            assert_eq!(first.load(Ordering::Relaxed), 0, "reads should not notify");
+
+            // Each publish notifies once.
+            my_value.publish(|value| *value += 1);
+            assert_eq!(first.load(Ordering::Relaxed), 1, "publishing should notify once");
+            assert_eq!(second.load(Ordering::Relaxed), 1);
+            assert!(!ReferenceCounter::ptr_eq(my_value.current(), &before), "a new allocation should be published");
+            my_value.publish(|value| *value += 1);
+            assert_eq!(first.load(Ordering::Relaxed), 2);
+
+            // Unsubscribed callbacks stop.
+            assert!(my_value.unsubscribe(first_id));
+            assert!(!my_value.unsubscribe(first_id), "unsubscribing twice should do nothing");
+            my_value.publish(|value| *value += 1);
+            assert_eq!(first.load(Ordering::Relaxed), 2, "unsubscribed callbacks should not be called");
+            assert_eq!(second.load(Ordering::Relaxed), 3);
+            assert_eq!(my_value.subscriber_count(), 1);
+        }
+    };
+}
+
+make_test_watched!(test_arc_watched, CombArc, CombArcWatched);
+make_test_watched!(test_rc_watched, CombRc, CombRcWatched);
+
+#[test]
+fn test_comb_rc_cell() {
+    use crate::{CombRc, CombRcCell};
+
+    let cell = CombRcCell::new(CombRc::new(alloc::vec![1u32]));
+    let alias = &cell;
+    let handed_out = cell.get();
+    assert!(CombRc::ptr_eq(&handed_out, &alias.get()), "get should clone the handle");
+
+    // Aliases see swaps.
+    let old = cell.replace(CombRc::new(alloc::vec![2]));
+    assert!(CombRc::ptr_eq(&old, &handed_out));
+    assert_eq!(*alias.get(), [2], "aliases should see the new handle");
+    alias.set(CombRc::new(alloc::vec![3]));
+    assert_eq!(*cell.get(), [3]);
+
+    // Updating does not change handles which were handed out.
+    let handed_out = cell.get();
+    alias.update(|value| value.push(4));
+    assert_eq!(*cell.get(), [3, 4]);
+    assert_eq!(*handed_out, [3], "handles which were handed out should not change");
+
+    // Updating a unique handle does not clone.
+    drop(handed_out);
+    let before = CombRc::as_ptr(&cell.get());
+    cell.update(|value| value.push(5));
+    assert_eq!(CombRc::as_ptr(&cell.get()), before, "unique handles should be mutated in place");
+    assert_eq!(*cell.into_inner(), [3, 4, 5]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_comb_rc_cell_set_in_update() {
+    use crate::{CombRc, CombRcCell};
+    use std::panic::AssertUnwindSafe;
+
+    let cell = CombRcCell::new(CombRc::new(alloc::vec![1u32]));
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| cell.update(|value| {
+        value.push(2);
+        cell.set(CombRc::new(alloc::vec![3]));
+    })));
+    assert!(result.is_err(), "setting the cell from its own update closure should panic");
+    assert_eq!(*cell.get(), [1, 2], "the cell should hold the updated handle");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_comb_arc_lock() {
+    use crate::{CombArc, CombArcLock};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let lock = Arc::new(CombArcLock::new(alloc::vec![1u32]));
+    let before = lock.snapshot();
+
+    // Readers are not blocked while a writer is editing.
+    let (started_sender, started) = mpsc::channel();
+    let (finish, finish_receiver) = mpsc::channel::<()>();
+    let writer = {
+        let lock = lock.clone();
+        std::thread::spawn(move || {
+            lock.write(|value| {
+                value.push(2);
+                started_sender.send(()).unwrap();
+                finish_receiver.recv_timeout(Duration::from_secs(10)).is_ok()
+            })
+        })
+    };
+    started.recv().unwrap();
+    assert_eq!(*lock.snapshot(), [1], "readers should see the old value while a write is in progress");
+    finish.send(()).unwrap();
+    assert!(writer.join().unwrap(), "readers should not be blocked by writers");
+
+    // Old snapshots do not change.
+    assert_eq!(*before, [1], "old snapshots should not change");
+    assert_eq!(*lock.snapshot(), [1, 2]);
+
+    // Concurrent writes are not lost.
+    let writers: alloc::vec::Vec<_> = (0..4).map(|_| {
+        let lock = lock.clone();
+        std::thread::spawn(move || {
+            for _ in 0..100 {
+                lock.write(|value| value[0] += 1);
+            }
+        })
+    }).collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(lock.snapshot()[0], 401, "concurrent writes should not be lost");
+
+    // Replacing returns the old value.
+    let old = lock.replace(CombArc::new(alloc::vec![0]));
+    assert_eq!(*old, [401, 2]);
+    assert_eq!(*lock.snapshot(), [0]);
+
+    // Replacing waits for a write in progress, so neither is lost.
+    let (started_sender, started) = mpsc::channel();
+    let (finish, finish_receiver) = mpsc::channel::<()>();
+    let writer = {
+        let lock = lock.clone();
+        std::thread::spawn(move || {
+            lock.write(|value| {
+                value.push(1);
+                started_sender.send(()).unwrap();
+                finish_receiver.recv_timeout(Duration::from_secs(10)).is_ok()
+            })
+        })
+    };
+    started.recv().unwrap();
+    let (replaced_sender, replaced) = mpsc::channel();
+    let replacer = {
+        let lock = lock.clone();
+        std::thread::spawn(move || {
+            let old = lock.replace(CombArc::new(alloc::vec![2]));
+            replaced_sender.send(()).unwrap();
+            old
+        })
+    };
+    assert!(replaced.recv_timeout(Duration::from_millis(100)).is_err(), "replace should wait for the writer");
+    finish.send(()).unwrap();
+    assert!(writer.join().unwrap());
+    assert_eq!(*replacer.join().unwrap(), [0, 1], "replace should return the written value");
+    assert_eq!(*lock.snapshot(), [2]);
+}
+
+macro_rules! make_test_pool {
+    ($test_name:tt, $t:tt, $pool:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$pool as Pool;
+
+            let pool = Pool::new(2);
+
+            // Only unique references are recycled, up to the capacity.
+            let shared = ReferenceCounter::new(alloc::vec::Vec::<u32>::with_capacity(100));
+            let another_shared = shared.clone();
+            assert!(!pool.recycle(shared), "shared references should not be recycled");
+            assert!(pool.recycle(another_shared));
+            assert!(pool.recycle(ReferenceCounter::new(alloc::vec::Vec::with_capacity(100))));
+            assert!(!pool.recycle(ReferenceCounter::new(alloc::vec::Vec::with_capacity(100))), "the pool should be bounded");
+            assert_eq!(pool.len(), 2);
+
+            // Copy-on-write clones reuse the recycled buffers.
+            let mut my_value = ReferenceCounter::new(alloc::vec![1u32, 2, 3]);
+            let snapshot = my_value.clone();
+            pool.make_mut(&mut my_value).push(4);
+            assert_eq!(*my_value, [1, 2, 3, 4]);
+            assert_eq!(*snapshot, [1, 2, 3], "other references should not change");
+            assert!(my_value.capacity() >= 100, "the recycled buffer should be reused");
+            assert_eq!(pool.len(), 1);
+
+            // Unique references are not cloned.
+            let buffer = my_value.as_ptr();
+            pool.make_mut(&mut my_value).push(5);
+            assert_eq!(my_value.as_ptr(), buffer, "unique references should be mutated in place");
+            assert_eq!(pool.len(), 1);
+
+            // Recycled buffers go round.
+            let buffer = my_value.as_ptr();
+            assert!(pool.recycle(my_value));
+            let cloned = pool.clone_in_pool(&alloc::vec![6, 7]);
+            let recycled = pool.clone_in_pool(&alloc::vec![8]);
+            assert_eq!(*cloned, [6, 7]);
+            assert_eq!(*recycled, [8]);
+            assert_eq!(cloned.as_ptr(), buffer, "the most recently recycled buffer should be reused first");
+            assert!(pool.is_empty());
+            assert!(pool.clone_in_pool(&alloc::vec![9]).capacity() < 100, "an empty pool should clone normally");
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_pool!(test_arc_pool, CombArc, CombArcPool);
+make_test_pool!(test_rc_pool, CombRc, CombRcPool);
+
+#[cfg(feature = "std")]
+macro_rules! make_test_pool_allocations {
+    ($test_name:tt, $t:tt, $pool:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$pool as Pool;
+
+            let pool = Pool::new(1);
+            let my_value = ReferenceCounter::new(1u32);
+            let address = ReferenceCounter::as_ptr(&my_value);
+            assert!(pool.recycle(my_value));
+
+            // Recycled allocations are reused.
+            let before = allocations();
+            let my_value = pool.new_in_pool(2);
+            assert_eq!(allocations(), before, "a recycled allocation should be reused");
+            assert_eq!(ReferenceCounter::as_ptr(&my_value), address);
+            assert_eq!(*my_value, 2);
+            assert!(pool.recycle(my_value));
+            let my_value = pool.clone_in_pool(&3);
+            assert_eq!(allocations(), before, "cloning should reuse a recycled allocation");
+            assert_eq!(*my_value, 3);
+
+            // An empty pool allocates normally.
+            let another_value = pool.new_in_pool(4);
+            assert_eq!(allocations(), before + 1);
+            assert_eq!(*another_value, 4);
+
+            // References with weak references are not recycled.
+            let weak = ReferenceCounter::downgrade(&my_value);
+            assert!(!pool.recycle(my_value), "references with weak references should not be recycled");
+            assert!(weak.upgrade().is_none());
+            assert!(pool.is_empty());
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_pool_allocations!(test_arc_pool_allocations, CombArc, CombArcPool);
+#[cfg(feature = "std")]
+make_test_pool_allocations!(test_rc_pool_allocations, CombRc, CombRcPool);
+
+macro_rules! make_test_lazy {
+    ($test_name:tt, $t:tt, $lazy:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$lazy as Lazy;
+
+            let runs = Arc::new(AtomicUsize::new(0));
+            let runs_in_lazy = runs.clone();
+            let lazy = Lazy::new(move || {
+                runs_in_lazy.fetch_add(1, Ordering::Relaxed);
+                alloc::vec![1u32]
+            });
+            let another_lazy = lazy.clone();
+            assert!(Lazy::get(&lazy).is_none());
+            assert_eq!(runs.load(Ordering::Relaxed), 0, "the function should not run until it is needed");
+
+            // The function runs once across clones.
+            assert_eq!(*another_lazy, [1]);
+            assert_eq!(*Lazy::force(&lazy), [1]);
+            assert_eq!(runs.load(Ordering::Relaxed), 1, "the function should run once");
+            assert!(ReferenceCounter::ptr_eq(Lazy::force_comb(&lazy), Lazy::force_comb(&another_lazy)), "clones should share the value");
+
+            // Mutating after forcing is copy-on-write.
+            let mut my_value = Lazy::into_comb(lazy);
+            assert!(ReferenceCounter::is_shared(&my_value));
+            my_value.push(2);
+            assert_eq!(*my_value, [1, 2]);
+            assert_eq!(*another_lazy, [1], "other clones should not change");
+            let last_value = Lazy::into_comb(another_lazy);
+            assert!(ReferenceCounter::is_unique(&last_value), "the last clone should give a unique reference");
+            assert_eq!(runs.load(Ordering::Relaxed), 1);
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+make_test_lazy!(test_arc_lazy, CombArc, CombArcLazy);
+make_test_lazy!(test_rc_lazy, CombRc, CombRcLazy);
+
+#[cfg(feature = "std")]
+#[test]
+fn test_arc_lazy_threads() {
+    use crate::CombArcLazy;
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs_in_lazy = runs.clone();
+    let lazy = CombArcLazy::new(move || {
+        runs_in_lazy.fetch_add(1, Ordering::Relaxed);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        5u32
+    });
+    let threads: alloc::vec::Vec<_> = (0..8).map(|_| {
+        let lazy = lazy.clone();
+        std::thread::spawn(move || *lazy)
+    }).collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 5);
+    }
+    assert_eq!(runs.load(Ordering::Relaxed), 1, "the function should run once across threads");
+}
+
+macro_rules! make_test_ext {
+    ($test_name:tt, $t:tt, $inner:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::prelude::*;
+
+            // No allocation, so the pointers are the same.
+            let inner = $inner::new(alloc::vec![1u32]);
+            let borrowed = inner.as_comb();
+            assert_eq!(ReferenceCounter::as_ptr(&borrowed), $inner::as_ptr(&inner), "as_comb should share the allocation");
+            assert_eq!($inner::strong_count(&inner), 2);
+            let pointer = $inner::as_ptr(&inner);
+            let mut converted = inner.into_comb();
+            assert_eq!(ReferenceCounter::as_ptr(&converted), pointer, "into_comb should not allocate");
+            assert!(ReferenceCounter::ptr_eq(&converted, &borrowed));
+
+            // Still copy-on-write.
+            converted.push(2);
+            assert_eq!(*borrowed, [1]);
+            assert_eq!(*converted, [1, 2]);
+        }
+    };
+}
+
+make_test_ext!(test_arc_ext, CombArc, Arc);
+make_test_ext!(test_rc_ext, CombRc, Rc);
+
+#[derive(Clone)]
+enum ProjectShape {
+    Circle(u32),
+    Square { side: u32 }
+}
+
+impl ProjectShape {
+    fn size(&self) -> &u32 {
+        match self {
+            ProjectShape::Circle(radius) => radius,
+            ProjectShape::Square { side } => side
+        }
+    }
+
+    fn size_mut(&mut self) -> &mut u32 {
+        match self {
+            ProjectShape::Circle(radius) => radius,
+            ProjectShape::Square { side } => side
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ProjectInner {
+    shape: ProjectShape,
+    pair: (u32, alloc::string::String)
+}
+
+#[derive(Clone)]
+struct ProjectOuter {
+    inner: ProjectInner
+}
+
+macro_rules! make_test_comb_project {
+    ($test_name:tt, $t:tt, $ref:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use crate::$ref as Ref;
+            use crate::comb_project;
+
+            let mut my_value = ReferenceCounter::new(ProjectOuter {
+                inner: ProjectInner {
+                    shape: ProjectShape::Circle(3),
+                    pair: (1, "one".into())
+                }
+            });
+
+            // Nested fields and tuple indices.
+            let name = comb_project!(my_value.clone() => .inner.pair.1);
+            assert_eq!(*name, "one");
+            assert!(ReferenceCounter::ptr_eq(Ref::root(&name), &my_value), "projections should share the value");
+            let number = comb_project!(my_value.clone() => .inner.pair.0);
+            assert_eq!(*number, 1);
+
+            // Enums through methods.
+            let size = comb_project!(my_value.clone() => .inner.shape.size());
+            assert_eq!(*size, 3);
+
+            // Mutable projections are copy-on-write.
+            *comb_project!(mut my_value => .inner.pair.0) += 1;
+            comb_project!(mut my_value => .inner.pair.1).push('!');
+            *comb_project!(mut my_value => .inner.shape) = ProjectShape::Square { side: 4 };
+            *comb_project!(mut my_value => .inner.shape.size_mut()) += 1;
+            assert_eq!(my_value.inner.pair.0, 2);
+            assert_eq!(my_value.inner.pair.1, "one!");
+            assert_eq!(*my_value.inner.shape.size(), 5);
+            assert_eq!(*name, "one", "other references should not change");
+            assert_eq!(*size, 3);
+        }
+    };
+}
+
+make_test_comb_project!(test_arc_comb_project, CombArc, CombArcRef);
+make_test_comb_project!(test_rc_comb_project, CombRc, CombRcRef);
+
+macro_rules! make_test_call {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            // Fn closures are called in place.
+            let double = ReferenceCounter::new(|x: u32| x * 2);
+            let another_double = double.clone();
+            assert_eq!(ReferenceCounter::call(&double, 2), 4);
+            assert!(ReferenceCounter::ptr_eq(&double, &another_double), "calling should not clone");
+
+            // Each sharing reference gets its own counter after the first call.
+            let mut count = 0u32;
+            let mut counter = ReferenceCounter::new(move |step: u32| {
+                count += step;
+                count
+            });
+            assert_eq!(ReferenceCounter::call_mut(&mut counter, 1), 1);
+            let mut another_counter = counter.clone();
+            assert_eq!(ReferenceCounter::call_mut(&mut counter, 1), 2, "a shared closure should be cloned with its captures");
+            assert_eq!(ReferenceCounter::call_mut(&mut another_counter, 1), 2, "each reference should have its own captures");
+            assert_eq!(ReferenceCounter::call_mut(&mut another_counter, 1), 3);
+            assert_eq!(ReferenceCounter::call_mut(&mut counter, 1), 3);
+            assert!(!ReferenceCounter::ptr_eq(&counter, &another_counter));
+        }
+    };
+}
+
+make_test_call!(test_arc_call, CombArc);
+make_test_call!(test_rc_call, CombRc);
+
+#[cfg(feature = "fn_traits")]
+macro_rules! make_test_fn_traits {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let add = ReferenceCounter::new(|a: u32, b: u32| a + b);
+            assert_eq!(add(1, 2), 3);
+            assert_eq!([1u32, 2].iter().map(|x| add(*x, 1)).sum::<u32>(), 5);
+
+            // The closure's kind has to be known before it can be called through a reference.
+            fn make_counter() -> impl FnMut() -> u32 + Clone {
+                let mut count = 0u32;
+                move || {
+                    count += 1;
+                    count
+                }
+            }
+            let mut counter = ReferenceCounter::new(make_counter());
+            assert_eq!(counter(), 1);
+            let mut another_counter = counter.clone();
+            assert_eq!(counter(), 2);
+            assert_eq!(another_counter(), 2, "each reference should have its own captures");
+            assert_eq!(counter.clone()(), 3, "calling by value should not change other references");
+            assert_eq!(counter(), 3);
+        }
+    };
+}
+
+#[cfg(feature = "fn_traits")]
+make_test_fn_traits!(test_arc_fn_traits, CombArc);
+#[cfg(feature = "fn_traits")]
+make_test_fn_traits!(test_rc_fn_traits, CombRc);
+
+macro_rules! make_test_hash {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        #[allow(clippy::mutable_key_type)] // the hash does not depend on the budget
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use std::collections::HashSet;
+
+            let mut my_value = ReferenceCounter::new(alloc::string::String::from("a"));
+            let another_value = my_value.clone();
+            let equal_value = ReferenceCounter::new(alloc::string::String::from("a"));
+
+            // Equal values are one entry, whether or not they share an allocation.
+            let mut set = HashSet::new();
+            assert!(set.insert(my_value.clone()));
+            assert!(!set.insert(another_value.clone()), "clones should be the same entry");
+            assert!(!set.insert(equal_value), "equal values should be the same entry");
+
+            // Diverged values are different entries.
+            my_value.push('b');
+            assert!(set.insert(my_value.clone()), "diverged values should be different entries");
+            assert_eq!(set.len(), 2);
+            assert!(set.contains(&ReferenceCounter::new(alloc::string::String::from("ab"))));
+            assert!(set.contains(&another_value));
+        }
+    };
+}
+
+make_test_hash!(test_arc_hash, CombArc);
+make_test_hash!(test_rc_hash, CombRc);
+
+macro_rules! make_test_hasher {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use core::hash::Hasher;
+            use std::collections::hash_map::DefaultHasher;
+
+            let digest = |parts: &[&[u8]]| {
+                let mut hasher = DefaultHasher::new();
+                for part in parts {
+                    hasher.write(part);
+                }
+                hasher.finish()
+            };
+
+            let mut hasher = ReferenceCounter::new(DefaultHasher::new());
+            hasher.write(b"shared");
+            let mut fork = hasher.clone();
+
+            // Writing to a shared hasher forks its state.
+            hasher.write(b"left");
+            fork.write(b"right");
+            assert_eq!(hasher.finish(), digest(&[b"shared", b"left"]));
+            assert_eq!(fork.finish(), digest(&[b"shared", b"right"]), "forks should have independent state");
+            assert!(!ReferenceCounter::ptr_eq(&hasher, &fork));
+
+            // Works with generic code.
+            fn write_all<H: Hasher>(hasher: &mut H) {
+                hasher.write_u32(1);
+                hasher.write_usize(2);
+            }
+            let mut plain = DefaultHasher::new();
+            write_all(&mut plain);
+            let mut comb = ReferenceCounter::new(DefaultHasher::new());
+            write_all(&mut comb);
+            assert_eq!(comb.finish(), plain.finish());
+        }
+    };
+}
+
+make_test_hasher!(test_arc_hasher, CombArc);
+make_test_hasher!(test_rc_hasher, CombRc);
+
+macro_rules! make_test_borrow {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        #[allow(clippy::mutable_key_type)] // the hash does not depend on the budget
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::string::String;
+            use core::borrow::{Borrow, BorrowMut};
+            use std::collections::HashMap;
+
+            fn total_length<B: Borrow<String>>(values: &[B]) -> usize {
+                values.iter().map(|value| value.borrow().len()).sum()
+            }
+            fn shout<B: BorrowMut<String>>(mut value: B) -> B {
+                value.borrow_mut().make_ascii_uppercase();
+                value
+            }
+
+            let my_value = ReferenceCounter::new(String::from("abc"));
+            assert_eq!(total_length(&[my_value.clone(), ReferenceCounter::new(String::from("de"))]), 5);
+
+            // Lookups by the inner value.
+            let mut map = HashMap::new();
+            map.insert(my_value.clone(), 1);
+            assert_eq!(map.get(&String::from("abc")), Some(&1), "lookups by the inner value should work");
+
+            // Mutable borrows are copy-on-write.
+            let shouted = shout(my_value.clone());
+            assert_eq!(*shouted, "ABC");
+            assert_eq!(*my_value, "abc", "other references should not change");
+        }
+    };
+}
+
+make_test_borrow!(test_arc_borrow, CombArc);
+make_test_borrow!(test_rc_borrow, CombRc);
+
+macro_rules! make_test_as_ref {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            fn read(value: impl AsRef<u32>) -> u32 {
+                *value.as_ref()
+            }
+            fn increment(mut value: impl AsMut<u32>) {
+                *value.as_mut() += 1;
+            }
+
+            let mut my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+            assert_eq!(read(&my_value), 1);
+            assert_eq!(read(my_value.clone()), 1);
+
+            // Only shared references are cloned.
+            increment(&mut my_value);
+            assert_eq!(my_value.value, 2);
+            assert_eq!(my_value.clones(), 0, "unique references should not be cloned");
+            let another_value = my_value.clone();
+            increment(&mut my_value);
+            assert_eq!(my_value.value, 3);
+            assert_eq!(another_value.value, 2);
+            assert_eq!(my_value.clones(), 1, "shared references should be cloned");
+        }
+    };
+}
+
+make_test_as_ref!(test_arc_as_ref, CombArc);
+make_test_as_ref!(test_rc_as_ref, CombRc);
+
+macro_rules! make_test_as_ref_forwarding {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::string::String;
+            use std::path::{Path, PathBuf};
+
+            fn length(value: impl AsRef<str>) -> usize {
+                value.as_ref().len()
+            }
+            fn file_name(path: impl AsRef<Path>) -> Option<String> {
+                path.as_ref().file_name().map(|name| name.to_string_lossy().into_owned())
+            }
+
+            let text = ReferenceCounter::new(String::from("hello"));
+            assert_eq!(length(&text), 5);
+            assert_eq!(length(text.clone()), 5);
+
+            let path = ReferenceCounter::new(PathBuf::from("directory/file.txt"));
+            assert_eq!(file_name(&path).as_deref(), Some("file.txt"));
+            assert_eq!(file_name(path), Some(String::from("file.txt")));
+        }
+    };
+}
+
+make_test_as_ref_forwarding!(test_arc_as_ref_forwarding, CombArc);
+make_test_as_ref_forwarding!(test_rc_as_ref_forwarding, CombRc);
+
+macro_rules! make_test_as_ref_inner {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::vec::Vec;
+
+            fn sum(value: impl AsRef<Vec<u32>>) -> u32 {
+                value.as_ref().iter().sum()
+            }
+
+            // Inner values which reference themselves still resolve `AsRef<T>`.
+            let my_value = ReferenceCounter::new(alloc::vec![1u32, 2]);
+            assert_eq!(sum(&my_value), 3);
+            assert_eq!(AsRef::<Vec<u32>>::as_ref(&my_value), &alloc::vec![1, 2]);
+            assert_eq!(sum(my_value), 3);
+
+            // Inherent methods of the inner value are called by path.
+            let optional = ReferenceCounter::new(Some(1u32));
+            assert_eq!(Option::as_ref(&optional), Some(&1));
+        }
+    };
+}
+
+make_test_as_ref_inner!(test_arc_as_ref_inner, CombArc);
+make_test_as_ref_inner!(test_rc_as_ref_inner, CombRc);
+
+macro_rules! make_test_as_mut_forwarding {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            fn invert(mut buffer: impl AsMut<[u8]>) {
+                for byte in buffer.as_mut() {
+                    *byte = !*byte;
+                }
+            }
+
+            let mut my_value = ReferenceCounter::new(alloc::vec![0u8, 0xF0]);
+            let another_value = my_value.clone();
+            invert(&mut my_value);
+            assert_eq!(*my_value, [0xFF, 0x0F]);
+            assert_eq!(*another_value, [0, 0xF0], "other references should not change");
+
+            // Only the first call clones.
+            let mut counted = ReferenceCounter::new(CloneCounter::new(0u32));
+            let _another_counted = counted.clone();
+            for _ in 0..10 {
+                *counted.as_mut() += 1;
+            }
+            assert_eq!(counted.value, 10);
+            assert_eq!(counted.clones(), 1, "only the first call should clone");
+        }
+    };
+}
+
+make_test_as_mut_forwarding!(test_arc_as_mut_forwarding, CombArc);
+make_test_as_mut_forwarding!(test_rc_as_mut_forwarding, CombRc);
+
+macro_rules! make_test_borrow_unsized {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        #[allow(clippy::mutable_key_type)] // the hash does not depend on the budget
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::string::String;
+            use std::collections::{BTreeSet, HashMap};
+
+            // Strings are looked up by str.
+            let mut map = HashMap::new();
+            map.insert(ReferenceCounter::new(String::from("apples")), 1u32);
+            map.insert(ReferenceCounter::new(String::from("pears")), 2);
+            assert_eq!(map.get("apples"), Some(&1), "lookups by str should work");
+            assert_eq!(map.get("pears"), Some(&2));
+            assert_eq!(map.get("plums"), None);
+
+            // Vecs are looked up by slice.
+            let mut map = HashMap::new();
+            map.insert(ReferenceCounter::new(alloc::vec![1u8, 2]), "one two");
+            assert_eq!(map.get(&[1u8, 2][..]), Some(&"one two"), "lookups by slice should work");
+            assert_eq!(map.get(&[1u8][..]), None);
+
+            // Ordered collections too.
+            let set: BTreeSet<_> = alloc::vec![ReferenceCounter::new(String::from("b")), ReferenceCounter::new(String::from("a"))].into_iter().collect();
+            assert!(set.contains("a"));
+            assert!(!set.contains("c"));
+        }
+    };
+}
+
+make_test_borrow_unsized!(test_arc_borrow_unsized, CombArc);
+make_test_borrow_unsized!(test_rc_borrow_unsized, CombRc);
+
+macro_rules! make_test_from_value {
+    ($test_name:tt, $t:tt, $inner:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            fn store(value: impl Into<ReferenceCounter<u32>>) -> ReferenceCounter<u32> {
+                value.into()
+            }
+            let stored = store(1);
+            assert_eq!(*stored, 1);
+            assert!(ReferenceCounter::ptr_eq(&store(stored.clone()), &stored), "references should be moved as is");
+
+            // With an inner reference counter, the type picks the conversion.
+            let inner = $inner::new(2u32);
+            let wrapped: ReferenceCounter<$inner<u32>> = ReferenceCounter::from(inner.clone());
+            assert!($inner::ptr_eq(&*wrapped, &inner), "the value should be moved into a new allocation");
+            assert_eq!($inner::strong_count(&inner), 2);
+            let converted: ReferenceCounter<u32> = ReferenceCounter::from(inner.clone());
+            assert_eq!(ReferenceCounter::as_ptr(&converted), $inner::as_ptr(&inner), "the allocation should be reused");
+        }
+    };
+}
+
+make_test_from_value!(test_arc_from_value, CombArc, Arc);
+make_test_from_value!(test_rc_from_value, CombRc, Rc);
+
+macro_rules! make_test_from_ref {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let original = ReferenceCounter::new(CloneCounter::new(1u32));
+            let from_ref = ReferenceCounter::from_ref(&*original);
+            let converted: ReferenceCounter<CloneCounter<u32>> = ReferenceCounter::from(&*original);
+            assert_eq!(from_ref.value, 1);
+            assert_eq!(converted.value, 1);
+            assert_eq!(original.clones(), 2, "the value should be cloned");
+            assert!(!ReferenceCounter::ptr_eq(&from_ref, &original), "a new allocation should be made");
+            assert!(!ReferenceCounter::ptr_eq(&converted, &original));
+            assert!(ReferenceCounter::is_unique(&original));
+
+            // From a slice.
+            let values = [1u32, 2, 3];
+            let handles: alloc::vec::Vec<ReferenceCounter<u32>> = values.iter().map(ReferenceCounter::from).collect();
+            assert_eq!(handles, [1, 2, 3]);
+        }
+    };
+}
+
+make_test_from_ref!(test_arc_from_ref, CombArc);
+make_test_from_ref!(test_rc_from_ref, CombRc);
+
+macro_rules! make_test_from_box {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let boxed = alloc::boxed::Box::new(CloneCounter::new([7u8; 4096]));
+            let converted: ReferenceCounter<CloneCounter<[u8; 4096]>> = ReferenceCounter::from(boxed);
+            assert!(converted.value.iter().all(|byte| *byte == 7), "the value should survive");
+            assert_eq!(converted.clones(), 0, "the value should be moved");
+            assert!(ReferenceCounter::is_unique(&converted));
+
+            // And back again.
+            let boxed = ReferenceCounter::into_box(converted);
+            assert_eq!(boxed.value[0], 7);
+            assert_eq!(boxed.clones(), 0);
+        }
+    };
+}
+
+make_test_from_box!(test_arc_from_box, CombArc);
+make_test_from_box!(test_rc_from_box, CombRc);
+
+macro_rules! make_test_from_cow {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::borrow::Cow;
+
+            // Owned values are moved.
+            let owned: Cow<'_, CloneCounter<u32>> = Cow::Owned(CloneCounter::new(1u32));
+            let converted: ReferenceCounter<CloneCounter<u32>> = ReferenceCounter::from(owned);
+            assert_eq!(converted.value, 1);
+            assert_eq!(converted.clones(), 0, "owned values should be moved");
+
+            // Borrowed values are cloned once.
+            let original = CloneCounter::new(2u32);
+            let converted: ReferenceCounter<CloneCounter<u32>> = ReferenceCounter::from(Cow::Borrowed(&original));
+            assert_eq!(converted.value, 2);
+            assert_eq!(original.clones(), 1, "borrowed values should be cloned once");
+        }
+    };
+}
+
+make_test_from_cow!(test_arc_from_cow, CombArc);
+make_test_from_cow!(test_rc_from_cow, CombRc);
+
+macro_rules! make_test_to_cow {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::borrow::Cow;
+
+            fn value_of(value: impl Into<Cow<'static, CloneCounter<u32>>>) -> u32 {
+                value.into().value
+            }
+
+            let my_value = ReferenceCounter::new(CloneCounter::new(1u32));
+
+            // Borrowing is zero-copy.
+            let borrowed = ReferenceCounter::to_cow(&my_value);
+            assert!(matches!(borrowed, Cow::Borrowed(_)));
+            assert!(core::ptr::eq(&*borrowed, ReferenceCounter::as_ptr(&my_value)), "borrowing should not copy");
+            let borrowed: Cow<'_, CloneCounter<u32>> = Cow::from(&my_value);
+            assert!(core::ptr::eq(&*borrowed, ReferenceCounter::as_ptr(&my_value)));
+            assert_eq!(my_value.clones(), 0);
+
+            // Owned is only cloned if shared.
+            let another_value = my_value.clone();
+            assert_eq!(value_of(ReferenceCounter::into_cow(another_value)), 1);
+            assert_eq!(my_value.clones(), 1, "shared references should be cloned");
+            let clones = my_value.clones.clone();
+            assert_eq!(value_of(ReferenceCounter::into_cow(my_value)), 1);
+            assert_eq!(clones.load(Ordering::Relaxed), 1, "unique references should be moved");
+        }
+    };
+}
+
+make_test_to_cow!(test_arc_to_cow, CombArc);
+make_test_to_cow!(test_rc_to_cow, CombRc);
+
+macro_rules! make_test_from_str {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let parsed: ReferenceCounter<u32> = "42".parse().expect("should parse");
+            assert_eq!(*parsed, 42);
+
+            let error = "forty-two".parse::<ReferenceCounter<u32>>().expect_err("should not parse");
+            assert_eq!(error, "forty-two".parse::<u32>().expect_err("should not parse"), "the error should be passed through");
+        }
+    };
+}
+
+make_test_from_str!(test_arc_from_str, CombArc);
+make_test_from_str!(test_rc_from_str, CombRc);
+
+macro_rules! make_test_from_iterator {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::collections::BTreeMap;
+
+            let map: ReferenceCounter<BTreeMap<u32, char>> = "abc".chars().zip(1..).map(|(c, i)| (i, c)).collect();
+            assert_eq!(ReferenceCounter::strong_count(&map), 1, "collecting should allocate once");
+            assert_eq!(map.len(), 3);
+            assert_eq!(map.get(&2), Some(&'b'));
+
+            let values: ReferenceCounter<alloc::vec::Vec<u32>> = (1..=3).map(|i| i * 10).collect();
+            assert_eq!(*values, alloc::vec![10, 20, 30]);
+        }
+    };
+}
+
+make_test_from_iterator!(test_arc_from_iterator, CombArc);
+make_test_from_iterator!(test_rc_from_iterator, CombRc);
+
+macro_rules! make_test_extend {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let original = ReferenceCounter::new(CloneCounter::new(alloc::vec![1u32, 2]));
+            let mut extended = original.clone();
+
+            extended.extend(core::iter::empty::<u32>());
+            assert!(ReferenceCounter::ptr_eq(&original, &extended), "extending with nothing should not clone");
+            assert_eq!(original.clones(), 0, "extending with nothing should not clone");
+
+            extended.extend(alloc::vec![3, 4]);
+            assert!(!ReferenceCounter::ptr_eq(&original, &extended), "extending a shared reference should clone");
+            assert_eq!(original.clones(), 1, "extending should clone once");
+            assert_eq!(original.value, alloc::vec![1, 2]);
+            assert_eq!(extended.value, alloc::vec![1, 2, 3, 4]);
+
+            extended.extend(alloc::vec![5]);
+            assert_eq!(original.clones(), 1, "extending a unique reference should not clone");
+            assert_eq!(extended.value, alloc::vec![1, 2, 3, 4, 5]);
+        }
+    };
+}
+
+make_test_extend!(test_arc_extend, CombArc);
+make_test_extend!(test_rc_extend, CombRc);
+
+macro_rules! make_test_into_iterator_ref {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::collections::BTreeMap;
+
+            let original = ReferenceCounter::new(alloc::vec![CloneCounter::new(1u32), CloneCounter::new(2)]);
+            let shared = original.clone();
+            let mut sum = 0;
+            for item in &shared {
+                sum += item.value;
+            }
+            assert_eq!(sum, 3);
+            assert!(ReferenceCounter::ptr_eq(&original, &shared), "iterating should not clone");
+            assert!(original.iter().all(|item| item.clones() == 0), "iterating should not clone the items");
+
+            let map: ReferenceCounter<BTreeMap<u32, char>> = ReferenceCounter::new([(1, 'a'), (2, 'b')].iter().copied().collect());
+            let keys: alloc::vec::Vec<u32> = (&map).into_iter().map(|(key, _)| *key).collect();
+            assert_eq!(keys, alloc::vec![1, 2]);
+        }
+    };
+}
+
+make_test_into_iterator_ref!(test_arc_into_iterator_ref, CombArc);
+make_test_into_iterator_ref!(test_rc_into_iterator_ref, CombRc);
+
+macro_rules! make_test_into_iterator {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let first = CloneCounter::new(1u32);
+            let second = CloneCounter::new(2u32);
+            let unique = ReferenceCounter::new(alloc::vec![first.clone(), second.clone()]);
+            let clones = first.clones() + second.clones();
+            let values: alloc::vec::Vec<u32> = unique.into_iter().map(|item| item.value).collect();
+            assert_eq!(values, alloc::vec![1, 2]);
+            assert_eq!(first.clones() + second.clones(), clones, "iterating a unique reference should not clone");
+
+            let original = ReferenceCounter::new(alloc::vec![first.clone(), second.clone()]);
+            let clones = first.clones() + second.clones();
+            let values: alloc::vec::Vec<u32> = original.clone().into_iter().map(|item| item.value).collect();
+            assert_eq!(values, alloc::vec![1, 2]);
+            assert_eq!(first.clones() + second.clones(), clones + 2, "iterating a shared reference should clone");
+            assert_eq!(original.len(), 2, "the original should be untouched");
+        }
+    };
+}
+
+make_test_into_iterator!(test_arc_into_iterator, CombArc);
+make_test_into_iterator!(test_rc_into_iterator, CombRc);
+
+macro_rules! make_test_comb_iter {
+    ($test_name:tt, $t:tt, $iter:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::{$t as ReferenceCounter, $iter as CombIterator};
+
+            let mut preview = CombIterator::new(alloc::vec![1u32, 2, 3, 4].into_iter());
+            assert_eq!(preview.next(), Some(1));
+
+            let mut real = preview.clone();
+            assert!(ReferenceCounter::ptr_eq(preview.get_comb(), real.get_comb()), "cloning should share the state");
+            assert_eq!(real.size_hint(), (3, Some(3)));
+            assert!(ReferenceCounter::ptr_eq(preview.get_comb(), real.get_comb()), "size_hint should not fork");
+
+            assert_eq!(real.next(), Some(2));
+            assert!(!ReferenceCounter::ptr_eq(preview.get_comb(), real.get_comb()), "advancing should fork");
+            assert_eq!(real.collect::<alloc::vec::Vec<_>>(), alloc::vec![3, 4]);
+            assert_eq!(preview.collect::<alloc::vec::Vec<_>>(), alloc::vec![2, 3, 4], "the other iterator should be independent");
+        }
+    };
+}
+
+make_test_comb_iter!(test_arc_comb_iter, CombArc, CombIter);
+make_test_comb_iter!(test_rc_comb_iter, CombRc, CombRcIter);
+
+macro_rules! make_test_comb_iter_adapters {
+    ($test_name:tt, $t:tt, $iter:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::{$t as ReferenceCounter, $iter as CombIterator};
+
+            let forward = CombIterator::new(alloc::vec![1u32, 2, 3].into_iter());
+            let backward = forward.clone();
+            assert_eq!(backward.len(), 3);
+            assert!(ReferenceCounter::ptr_eq(forward.get_comb(), backward.get_comb()), "len should not fork");
+
+            assert_eq!(backward.rev().collect::<alloc::vec::Vec<_>>(), alloc::vec![3, 2, 1]);
+            assert_eq!(forward.len(), 3, "reversing should fork");
+
+            let mut fused = forward.fuse();
+            assert_eq!(fused.by_ref().count(), 3);
+            assert_eq!(fused.next(), None);
+        }
+    };
+}
+
+make_test_comb_iter_adapters!(test_arc_comb_iter_adapters, CombArc, CombIter);
+make_test_comb_iter_adapters!(test_rc_comb_iter_adapters, CombRc, CombRcIter);
+
+macro_rules! make_test_sum_product {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let amounts = alloc::vec![ReferenceCounter::new(2u32), ReferenceCounter::new(3), ReferenceCounter::new(4)];
+            let total: ReferenceCounter<u32> = amounts.iter().sum();
+            assert_eq!(*total, 9);
+            let product: ReferenceCounter<u32> = amounts.iter().product();
+            assert_eq!(*product, 24);
+            assert_eq!(amounts, alloc::vec![ReferenceCounter::new(2), ReferenceCounter::new(3), ReferenceCounter::new(4)], "the inputs should not change");
+
+            let total: ReferenceCounter<u32> = amounts.iter().cloned().sum();
+            assert_eq!(*total, 9);
+            let product: ReferenceCounter<u32> = amounts.clone().into_iter().product();
+            assert_eq!(*product, 24);
+            assert_eq!(amounts, alloc::vec![ReferenceCounter::new(2), ReferenceCounter::new(3), ReferenceCounter::new(4)], "the inputs should not change");
+        }
+    };
+}
+
+make_test_sum_product!(test_arc_sum_product, CombArc);
+make_test_sum_product!(test_rc_sum_product, CombRc);
+
+macro_rules! make_test_compare_inner_pointer {
+    ($test_name:tt, $t:tt, $pointer:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let pointer = $pointer::new(2u32);
+            let reference: ReferenceCounter<u32> = ReferenceCounter::from(pointer.clone());
+            assert_eq!(reference, pointer, "a reference should equal the pointer it wraps");
+            assert_eq!(pointer, reference, "a reference should equal the pointer it wraps");
+
+            let equal = $pointer::new(2u32);
+            assert_eq!(reference, equal, "a reference should equal an equal pointer");
+            assert!(reference <= equal && equal >= reference);
+
+            let greater = $pointer::new(3u32);
+            assert_ne!(reference, greater, "a reference should not equal an unequal pointer");
+            assert_ne!(greater, reference, "a reference should not equal an unequal pointer");
+            assert!(reference < greater && greater > reference);
+        }
+    };
+}
+
+make_test_compare_inner_pointer!(test_arc_compare_inner_pointer, CombArc, Arc);
+make_test_compare_inner_pointer!(test_rc_compare_inner_pointer, CombRc, Rc);
+
+macro_rules! make_test_compare_reference {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let reference = ReferenceCounter::new(2u32);
+            let expected = 2u32;
+            assert_eq!(reference, &expected);
+            assert_eq!(reference, &&expected);
+            assert_ne!(reference, &3);
+            assert!(reference < &3 && reference > &&1);
+
+            let values = alloc::vec![1u32, 2, 3];
+            assert!(values.iter().any(|value| reference == value), "should compare with items from an iterator");
+            assert_eq!(values.iter().filter(|value| reference == *value).count(), 1);
+            assert_eq!(values.iter().filter(|value| reference == value).count(), 1, "should compare with doubled references");
+        }
+    };
+}
+
+make_test_compare_reference!(test_arc_compare_reference, CombArc);
+make_test_compare_reference!(test_rc_compare_reference, CombRc);
+
+#[test]
+fn test_compare_arc_with_rc() {
+    use crate::{CombArc, CombRc};
+
+    let arc = CombArc::new(alloc::vec![1u32, 2]);
+    let rc = CombRc::new(alloc::vec![1u32, 2]);
+    assert_eq!(arc, rc);
+    assert_eq!(rc, arc);
+
+    let other = CombRc::new(alloc::vec![3u32]);
+    assert_ne!(arc, other);
+    assert_ne!(other, arc);
+
+    assert_eq!(arc, alloc::vec![1, 2], "comparing with the inner value should not be ambiguous");
+    assert_eq!(rc, alloc::vec![1, 2], "comparing with the inner value should not be ambiguous");
+}
+
+struct CountingEq {
+    value: u32,
+    comparisons: Arc<AtomicUsize>
+}
+
+impl CountingEq {
+    fn comparisons(&self) -> usize {
+        self.comparisons.load(Ordering::Relaxed)
+    }
+}
+
+impl Clone for CountingEq {
+    fn clone(&self) -> Self {
+        Self { value: self.value, comparisons: self.comparisons.clone() }
+    }
+}
+
+impl PartialEq for CountingEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.comparisons.fetch_add(1, Ordering::Relaxed);
+        self.value == other.value
+    }
+}
+
+impl Eq for CountingEq {}
+
+impl PartialOrd for CountingEq {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CountingEq {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.comparisons.fetch_add(1, Ordering::Relaxed);
+        self.value.cmp(&other.value)
+    }
+}
+
+macro_rules! make_test_compare_ptr_fast_path {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+
+            let original = ReferenceCounter::new(CountingEq { value: 1, comparisons: Arc::new(AtomicUsize::new(0)) });
+            let shared = original.clone();
+            assert!(original == shared);
+            assert_eq!(original.cmp(&shared), core::cmp::Ordering::Equal);
+            assert_eq!(original.partial_cmp(&shared), Some(core::cmp::Ordering::Equal));
+            assert_eq!(original.comparisons(), 0, "comparing a shared allocation should not compare the values");
+
+            let mut diverged = shared.clone();
+            ReferenceCounter::make_mut(&mut diverged);
+            assert!(original == diverged);
+            assert_eq!(original.comparisons(), 1, "comparing diverged references should compare the values");
+            assert_eq!(original.cmp(&diverged), core::cmp::Ordering::Equal);
+            assert_eq!(original.comparisons(), 2, "comparing diverged references should compare the values");
+        }
+    };
+}
+
+make_test_compare_ptr_fast_path!(test_arc_compare_ptr_fast_path, CombArc);
+make_test_compare_ptr_fast_path!(test_rc_compare_ptr_fast_path, CombRc);
+
+macro_rules! make_test_debug_transparent {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::format;
+
+            let value = alloc::vec![Some(1u32), None];
+            assert_eq!(format!("{:?}", ReferenceCounter::new(value.clone())), format!("{:?}", value));
+            assert_eq!(format!("{:#?}", ReferenceCounter::new(value.clone())), format!("{:#?}", value), "flags should be passed through");
+
+            let nested = ReferenceCounter::new(alloc::vec![ReferenceCounter::new(1u32), ReferenceCounter::new(2)]);
+            assert_eq!(format!("{:?}", nested), "[1, 2]");
+        }
+    };
+}
+
+make_test_debug_transparent!(test_arc_debug_transparent, CombArc);
+make_test_debug_transparent!(test_rc_debug_transparent, CombRc);
+
+#[cfg(feature = "allocator_api")]
+macro_rules! make_test_new_in {
+    ($test_name:tt, $t:tt) => {
+        #[test]
+        fn $test_name() {
+            use crate::$t as ReferenceCounter;
+            use alloc::alloc::Global;
+
+            let mut my_value = ReferenceCounter::new_in(CloneCounter::new(1u32), Global);
+            let another_value = my_value.clone();
+            assert_eq!(my_value.clones(), 0, "cloning the reference should not clone the value");
+
+            my_value.value += 1;
+            assert_eq!(my_value.clones(), 1, "mutating a shared reference should clone");
+            assert_eq!((my_value.value, another_value.value), (2, 1));
+
+            my_value.value += 1;
+            assert_eq!(my_value.clones(), 1, "mutating a unique reference should not clone");
+            let _: &Global = ReferenceCounter::allocator(&my_value);
+        }
+    };
+}
+
+#[cfg(feature = "allocator_api")]
+make_test_new_in!(test_arc_new_in, CombArc);
+#[cfg(feature = "allocator_api")]
+make_test_new_in!(test_rc_new_in, CombRc);
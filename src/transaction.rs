@@ -0,0 +1,202 @@
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A guard for a [`CombArc`] which puts back the previous value unless it is committed.
+///
+/// This is returned by [`CombArc::transaction`]. It keeps a reference to the value from before the
+/// transaction, and dereferences to the value being edited. Since the previous value is kept, the
+/// first mutable borrow always clones the inner value.
+///
+/// Calling [`CombArcTransaction::commit`] keeps the edited value. Dropping the guard without
+/// committing it, including while panicking, rolls back to the previous value, which is the same
+/// allocation as before the transaction.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombArcTransaction};
+///
+/// let mut value = CombArc::new(vec![1u32]);
+///
+/// {
+///     let mut transaction = CombArc::transaction(&mut value);
+///     transaction.push(2);
+/// }
+/// assert_eq!(*value, [1], "dropped transactions are rolled back");
+///
+/// let mut transaction = CombArc::transaction(&mut value);
+/// transaction.push(2);
+/// CombArcTransaction::commit(transaction);
+/// assert_eq!(*value, [1, 2]);
+/// ```
+pub struct CombArcTransaction<'a, T: Clone, P: CowPolicy = CloneOnWrite> {
+    what: &'a mut CombArc<T, P>,
+    saved: CombArc<T, P>,
+    committed: bool
+}
+
+impl<'a, T: Clone, P: CowPolicy> CombArcTransaction<'a, T, P> {
+    /// Constructs a `CombArcTransaction`.
+    ///
+    /// This is the same as [`CombArc::transaction`].
+    #[inline]
+    pub fn new(what: &'a mut CombArc<T, P>) -> CombArcTransaction<'a, T, P> {
+        let saved = what.clone();
+        Self {
+            what,
+            saved,
+            committed: false
+        }
+    }
+
+    /// Keep the edited value.
+    #[inline]
+    pub fn commit(mut what: CombArcTransaction<'a, T, P>) {
+        what.committed = true;
+    }
+
+    /// Put back the previous value.
+    ///
+    /// This is the same as dropping the guard.
+    #[inline]
+    pub fn rollback(what: CombArcTransaction<'a, T, P>) {
+        drop(what);
+    }
+
+    /// Get the value from before the transaction.
+    #[inline]
+    pub fn saved<'b>(what: &'b CombArcTransaction<'a, T, P>) -> &'b CombArc<T, P> {
+        &what.saved
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Drop for CombArcTransaction<'_, T, P> {
+    fn drop(&mut self) {
+        if !self.committed {
+            core::mem::swap(self.what, &mut self.saved);
+        }
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcTransaction<'_, T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcTransaction")
+            .field("inner", &**self)
+            .field("saved", &self.saved)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombArcTransaction<'_, T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.what
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombArcTransaction<'_, T, P> {
+    /// This just calls [`CombArc::make_mut`], which clones the inner value the first time.
+    fn deref_mut(&mut self) -> &mut T {
+        CombArc::make_mut(self.what)
+    }
+}
+
+/// A guard for a [`CombRc`] which puts back the previous value unless it is committed.
+///
+/// This is returned by [`CombRc::transaction`]. It keeps a reference to the value from before the
+/// transaction, and dereferences to the value being edited. Since the previous value is kept, the
+/// first mutable borrow always clones the inner value.
+///
+/// Calling [`CombRcTransaction::commit`] keeps the edited value. Dropping the guard without
+/// committing it, including while panicking, rolls back to the previous value, which is the same
+/// allocation as before the transaction.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcTransaction};
+///
+/// let mut value = CombRc::new(vec![1u32]);
+///
+/// {
+///     let mut transaction = CombRc::transaction(&mut value);
+///     transaction.push(2);
+/// }
+/// assert_eq!(*value, [1], "dropped transactions are rolled back");
+///
+/// let mut transaction = CombRc::transaction(&mut value);
+/// transaction.push(2);
+/// CombRcTransaction::commit(transaction);
+/// assert_eq!(*value, [1, 2]);
+/// ```
+pub struct CombRcTransaction<'a, T: Clone, P: CowPolicy = CloneOnWrite> {
+    what: &'a mut CombRc<T, P>,
+    saved: CombRc<T, P>,
+    committed: bool
+}
+
+impl<'a, T: Clone, P: CowPolicy> CombRcTransaction<'a, T, P> {
+    /// Constructs a `CombRcTransaction`.
+    ///
+    /// This is the same as [`CombRc::transaction`].
+    #[inline]
+    pub fn new(what: &'a mut CombRc<T, P>) -> CombRcTransaction<'a, T, P> {
+        let saved = what.clone();
+        Self {
+            what,
+            saved,
+            committed: false
+        }
+    }
+
+    /// Keep the edited value.
+    #[inline]
+    pub fn commit(mut what: CombRcTransaction<'a, T, P>) {
+        what.committed = true;
+    }
+
+    /// Put back the previous value.
+    ///
+    /// This is the same as dropping the guard.
+    #[inline]
+    pub fn rollback(what: CombRcTransaction<'a, T, P>) {
+        drop(what);
+    }
+
+    /// Get the value from before the transaction.
+    #[inline]
+    pub fn saved<'b>(what: &'b CombRcTransaction<'a, T, P>) -> &'b CombRc<T, P> {
+        &what.saved
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Drop for CombRcTransaction<'_, T, P> {
+    fn drop(&mut self) {
+        if !self.committed {
+            core::mem::swap(self.what, &mut self.saved);
+        }
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcTransaction<'_, T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombRcTransaction")
+            .field("inner", &**self)
+            .field("saved", &self.saved)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombRcTransaction<'_, T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.what
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombRcTransaction<'_, T, P> {
+    /// This just calls [`CombRc::make_mut`], which clones the inner value the first time.
+    fn deref_mut(&mut self) -> &mut T {
+        CombRc::make_mut(self.what)
+    }
+}
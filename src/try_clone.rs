@@ -0,0 +1,16 @@
+/// A fallible version of [`Clone`], used by `try_make_mut`.
+///
+/// This is for types which need to report an error when duplicating them fails, e.g. when a clone
+/// would exceed a quota, rather than panicking or aborting.
+///
+/// There is no blanket implementation for types which implement `Clone`. Since `CombArc` and
+/// `CombRc` require their inner types to implement `Clone`, such an implementation would prevent
+/// any of them from implementing `TryClone` themselves. Types which cannot fail to clone can just
+/// use `make_mut` instead.
+pub trait TryClone: Sized {
+    /// The error returned when cloning fails.
+    type Error;
+
+    /// Attempt to clone the value.
+    fn try_clone(&self) -> Result<Self, Self::Error>;
+}
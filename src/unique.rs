@@ -0,0 +1,229 @@
+use core::fmt::Formatter;
+use core::marker::PhantomData;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A unique [`CombArc`], which can be mutably borrowed without checking whether it is shared.
+///
+/// This holds the inner value directly, so mutably borrowing it is free, and it cannot be cloned.
+/// Converting a `CombArc` into this moves the inner value out if it is unique or clones it
+/// otherwise, and converting it back allocates a new `CombArc`.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArc;
+///
+/// let shared = CombArc::new(vec![0u32; 4]);
+/// let mut unique = CombArc::into_unique(shared.clone());
+/// for i in unique.iter_mut() {
+///     *i += 1;
+/// }
+///
+/// let shared_again = combarc::UniqueCombArc::into_shared(unique);
+/// assert_eq!(*shared_again, [1, 1, 1, 1]);
+/// assert_eq!(*shared, [0, 0, 0, 0]);
+/// ```
+///
+/// It cannot be cloned:
+///
+/// ```rust,compile_fail
+/// use combarc::UniqueCombArc;
+///
+/// let unique = UniqueCombArc::new(1u32);
+/// let another_unique: UniqueCombArc<u32> = unique.clone();
+/// ```
+pub struct UniqueCombArc<T: Clone, P: CowPolicy = CloneOnWrite> {
+    inner: T,
+    policy: PhantomData<P>
+}
+
+impl<T: Clone> UniqueCombArc<T> {
+    /// Constructs a `UniqueCombArc`.
+    #[inline]
+    pub fn new(what: T) -> UniqueCombArc<T> {
+        Self::from_inner(what)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> UniqueCombArc<T, P> {
+    pub(crate) fn from_inner(inner: T) -> UniqueCombArc<T, P> {
+        Self {
+            inner,
+            policy: PhantomData
+        }
+    }
+
+    /// Convert this back into a `CombArc`, moving the inner value into a new allocation.
+    #[inline]
+    pub fn into_shared(what: UniqueCombArc<T, P>) -> CombArc<T, P> {
+        CombArc::with_policy(CombArc::new(what.inner))
+    }
+
+    /// Clone the inner value into a new `CombArc`.
+    ///
+    /// This reference holds the inner value directly, so it cannot share it, and it has to be
+    /// cloned.
+    #[inline]
+    pub fn downgrade_to_shared(what: &UniqueCombArc<T, P>) -> CombArc<T, P> {
+        CombArc::with_policy(CombArc::new(what.inner.clone()))
+    }
+
+    /// Get the inner value.
+    #[inline]
+    pub fn into_inner(what: UniqueCombArc<T, P>) -> T {
+        what.inner
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for UniqueCombArc<T, P> {
+    fn default() -> Self {
+        Self::from_inner(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for UniqueCombArc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UniqueCombArc")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<UniqueCombArc<T, P>> for CombArc<T, P> {
+    fn from(value: UniqueCombArc<T, P>) -> Self {
+        UniqueCombArc::into_shared(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for UniqueCombArc<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        CombArc::into_unique(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for UniqueCombArc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for UniqueCombArc<T, P> {
+    /// This borrows the inner value directly, without checking whether it is shared.
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// A unique [`CombRc`], which can be mutably borrowed without checking whether it is shared.
+///
+/// This holds the inner value directly, so mutably borrowing it is free, and it cannot be cloned.
+/// Converting a `CombRc` into this moves the inner value out if it is unique or clones it
+/// otherwise, and converting it back allocates a new `CombRc`.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRc;
+///
+/// let shared = CombRc::new(vec![0u32; 4]);
+/// let mut unique = CombRc::into_unique(shared.clone());
+/// for i in unique.iter_mut() {
+///     *i += 1;
+/// }
+///
+/// let shared_again = combarc::UniqueCombRc::into_shared(unique);
+/// assert_eq!(*shared_again, [1, 1, 1, 1]);
+/// assert_eq!(*shared, [0, 0, 0, 0]);
+/// ```
+///
+/// It cannot be cloned:
+///
+/// ```rust,compile_fail
+/// use combarc::UniqueCombRc;
+///
+/// let unique = UniqueCombRc::new(1u32);
+/// let another_unique: UniqueCombRc<u32> = unique.clone();
+/// ```
+pub struct UniqueCombRc<T: Clone, P: CowPolicy = CloneOnWrite> {
+    inner: T,
+    policy: PhantomData<P>
+}
+
+impl<T: Clone> UniqueCombRc<T> {
+    /// Constructs a `UniqueCombRc`.
+    #[inline]
+    pub fn new(what: T) -> UniqueCombRc<T> {
+        Self::from_inner(what)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> UniqueCombRc<T, P> {
+    pub(crate) fn from_inner(inner: T) -> UniqueCombRc<T, P> {
+        Self {
+            inner,
+            policy: PhantomData
+        }
+    }
+
+    /// Convert this back into a `CombRc`, moving the inner value into a new allocation.
+    #[inline]
+    pub fn into_shared(what: UniqueCombRc<T, P>) -> CombRc<T, P> {
+        CombRc::with_policy(CombRc::new(what.inner))
+    }
+
+    /// Clone the inner value into a new `CombRc`.
+    ///
+    /// This reference holds the inner value directly, so it cannot share it, and it has to be
+    /// cloned.
+    #[inline]
+    pub fn downgrade_to_shared(what: &UniqueCombRc<T, P>) -> CombRc<T, P> {
+        CombRc::with_policy(CombRc::new(what.inner.clone()))
+    }
+
+    /// Get the inner value.
+    #[inline]
+    pub fn into_inner(what: UniqueCombRc<T, P>) -> T {
+        what.inner
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for UniqueCombRc<T, P> {
+    fn default() -> Self {
+        Self::from_inner(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for UniqueCombRc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("UniqueCombRc")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<UniqueCombRc<T, P>> for CombRc<T, P> {
+    fn from(value: UniqueCombRc<T, P>) -> Self {
+        UniqueCombRc::into_shared(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for UniqueCombRc<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        CombRc::into_unique(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for UniqueCombRc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for UniqueCombRc<T, P> {
+    /// This borrows the inner value directly, without checking whether it is shared.
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
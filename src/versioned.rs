@@ -0,0 +1,264 @@
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A [`CombArc`] which counts how many times it was mutably borrowed.
+///
+/// The version is stored in this reference, not alongside the inner value, so each reference has
+/// its own version. It starts at 0, and is incremented every time a mutable reference to the inner
+/// value is given out, whether or not the inner value had to be cloned. Cloning this reference
+/// clones the version too, and [`Deref`] does not change it.
+///
+/// Since it only counts mutable borrows, a version which did not change means the inner value did
+/// not change, but a version which did change does not necessarily mean it did.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::VersionedCombArc;
+///
+/// let mut config = VersionedCombArc::new(vec![1u32]);
+/// let seen = VersionedCombArc::version(&config);
+/// assert!(!VersionedCombArc::has_changed_since(&config, seen));
+///
+/// config.push(2);
+/// assert!(VersionedCombArc::has_changed_since(&config, seen));
+/// ```
+///
+/// [`Deref`]: core::ops::Deref
+pub struct VersionedCombArc<T: Clone, P: CowPolicy = CloneOnWrite> {
+    inner: CombArc<T, P>,
+    version: u64
+}
+
+impl<T: Clone> VersionedCombArc<T> {
+    /// Constructs a `VersionedCombArc` at version 0.
+    #[inline]
+    pub fn new(what: T) -> VersionedCombArc<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> VersionedCombArc<T, P> {
+    /// Constructs a `VersionedCombArc` at version 0 from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<T, P>) -> VersionedCombArc<T, P> {
+        Self {
+            inner: what,
+            version: 0
+        }
+    }
+
+    /// Get the inner `CombArc`, discarding the version.
+    #[inline]
+    pub fn into_comb(what: VersionedCombArc<T, P>) -> CombArc<T, P> {
+        what.inner
+    }
+
+    /// Get the inner `CombArc` value.
+    #[inline]
+    pub fn get_comb(what: &VersionedCombArc<T, P>) -> &CombArc<T, P> {
+        &what.inner
+    }
+
+    /// Get the number of times this reference was mutably borrowed.
+    #[inline]
+    pub fn version(what: &VersionedCombArc<T, P>) -> u64 {
+        what.version
+    }
+
+    /// Returns `true` if this reference was mutably borrowed since it had the given version.
+    #[inline]
+    pub fn has_changed_since(what: &VersionedCombArc<T, P>, version: u64) -> bool {
+        what.version != version
+    }
+
+    /// Get a mutable reference to the inner value with [`CombArc::make_mut`], incrementing the
+    /// version.
+    #[inline]
+    pub fn make_mut(what: &mut VersionedCombArc<T, P>) -> &mut T {
+        what.version += 1;
+        CombArc::make_mut(&mut what.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Clone for VersionedCombArc<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            version: self.version
+        }
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for VersionedCombArc<T, P> {
+    /// Constructs a `VersionedCombArc` of the default value at version 0.
+    fn default() -> Self {
+        Self::from_comb(CombArc::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for VersionedCombArc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VersionedCombArc")
+            .field("inner", &self.inner)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for VersionedCombArc<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        VersionedCombArc::from_comb(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<VersionedCombArc<T, P>> for CombArc<T, P> {
+    fn from(value: VersionedCombArc<T, P>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for VersionedCombArc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for VersionedCombArc<T, P> {
+    /// This just calls [`VersionedCombArc::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+/// A [`CombRc`] which counts how many times it was mutably borrowed.
+///
+/// The version is stored in this reference, not alongside the inner value, so each reference has
+/// its own version. It starts at 0, and is incremented every time a mutable reference to the inner
+/// value is given out, whether or not the inner value had to be cloned. Cloning this reference
+/// clones the version too, and [`Deref`] does not change it.
+///
+/// Since it only counts mutable borrows, a version which did not change means the inner value did
+/// not change, but a version which did change does not necessarily mean it did.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::VersionedCombRc;
+///
+/// let mut config = VersionedCombRc::new(vec![1u32]);
+/// let seen = VersionedCombRc::version(&config);
+/// assert!(!VersionedCombRc::has_changed_since(&config, seen));
+///
+/// config.push(2);
+/// assert!(VersionedCombRc::has_changed_since(&config, seen));
+/// ```
+///
+/// [`Deref`]: core::ops::Deref
+pub struct VersionedCombRc<T: Clone, P: CowPolicy = CloneOnWrite> {
+    inner: CombRc<T, P>,
+    version: u64
+}
+
+impl<T: Clone> VersionedCombRc<T> {
+    /// Constructs a `VersionedCombRc` at version 0.
+    #[inline]
+    pub fn new(what: T) -> VersionedCombRc<T> {
+        Self::from_comb(CombRc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> VersionedCombRc<T, P> {
+    /// Constructs a `VersionedCombRc` at version 0 from an already created `CombRc`.
+    #[inline]
+    pub fn from_comb(what: CombRc<T, P>) -> VersionedCombRc<T, P> {
+        Self {
+            inner: what,
+            version: 0
+        }
+    }
+
+    /// Get the inner `CombRc`, discarding the version.
+    #[inline]
+    pub fn into_comb(what: VersionedCombRc<T, P>) -> CombRc<T, P> {
+        what.inner
+    }
+
+    /// Get the inner `CombRc` value.
+    #[inline]
+    pub fn get_comb(what: &VersionedCombRc<T, P>) -> &CombRc<T, P> {
+        &what.inner
+    }
+
+    /// Get the number of times this reference was mutably borrowed.
+    #[inline]
+    pub fn version(what: &VersionedCombRc<T, P>) -> u64 {
+        what.version
+    }
+
+    /// Returns `true` if this reference was mutably borrowed since it had the given version.
+    #[inline]
+    pub fn has_changed_since(what: &VersionedCombRc<T, P>, version: u64) -> bool {
+        what.version != version
+    }
+
+    /// Get a mutable reference to the inner value with [`CombRc::make_mut`], incrementing the
+    /// version.
+    #[inline]
+    pub fn make_mut(what: &mut VersionedCombRc<T, P>) -> &mut T {
+        what.version += 1;
+        CombRc::make_mut(&mut what.inner)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> Clone for VersionedCombRc<T, P> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            version: self.version
+        }
+    }
+}
+
+impl<T: Clone + Default, P: CowPolicy> Default for VersionedCombRc<T, P> {
+    /// Constructs a `VersionedCombRc` of the default value at version 0.
+    fn default() -> Self {
+        Self::from_comb(CombRc::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for VersionedCombRc<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("VersionedCombRc")
+            .field("inner", &self.inner)
+            .field("version", &self.version)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for VersionedCombRc<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        VersionedCombRc::from_comb(value)
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<VersionedCombRc<T, P>> for CombRc<T, P> {
+    fn from(value: VersionedCombRc<T, P>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for VersionedCombRc<T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for VersionedCombRc<T, P> {
+    /// This just calls [`VersionedCombRc::make_mut`].
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
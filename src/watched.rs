@@ -0,0 +1,231 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// An ID for unsubscribing from a [`CombArcWatched`] or [`CombRcWatched`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubscriptionId(u64);
+
+type Subscriber<T, P> = Box<dyn Fn(&CombArc<T, P>) + Send + Sync>;
+type RcSubscriber<T, P> = Box<dyn Fn(&CombRc<T, P>)>;
+
+/// A [`CombArc`] which calls subscribers whenever a new value is published.
+///
+/// Reading the current value never calls subscribers. [`CombArcWatched::publish`] edits the value
+/// with [`CombArc::make_mut`], then calls every subscriber with the new value, in the order they
+/// subscribed.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombArcWatched;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::sync::Arc;
+///
+/// let seen = Arc::new(AtomicU32::new(0));
+/// let mut state = CombArcWatched::new(1u32);
+///
+/// let seen_by_subscriber = seen.clone();
+/// state.subscribe(move |value| seen_by_subscriber.store(**value, Ordering::Relaxed));
+///
+/// state.publish(|value| *value += 1);
+/// assert_eq!(seen.load(Ordering::Relaxed), 2);
+/// ```
+pub struct CombArcWatched<T: Clone, P: CowPolicy = CloneOnWrite> {
+    current: CombArc<T, P>,
+    subscribers: Vec<(SubscriptionId, Subscriber<T, P>)>,
+    next_id: u64
+}
+
+impl<T: Clone> CombArcWatched<T> {
+    /// Constructs a `CombArcWatched` with no subscribers.
+    #[inline]
+    pub fn new(what: T) -> CombArcWatched<T> {
+        Self::from_comb(CombArc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombArcWatched<T, P> {
+    /// Constructs a `CombArcWatched` with no subscribers from an already created `CombArc`.
+    #[inline]
+    pub fn from_comb(what: CombArc<T, P>) -> CombArcWatched<T, P> {
+        Self {
+            current: what,
+            subscribers: Vec::new(),
+            next_id: 0
+        }
+    }
+
+    /// Get the current value.
+    #[inline]
+    pub fn current(&self) -> &CombArc<T, P> {
+        &self.current
+    }
+
+    /// Get the current value, dropping all subscribers.
+    #[inline]
+    pub fn into_current(self) -> CombArc<T, P> {
+        self.current
+    }
+
+    /// Add a subscriber, returning an ID for unsubscribing it.
+    pub fn subscribe<F: Fn(&CombArc<T, P>) + Send + Sync + 'static>(&mut self, callback: F) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Remove a subscriber, returning `false` if it was already removed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers.retain(|(subscriber, _)| *subscriber != id);
+        self.subscribers.len() != count
+    }
+
+    /// Get the number of subscribers.
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Edit the current value, then call every subscriber with the new value.
+    pub fn publish<F: FnOnce(&mut T)>(&mut self, f: F) {
+        f(CombArc::make_mut(&mut self.current));
+        for (_, subscriber) in &self.subscribers {
+            subscriber(&self.current);
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombArcWatched<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcWatched<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcWatched")
+            .field("current", &self.current)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombArc<T, P>> for CombArcWatched<T, P> {
+    fn from(value: CombArc<T, P>) -> Self {
+        CombArcWatched::from_comb(value)
+    }
+}
+
+/// A [`CombRc`] which calls subscribers whenever a new value is published.
+///
+/// Reading the current value never calls subscribers. [`CombRcWatched::publish`] edits the value
+/// with [`CombRc::make_mut`], then calls every subscriber with the new value, in the order they
+/// subscribed.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::CombRcWatched;
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+///
+/// let seen = Rc::new(Cell::new(0));
+/// let mut state = CombRcWatched::new(1u32);
+///
+/// let seen_by_subscriber = seen.clone();
+/// state.subscribe(move |value| seen_by_subscriber.set(**value));
+///
+/// state.publish(|value| *value += 1);
+/// assert_eq!(seen.get(), 2);
+/// ```
+pub struct CombRcWatched<T: Clone, P: CowPolicy = CloneOnWrite> {
+    current: CombRc<T, P>,
+    subscribers: Vec<(SubscriptionId, RcSubscriber<T, P>)>,
+    next_id: u64
+}
+
+impl<T: Clone> CombRcWatched<T> {
+    /// Constructs a `CombRcWatched` with no subscribers.
+    #[inline]
+    pub fn new(what: T) -> CombRcWatched<T> {
+        Self::from_comb(CombRc::new(what))
+    }
+}
+
+impl<T: Clone, P: CowPolicy> CombRcWatched<T, P> {
+    /// Constructs a `CombRcWatched` with no subscribers from an already created `CombRc`.
+    #[inline]
+    pub fn from_comb(what: CombRc<T, P>) -> CombRcWatched<T, P> {
+        Self {
+            current: what,
+            subscribers: Vec::new(),
+            next_id: 0
+        }
+    }
+
+    /// Get the current value.
+    #[inline]
+    pub fn current(&self) -> &CombRc<T, P> {
+        &self.current
+    }
+
+    /// Get the current value, dropping all subscribers.
+    #[inline]
+    pub fn into_current(self) -> CombRc<T, P> {
+        self.current
+    }
+
+    /// Add a subscriber, returning an ID for unsubscribing it.
+    pub fn subscribe<F: Fn(&CombRc<T, P>) + 'static>(&mut self, callback: F) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, Box::new(callback)));
+        id
+    }
+
+    /// Remove a subscriber, returning `false` if it was already removed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers.retain(|(subscriber, _)| *subscriber != id);
+        self.subscribers.len() != count
+    }
+
+    /// Get the number of subscribers.
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// Edit the current value, then call every subscriber with the new value.
+    pub fn publish<F: FnOnce(&mut T)>(&mut self, f: F) {
+        f(CombRc::make_mut(&mut self.current));
+        for (_, subscriber) in &self.subscribers {
+            subscriber(&self.current);
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for CombRcWatched<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcWatched<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombRcWatched")
+            .field("current", &self.current)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> From<CombRc<T, P>> for CombRcWatched<T, P> {
+    fn from(value: CombRc<T, P>) -> Self {
+        CombRcWatched::from_comb(value)
+    }
+}
@@ -0,0 +1,233 @@
+use alloc::rc;
+use alloc::sync;
+use core::fmt::Formatter;
+use core::hash::{Hash, Hasher};
+use crate::{CombArc, CombRc};
+
+/// A weak reference to the inner value of a [`CombArc`].
+///
+/// This wraps a [`sync::Weak`] which upgrades to a `CombArc` rather than an [`Arc`], so the
+/// upgraded reference is copy-on-write like any other `CombArc`.
+///
+/// Like [`sync::Weak`], this value is thread-safe.
+///
+/// [`Arc`]: alloc::sync::Arc
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct CombWeak<T: Clone> {
+    inner: sync::Weak<T>
+}
+
+impl<T: Clone> CombWeak<T> {
+    /// Constructs a `CombWeak` that does not point to anything.
+    ///
+    /// Calling [`CombWeak::upgrade`] on it will always return `None`.
+    #[inline]
+    pub fn new() -> CombWeak<T> {
+        Self {
+            inner: sync::Weak::new()
+        }
+    }
+
+    /// Constructs a `CombWeak` from an already created `Weak`.
+    #[inline]
+    pub fn from_weak(what: sync::Weak<T>) -> CombWeak<T> {
+        Self {
+            inner: what
+        }
+    }
+
+    /// Attempt to upgrade to a [`CombArc`].
+    ///
+    /// Returns `None` if there are no more strong references to the inner value, or if they were
+    /// dissociated by a mutable borrow.
+    #[inline]
+    pub fn upgrade(&self) -> Option<CombArc<T>> {
+        self.inner.upgrade().map(CombArc::from_arc)
+    }
+
+    /// Get the number of strong references to the inner value.
+    ///
+    /// Returns 0 if the inner value no longer exists.
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    /// Get the number of weak references to the inner value.
+    ///
+    /// Returns 0 if the inner value no longer exists.
+    #[inline]
+    pub fn weak_count(&self) -> usize {
+        self.inner.weak_count()
+    }
+
+    /// Returns `true` if both `CombWeak`s point to the same allocation.
+    ///
+    /// Two `CombWeak`s that do not point to anything are also considered equal. The [`PartialEq`]
+    /// and [`Hash`] implementations also use this, since the inner value may no longer exist.
+    #[inline]
+    pub fn ptr_eq(&self, other: &CombWeak<T>) -> bool {
+        self.inner.ptr_eq(&other.inner)
+    }
+}
+
+impl<T: Clone> PartialEq for CombWeak<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+    }
+}
+
+impl<T: Clone> Eq for CombWeak<T> {}
+
+impl<T: Clone> Hash for CombWeak<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.as_ptr().hash(state)
+    }
+}
+
+impl<T: Clone> core::fmt::Debug for CombWeak<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let strong_count = self.strong_count();
+        f.debug_struct("CombWeak")
+            .field("live", &(strong_count > 0))
+            .field("strong_count", &strong_count)
+            .field("weak_count", &self.weak_count())
+            .finish()
+    }
+}
+
+impl<T: Clone> Default for CombWeak<T> {
+    /// Constructs a `CombWeak` that does not point to anything.
+    ///
+    /// This is the same as [`CombWeak::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> From<CombWeak<T>> for sync::Weak<T> {
+    fn from(value: CombWeak<T>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone> From<sync::Weak<T>> for CombWeak<T> {
+    fn from(value: sync::Weak<T>) -> Self {
+        CombWeak::from_weak(value)
+    }
+}
+
+/// A weak reference to the inner value of a [`CombRc`].
+///
+/// This wraps a [`rc::Weak`] which upgrades to a `CombRc` rather than an [`Rc`], so the
+/// upgraded reference is copy-on-write like any other `CombRc`.
+///
+/// Like [`rc::Weak`], this is not thread-safe.
+///
+/// [`Rc`]: alloc::rc::Rc
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct CombRcWeak<T: Clone> {
+    inner: rc::Weak<T>
+}
+
+impl<T: Clone> CombRcWeak<T> {
+    /// Constructs a `CombRcWeak` that does not point to anything.
+    ///
+    /// Calling [`CombRcWeak::upgrade`] on it will always return `None`.
+    #[inline]
+    pub fn new() -> CombRcWeak<T> {
+        Self {
+            inner: rc::Weak::new()
+        }
+    }
+
+    /// Constructs a `CombRcWeak` from an already created `Weak`.
+    #[inline]
+    pub fn from_weak(what: rc::Weak<T>) -> CombRcWeak<T> {
+        Self {
+            inner: what
+        }
+    }
+
+    /// Attempt to upgrade to a [`CombRc`].
+    ///
+    /// Returns `None` if there are no more strong references to the inner value, or if they were
+    /// dissociated by a mutable borrow.
+    #[inline]
+    pub fn upgrade(&self) -> Option<CombRc<T>> {
+        self.inner.upgrade().map(CombRc::from_rc)
+    }
+
+    /// Get the number of strong references to the inner value.
+    ///
+    /// Returns 0 if the inner value no longer exists.
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    /// Get the number of weak references to the inner value.
+    ///
+    /// Returns 0 if the inner value no longer exists.
+    #[inline]
+    pub fn weak_count(&self) -> usize {
+        self.inner.weak_count()
+    }
+
+    /// Returns `true` if both `CombRcWeak`s point to the same allocation.
+    ///
+    /// Two `CombRcWeak`s that do not point to anything are also considered equal. The [`PartialEq`]
+    /// and [`Hash`] implementations also use this, since the inner value may no longer exist.
+    #[inline]
+    pub fn ptr_eq(&self, other: &CombRcWeak<T>) -> bool {
+        self.inner.ptr_eq(&other.inner)
+    }
+}
+
+impl<T: Clone> PartialEq for CombRcWeak<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other)
+    }
+}
+
+impl<T: Clone> Eq for CombRcWeak<T> {}
+
+impl<T: Clone> Hash for CombRcWeak<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.as_ptr().hash(state)
+    }
+}
+
+impl<T: Clone> core::fmt::Debug for CombRcWeak<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let strong_count = self.strong_count();
+        f.debug_struct("CombRcWeak")
+            .field("live", &(strong_count > 0))
+            .field("strong_count", &strong_count)
+            .field("weak_count", &self.weak_count())
+            .finish()
+    }
+}
+
+impl<T: Clone> Default for CombRcWeak<T> {
+    /// Constructs a `CombRcWeak` that does not point to anything.
+    ///
+    /// This is the same as [`CombRcWeak::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> From<CombRcWeak<T>> for rc::Weak<T> {
+    fn from(value: CombRcWeak<T>) -> Self {
+        value.inner
+    }
+}
+
+impl<T: Clone> From<rc::Weak<T>> for CombRcWeak<T> {
+    fn from(value: rc::Weak<T>) -> Self {
+        CombRcWeak::from_weak(value)
+    }
+}
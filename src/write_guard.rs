@@ -0,0 +1,150 @@
+use core::fmt::Formatter;
+use crate::{CloneOnWrite, CombArc, CombRc, CowPolicy};
+
+/// A guard for a [`CombArc`] which only clones the inner value once it is actually mutably
+/// borrowed.
+///
+/// This is returned by [`CombArc::write`]. Dereferencing it reads the inner value without cloning,
+/// and mutably dereferencing it calls [`CombArc::make_mut`]. This is useful for passing to code
+/// which may or may not modify the value.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombArc, CombArcWriteGuard};
+///
+/// let mut value = CombArc::new(2u32);
+/// let another_value = value.clone();
+///
+/// {
+///     let mut guard = CombArc::write(&mut value);
+///     if *guard % 2 == 1 {
+///         *guard *= 2;
+///     }
+///     assert!(!CombArcWriteGuard::was_cloned(&guard));
+/// }
+/// assert!(CombArc::ptr_eq(&value, &another_value));
+/// ```
+pub struct CombArcWriteGuard<'a, T: Clone, P: CowPolicy = CloneOnWrite> {
+    what: &'a mut CombArc<T, P>,
+    cloned: bool
+}
+
+impl<'a, T: Clone, P: CowPolicy> CombArcWriteGuard<'a, T, P> {
+    /// Constructs a `CombArcWriteGuard`.
+    ///
+    /// This is the same as [`CombArc::write`].
+    #[inline]
+    pub fn new(what: &'a mut CombArc<T, P>) -> CombArcWriteGuard<'a, T, P> {
+        Self {
+            what,
+            cloned: false
+        }
+    }
+
+    /// Returns `true` if mutably borrowing through this guard cloned the inner value.
+    #[inline]
+    pub fn was_cloned(what: &CombArcWriteGuard<'a, T, P>) -> bool {
+        what.cloned
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombArcWriteGuard<'_, T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombArcWriteGuard")
+            .field("inner", &**self)
+            .field("cloned", &self.cloned)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombArcWriteGuard<'_, T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.what
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombArcWriteGuard<'_, T, P> {
+    /// This just calls [`CombArc::make_mut`], recording whether it had to clone.
+    fn deref_mut(&mut self) -> &mut T {
+        if CombArc::is_shared(self.what) {
+            self.cloned = true;
+        }
+        CombArc::make_mut(self.what)
+    }
+}
+
+/// A guard for a [`CombRc`] which only clones the inner value once it is actually mutably
+/// borrowed.
+///
+/// This is returned by [`CombRc::write`]. Dereferencing it reads the inner value without cloning,
+/// and mutably dereferencing it calls [`CombRc::make_mut`]. This is useful for passing to code
+/// which may or may not modify the value.
+///
+/// # Examples
+///
+/// ```rust
+/// use combarc::{CombRc, CombRcWriteGuard};
+///
+/// let mut value = CombRc::new(2u32);
+/// let another_value = value.clone();
+///
+/// {
+///     let mut guard = CombRc::write(&mut value);
+///     if *guard % 2 == 1 {
+///         *guard *= 2;
+///     }
+///     assert!(!CombRcWriteGuard::was_cloned(&guard));
+/// }
+/// assert!(CombRc::ptr_eq(&value, &another_value));
+/// ```
+pub struct CombRcWriteGuard<'a, T: Clone, P: CowPolicy = CloneOnWrite> {
+    what: &'a mut CombRc<T, P>,
+    cloned: bool
+}
+
+impl<'a, T: Clone, P: CowPolicy> CombRcWriteGuard<'a, T, P> {
+    /// Constructs a `CombRcWriteGuard`.
+    ///
+    /// This is the same as [`CombRc::write`].
+    #[inline]
+    pub fn new(what: &'a mut CombRc<T, P>) -> CombRcWriteGuard<'a, T, P> {
+        Self {
+            what,
+            cloned: false
+        }
+    }
+
+    /// Returns `true` if mutably borrowing through this guard cloned the inner value.
+    #[inline]
+    pub fn was_cloned(what: &CombRcWriteGuard<'a, T, P>) -> bool {
+        what.cloned
+    }
+}
+
+impl<T: Clone + core::fmt::Debug, P: CowPolicy> core::fmt::Debug for CombRcWriteGuard<'_, T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CombRcWriteGuard")
+            .field("inner", &**self)
+            .field("cloned", &self.cloned)
+            .finish()
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::Deref for CombRcWriteGuard<'_, T, P> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.what
+    }
+}
+
+impl<T: Clone, P: CowPolicy> core::ops::DerefMut for CombRcWriteGuard<'_, T, P> {
+    /// This just calls [`CombRc::make_mut`], recording whether it had to clone.
+    fn deref_mut(&mut self) -> &mut T {
+        if CombRc::is_shared(self.what) {
+            self.cloned = true;
+        }
+        CombRc::make_mut(self.what)
+    }
+}